@@ -0,0 +1,2 @@
+-- Track token age so confirmation links can expire
+ALTER TABLE subscription_tokens ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
@@ -0,0 +1,3 @@
+-- Scheduled issues are stored before they are published
+ALTER TABLE newsletter_issues ALTER COLUMN published_at DROP NOT NULL;
+ALTER TABLE newsletter_issues ADD COLUMN scheduled_for timestamptz NULL;
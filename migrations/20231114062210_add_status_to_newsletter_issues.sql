@@ -0,0 +1,14 @@
+-- Issues move from draft to scheduled (optional) to sending to sent
+ALTER TABLE newsletter_issues ADD COLUMN status TEXT NULL;
+UPDATE newsletter_issues
+SET status = CASE
+    WHEN published_at IS NULL THEN 'scheduled'
+    WHEN EXISTS (
+        SELECT 1 FROM issue_delivery_queue
+        WHERE issue_delivery_queue.newsletter_issue_id = newsletter_issues.newsletter_issue_id
+    ) THEN 'sending'
+    ELSE 'sent'
+END;
+ALTER TABLE newsletter_issues ALTER COLUMN status SET NOT NULL;
+ALTER TABLE newsletter_issues ADD CONSTRAINT newsletter_issues_status_check
+    CHECK (status IN ('draft', 'scheduled', 'sending', 'sent'));
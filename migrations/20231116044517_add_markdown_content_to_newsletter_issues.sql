@@ -0,0 +1,2 @@
+-- Markdown source of issues authored in markdown, html and text are rendered from it
+ALTER TABLE newsletter_issues ADD COLUMN markdown_content TEXT NULL;
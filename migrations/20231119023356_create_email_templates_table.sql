@@ -0,0 +1,14 @@
+CREATE TABLE email_templates (
+    email_template_id uuid NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    html_layout TEXT NOT NULL,
+    text_layout TEXT NOT NULL,
+    -- Used for confirmation emails, and preselected when publishing
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (email_template_id)
+);
+CREATE UNIQUE INDEX email_templates_single_default ON email_templates (is_default) WHERE is_default;
+
+ALTER TABLE newsletter_issues ADD COLUMN email_template_id uuid NULL
+    REFERENCES email_templates (email_template_id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+-- Public issues are listed on /archive, the slug is assigned when an issue is published
+ALTER TABLE newsletter_issues ADD COLUMN is_public BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE newsletter_issues ADD COLUMN slug TEXT NULL UNIQUE;
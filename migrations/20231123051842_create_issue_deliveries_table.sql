@@ -0,0 +1,10 @@
+-- One row per completed delivery task, written by the worker as it removes the task from the queue
+CREATE TABLE issue_deliveries (
+    newsletter_issue_id uuid NOT NULL
+    REFERENCES newsletter_issues (newsletter_issue_id),
+    subscriber_email TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('delivered', 'failed', 'skipped')),
+    n_retries SMALLINT NOT NULL,
+    completed_at timestamptz NOT NULL,
+    PRIMARY KEY(newsletter_issue_id, subscriber_email)
+);
@@ -0,0 +1,18 @@
+-- One token per recipient for the open pixel, and one per recipient and tracked link
+CREATE TABLE tracking_tokens (
+    tracking_token TEXT NOT NULL,
+    newsletter_issue_id uuid NOT NULL
+    REFERENCES newsletter_issues (newsletter_issue_id),
+    subscriber_email TEXT NOT NULL,
+    -- NULL for the open pixel
+    url TEXT NULL,
+    PRIMARY KEY (tracking_token)
+);
+CREATE INDEX tracking_tokens_newsletter_issue_id_idx ON tracking_tokens (newsletter_issue_id);
+
+CREATE TABLE tracking_events (
+    tracking_token TEXT NOT NULL
+    REFERENCES tracking_tokens (tracking_token),
+    occurred_at timestamptz NOT NULL
+);
+CREATE INDEX tracking_events_tracking_token_idx ON tracking_events (tracking_token);
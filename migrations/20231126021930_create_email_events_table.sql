@@ -0,0 +1,11 @@
+-- Bounces, spam complaints and deliveries reported by the email provider.
+-- Bounced and complained subscribers get a matching subscriptions.status.
+CREATE TABLE email_events (
+    email_event_id uuid NOT NULL,
+    record_type TEXT NOT NULL,
+    email TEXT NOT NULL,
+    details TEXT NOT NULL,
+    received_at timestamptz NOT NULL,
+    PRIMARY KEY (email_event_id)
+);
+CREATE INDEX email_events_email_idx ON email_events (email);
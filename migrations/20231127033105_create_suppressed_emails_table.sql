@@ -0,0 +1,15 @@
+-- Addresses that must never be emailed again, whatever their subscription status.
+-- Stored lowercased, lookups lowercase the address they check.
+CREATE TABLE suppressed_emails (
+    email TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    source TEXT NOT NULL CHECK (source IN ('webhook', 'admin')),
+    suppressed_at timestamptz NOT NULL,
+    PRIMARY KEY (email)
+);
+
+INSERT INTO suppressed_emails (email, reason, source, suppressed_at)
+SELECT lower(email), status, 'webhook', now()
+FROM subscriptions
+WHERE status IN ('bounced', 'complained')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,40 @@
+CREATE TABLE lists (
+    list_id uuid NOT NULL,
+    -- Identifies the list in subscribe forms and unsubscribe links
+    name TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    -- Used when a subscribe form does not name a list, and preselected when publishing
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (list_id)
+);
+CREATE UNIQUE INDEX lists_single_default ON lists (is_default) WHERE is_default;
+
+INSERT INTO lists (list_id, name, title, is_default)
+VALUES (gen_random_uuid(), 'newsletter', 'Newsletter', true);
+
+-- subscriptions.status stays the state of the address itself: confirmed once
+-- it has been confirmed for any list, bounced or complained for every list
+CREATE TABLE list_subscriptions (
+    list_id uuid NOT NULL REFERENCES lists (list_id),
+    subscriber_id uuid NOT NULL REFERENCES subscriptions (id),
+    status TEXT NOT NULL
+        CHECK (status IN ('pending_confirmation', 'confirmed', 'unsubscribed')),
+    subscribed_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (list_id, subscriber_id)
+);
+INSERT INTO list_subscriptions (list_id, subscriber_id, status, subscribed_at)
+SELECT
+    (SELECT list_id FROM lists WHERE is_default),
+    id,
+    CASE WHEN status IN ('pending_confirmation', 'unsubscribed') THEN status ELSE 'confirmed' END,
+    subscribed_at
+FROM subscriptions;
+
+ALTER TABLE subscription_tokens ADD COLUMN list_id uuid NULL REFERENCES lists (list_id);
+UPDATE subscription_tokens SET list_id = (SELECT list_id FROM lists WHERE is_default);
+ALTER TABLE subscription_tokens ALTER COLUMN list_id SET NOT NULL;
+
+ALTER TABLE newsletter_issues ADD COLUMN list_id uuid NULL REFERENCES lists (list_id);
+UPDATE newsletter_issues SET list_id = (SELECT list_id FROM lists WHERE is_default);
+ALTER TABLE newsletter_issues ALTER COLUMN list_id SET NOT NULL;
@@ -0,0 +1,24 @@
+CREATE TABLE subscriber_tags (
+    subscriber_id uuid NOT NULL REFERENCES subscriptions (id),
+    tag TEXT NOT NULL,
+    tagged_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (subscriber_id, tag)
+);
+CREATE INDEX subscriber_tags_tag ON subscriber_tags (tag);
+
+-- Segments narrow down the confirmed members of the list an issue is
+-- published to, unconfirmed addresses are never sent issues
+CREATE TABLE segments (
+    segment_id uuid NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    -- Members carry every required tag and none of the excluded ones
+    required_tags TEXT[] NOT NULL DEFAULT '{}',
+    excluded_tags TEXT[] NOT NULL DEFAULT '{}',
+    -- Compared against the time members joined the list
+    subscribed_after timestamptz NULL,
+    subscribed_before timestamptz NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (segment_id)
+);
+
+ALTER TABLE newsletter_issues ADD COLUMN segment_id uuid NULL REFERENCES segments (segment_id);
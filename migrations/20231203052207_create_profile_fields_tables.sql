@@ -0,0 +1,25 @@
+CREATE TABLE profile_fields (
+    profile_field_id uuid NOT NULL,
+    -- Name of the subscribe form field, and of the {{ profile.<key> }} merge tag
+    key TEXT NOT NULL UNIQUE,
+    label TEXT NOT NULL,
+    field_type TEXT NOT NULL
+        CHECK (field_type IN ('string', 'number', 'boolean', 'date', 'enum')),
+    -- Allowed values of enum fields
+    options TEXT[] NOT NULL DEFAULT '{}',
+    is_required BOOLEAN NOT NULL DEFAULT false,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (profile_field_id)
+);
+
+-- Values are stored in the canonical text form of their field type
+CREATE TABLE subscriber_profile_values (
+    subscriber_id uuid NOT NULL REFERENCES subscriptions (id),
+    profile_field_id uuid NOT NULL REFERENCES profile_fields (profile_field_id) ON DELETE CASCADE,
+    value TEXT NOT NULL,
+    PRIMARY KEY (subscriber_id, profile_field_id)
+);
+
+-- Members have exactly this value for the field
+ALTER TABLE segments ADD COLUMN profile_field_key TEXT NULL;
+ALTER TABLE segments ADD COLUMN profile_field_value TEXT NULL;
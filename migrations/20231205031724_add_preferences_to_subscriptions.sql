@@ -0,0 +1,9 @@
+-- Issues published before this moment are not sent to the subscriber
+ALTER TABLE subscriptions ADD COLUMN paused_until timestamptz NULL;
+
+-- A token either confirms a list subscription or a change of address
+-- requested from the preference center, never both
+ALTER TABLE subscription_tokens ALTER COLUMN list_id DROP NOT NULL;
+ALTER TABLE subscription_tokens ADD COLUMN new_email TEXT NULL;
+ALTER TABLE subscription_tokens ADD CONSTRAINT subscription_tokens_confirms_one_thing
+    CHECK ((list_id IS NULL) <> (new_email IS NULL));
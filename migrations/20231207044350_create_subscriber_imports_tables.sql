@@ -0,0 +1,26 @@
+-- Confirmation emails sent by the worker instead of the request that created the token
+CREATE TABLE confirmation_email_queue (
+    subscription_token TEXT NOT NULL
+        REFERENCES subscription_tokens (subscription_token) ON DELETE CASCADE,
+    n_retries SMALLINT NOT NULL DEFAULT 0,
+    execute_after timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (subscription_token)
+);
+
+CREATE TABLE subscriber_imports (
+    import_id uuid NOT NULL,
+    file_name TEXT NOT NULL,
+    n_rows INTEGER NOT NULL,
+    n_imported INTEGER NOT NULL,
+    imported_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (import_id)
+);
+
+-- Every row that was not imported, and why
+CREATE TABLE subscriber_import_errors (
+    import_id uuid NOT NULL REFERENCES subscriber_imports (import_id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL,
+    email TEXT NOT NULL,
+    error TEXT NOT NULL,
+    PRIMARY KEY (import_id, row_number)
+);
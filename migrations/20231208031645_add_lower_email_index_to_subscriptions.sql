@@ -0,0 +1,3 @@
+-- Email providers and imported files do not keep the case an address was
+-- signed up with, so lookups by address compare lower(email)
+CREATE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email));
@@ -0,0 +1,4 @@
+-- Segments may also pick the members who have not confirmed the list yet,
+-- unsubscribed, bounced and complained addresses are never sent issues
+ALTER TABLE segments ADD COLUMN subscriber_status TEXT NOT NULL DEFAULT 'confirmed'
+    CHECK (subscriber_status IN ('confirmed', 'pending_confirmation'));
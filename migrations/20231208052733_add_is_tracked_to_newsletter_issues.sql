@@ -0,0 +1,3 @@
+-- Opens and clicks are only tracked for issues the author opted in at
+-- publish time, and only while tracking is enabled in the settings
+ALTER TABLE newsletter_issues ADD COLUMN is_tracked BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,162 @@
+use crate::domain::MergeTags;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Archived issues are not addressed to anyone, merge tags get generic values
+pub const ARCHIVE_MERGE_TAGS: MergeTags<'static> = MergeTags {
+    name: "reader",
+    email: "",
+    unsubscribe_url: "",
+    profile: &[],
+};
+
+// published_at is stored as text, it is cast back when read
+pub struct ArchivedIssue {
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub html_content: String,
+    pub published_at: DateTime<Utc>,
+}
+
+// Issues being delivered are listed too, so the "view in browser" link
+// works as soon as the first email lands. Most recent first, all of them
+// when there is no limit.
+#[tracing::instrument(name = "Get archived issues", skip(pool))]
+pub async fn get_archived_issues(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<ArchivedIssue>, sqlx::Error> {
+    sqlx::query_as!(
+        ArchivedIssue,
+        r#"
+        SELECT
+        newsletter_issue_id,
+        title,
+        slug as "slug!",
+        html_content,
+        published_at::timestamptz as "published_at!"
+        FROM newsletter_issues
+        WHERE
+        is_public AND
+        slug IS NOT NULL AND
+        published_at IS NOT NULL AND
+        status IN ('sending', 'sent')
+        ORDER BY published_at::timestamptz DESC
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Get archived issue", skip(pool))]
+pub async fn get_archived_issue(
+    pool: &PgPool,
+    slug: &str,
+) -> Result<Option<ArchivedIssue>, sqlx::Error> {
+    sqlx::query_as!(
+        ArchivedIssue,
+        r#"
+        SELECT
+        newsletter_issue_id,
+        title,
+        slug as "slug!",
+        html_content,
+        published_at::timestamptz as "published_at!"
+        FROM newsletter_issues
+        WHERE
+        slug = $1 AND
+        is_public AND
+        published_at IS NOT NULL AND
+        status IN ('sending', 'sent')
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub fn archive_url(base_url: &str, slug: &str) -> String {
+    format!("{}/archive/{}", base_url, slug)
+}
+
+// Every published issue gets a slug, so flipping the public flag later
+// would not need one to be made up after the fact
+#[tracing::instrument(skip_all)]
+pub async fn assign_archive_slug(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let issue = sqlx::query!(
+        "SELECT title, slug FROM newsletter_issues WHERE newsletter_issue_id = $1",
+        newsletter_issue_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    if issue.slug.is_some() {
+        return Ok(());
+    }
+
+    let mut slug = slugify(&ARCHIVE_MERGE_TAGS.render_text(&issue.title));
+    let taken = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM newsletter_issues WHERE slug = $1) as "taken!""#,
+        slug
+    )
+    .fetch_one(&mut *transaction)
+    .await?
+    .taken;
+    if taken {
+        slug = format!("{}-{}", slug, &newsletter_issue_id.simple().to_string()[..8]);
+    }
+    sqlx::query!(
+        "UPDATE newsletter_issues SET slug = $2 WHERE newsletter_issue_id = $1",
+        newsletter_issue_id,
+        slug
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
+
+// Lowercase ASCII letters and digits, anything else collapses into a single dash
+fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "issue".into()
+    } else {
+        slug.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slugify;
+
+    #[test]
+    fn titles_are_lowercased_and_dashed() {
+        assert_eq!(slugify("November News"), "november-news");
+    }
+
+    #[test]
+    fn punctuation_collapses_into_a_single_dash() {
+        assert_eq!(slugify("  Rust 1.74 -- what's new?! "), "rust-1-74-what-s-new");
+    }
+
+    #[test]
+    fn titles_without_ascii_letters_get_a_placeholder() {
+        assert_eq!(slugify("¡¿!?"), "issue");
+        assert_eq!(slugify(""), "issue");
+    }
+}
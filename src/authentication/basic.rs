@@ -0,0 +1,91 @@
+use super::Credentials;
+
+use anyhow::Context;
+use axum::http::HeaderMap;
+use base64::Engine;
+use secrecy::{ExposeSecret, Secret};
+
+pub fn basic_authentication(headers: &HeaderMap) -> Result<Credentials, anyhow::Error> {
+    let header_value = headers
+        .get("Authorization")
+        .context("The 'Authorization' header was missing")?
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")?;
+    let base64encoded_segment = header_value
+        .strip_prefix("Basic ")
+        .context("The authorization scheme was not 'Basic'.")?;
+    let decoded_bytes = base64::engine::general_purpose::STANDARD
+        .decode(base64encoded_segment)
+        .context("Failed to base64-decode 'Basic' credentials.")?;
+    let decoded_credentials = String::from_utf8(decoded_bytes)
+        .context("The decoded credential string is not valid UTF8.")?;
+
+    let (username, password) = decoded_credentials
+        .split_once(':')
+        .context("A password must be provided in 'Basic' auth.")?;
+    Ok(Credentials {
+        username: username.to_string(),
+        password: Secret::new(password.to_string()),
+    })
+}
+
+// For secrets kept in configuration rather than hashed in the database,
+// compared without returning early so timing does not leak a prefix
+pub fn credentials_match(expected: &Credentials, given: &Credentials) -> bool {
+    let username_matches = constant_time_eq(expected.username.as_bytes(), given.username.as_bytes());
+    let password_matches = constant_time_eq(
+        expected.password.expose_secret().as_bytes(),
+        given.password.expose_secret().as_bytes(),
+    );
+    username_matches & password_matches
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{basic_authentication, credentials_match};
+    use crate::authentication::Credentials;
+    use axum::http::{HeaderMap, HeaderValue};
+    use secrecy::{ExposeSecret, Secret};
+
+    fn credentials(username: &str, password: &str) -> Credentials {
+        Credentials {
+            username: username.into(),
+            password: Secret::new(password.into()),
+        }
+    }
+
+    #[test]
+    fn basic_credentials_are_decoded() {
+        let mut headers = HeaderMap::new();
+        // postmark:s3cr:et
+        headers.insert("Authorization", HeaderValue::from_static("Basic cG9zdG1hcms6czNjcjpldA=="));
+
+        let credentials = basic_authentication(&headers).unwrap();
+
+        assert_eq!(credentials.username, "postmark");
+        assert_eq!(credentials.password.expose_secret(), "s3cr:et");
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", HeaderValue::from_static("Bearer cG9zdG1hcms6c2VjcmV0"));
+
+        assert!(basic_authentication(&headers).is_err());
+        assert!(basic_authentication(&HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn credentials_must_match_exactly() {
+        let expected = credentials("postmark", "secret");
+
+        assert!(credentials_match(&expected, &credentials("postmark", "secret")));
+        assert!(!credentials_match(&expected, &credentials("postmark", "secre")));
+        assert!(!credentials_match(&expected, &credentials("postmark", "secrex")));
+        assert!(!credentials_match(&expected, &credentials("other", "secret")));
+    }
+}
@@ -1,7 +1,9 @@
+use crate::configuration::WebhookSettings;
 use crate::session_state::TypedSession;
+use super::{basic_authentication, credentials_match};
 
 use axum::{
-    http::Request,
+    http::{header::{self, HeaderValue}, Request, StatusCode},
     middleware::Next,
     response::{Response, IntoResponse},
 };
@@ -44,4 +46,26 @@ pub async fn reject_anonymous_users<B>(
             (flash, axum::response::Redirect::to("/login")).into_response()
         }
     }
+}
+
+// Runs before the payload is parsed, so unauthenticated callers learn nothing about it
+pub async fn reject_unauthenticated_webhooks<B>(
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let webhooks = request.extensions().get::<WebhookSettings>();
+    let authenticated = match (webhooks, basic_authentication(request.headers())) {
+        (Some(webhooks), Ok(credentials)) => credentials_match(&webhooks.credentials(), &credentials),
+        _ => false,
+    };
+    if authenticated {
+        next.run(request).await
+    } else {
+        let mut response = StatusCode::UNAUTHORIZED.into_response();
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static(r#"Basic realm="webhooks""#),
+        );
+        response
+    }
 }
\ No newline at end of file
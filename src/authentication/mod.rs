@@ -1,11 +1,14 @@
+mod basic;
 mod middleware;
 mod password;
 
+pub use basic::{basic_authentication, credentials_match};
 pub use password::{
     change_password, validate_credentials,
     AuthError, Credentials
 };
 pub use middleware::{
     reject_anonymous_users,
+    reject_unauthenticated_webhooks,
     UserId
 };
\ No newline at end of file
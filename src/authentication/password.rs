@@ -40,7 +40,7 @@ pub async fn validate_credentials(
         .to_string()
     );
     if let Some((stored_user_id, stored_password_hash)) =
-        get_stored_credentials(&credentials.username, &pool)
+        get_stored_credentials(&credentials.username, pool)
             .await?
     {
         user_id = Some(stored_user_id);
@@ -72,7 +72,7 @@ fn verify_password_hash(
 ) -> Result<(), AuthError> {
     tracing::error!("expected: {}", &expected_password_hash.expose_secret());
     let expected_password_hash = PasswordHash::new(
-        &expected_password_hash.expose_secret()
+        expected_password_hash.expose_secret()
     );
     tracing::error!("password hash result: {:?}", &expected_password_hash);
 
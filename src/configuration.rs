@@ -2,8 +2,13 @@ use secrecy::{Secret, ExposeSecret};
 use serde_aux::field_attributes::deserialize_number_from_string;
 use sqlx::{postgres::{PgConnectOptions, PgSslMode}, ConnectOptions};
 
+use crate::authentication::Credentials;
 use crate::domain::SubscriberEmail;
-use crate::email_client::EmailClient;
+use crate::email_client::{
+    EmailTransport, FileTransport, PostmarkTransport, RateLimitedTransport, SmtpTransport,
+};
+
+use std::sync::Arc;
 
 #[derive(Clone, serde::Deserialize)]
 pub struct Settings {
@@ -11,6 +16,8 @@ pub struct Settings {
     pub application: ApplicationSettings,
     pub email_client: EmailClientSettings,
     pub redis_uri: Secret<String>,
+    pub worker: WorkerSettings,
+    pub webhooks: WebhookSettings,
 }
 
 #[derive(Clone, serde::Deserialize)]
@@ -20,6 +27,36 @@ pub struct ApplicationSettings {
     pub host: String,
     pub base_url: String,
     pub hmac_secret: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub confirmation_token_ttl_hours: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub shutdown_timeout_seconds: u64,
+    pub tracking_enabled: bool,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct WorkerSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_seconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub concurrency: usize,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub scheduler_interval_seconds: u64,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct WebhookSettings {
+    pub username: String,
+    pub password: Secret<String>,
+}
+
+impl WebhookSettings {
+    pub fn credentials(&self) -> Credentials {
+        Credentials {
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
 }
 
 #[derive(Clone, serde::Deserialize)]
@@ -35,10 +72,35 @@ pub struct DatabaseSettings {
 
 #[derive(Clone, serde::Deserialize)]
 pub struct EmailClientSettings {
+    #[serde(default)]
+    pub transport: EmailTransportKind,
     pub base_url: String,
     pub sender_email: String,
     pub authorisation_token: Secret<String>,
     pub timeout_milliseconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_emails_per_second: u32,
+    pub smtp: Option<SmtpSettings>,
+    pub outbox_directory: Option<String>,
+}
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransportKind {
+    #[default]
+    Postmark,
+    Smtp,
+    File,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    pub starttls: bool,
+    pub username: Option<String>,
+    pub password: Option<Secret<String>>,
 }
 
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
@@ -101,6 +163,26 @@ impl TryFrom<String> for Environment {
     }
 }
 
+impl ApplicationSettings {
+    pub fn confirmation_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.confirmation_token_ttl_hours as i64)
+    }
+
+    pub fn shutdown_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.shutdown_timeout_seconds)
+    }
+}
+
+impl WorkerSettings {
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_interval_seconds)
+    }
+
+    pub fn scheduler_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.scheduler_interval_seconds)
+    }
+}
+
 impl DatabaseSettings {
     pub fn without_db(&self) -> PgConnectOptions {
         let ssl_mode = if self.require_ssl {
@@ -112,7 +194,7 @@ impl DatabaseSettings {
         PgConnectOptions::new()
             .host(&self.host)
             .username(&self.username)
-            .password(&self.password.expose_secret())
+            .password(self.password.expose_secret())
             .port(self.port)
             .ssl_mode(ssl_mode)
     } 
@@ -133,14 +215,44 @@ impl EmailClientSettings {
         std::time::Duration::from_millis(self.timeout_milliseconds)
     }
 
-    pub fn client(self) -> EmailClient {
+    // Build it once per process, every clone shares the same send rate limit
+    pub fn client(self) -> Arc<dyn EmailTransport> {
+        let max_emails_per_second = self.max_emails_per_second;
+        Arc::new(RateLimitedTransport::new(self.transport(), max_emails_per_second))
+    }
+
+    fn transport(self) -> Arc<dyn EmailTransport> {
         let sender_email = self.sender().expect("Invalid sender email address.");
         let timeout = self.timeout();
-        EmailClient::new(
-            self.base_url,
-            sender_email,
-            self.authorisation_token,
-            timeout,
-        )
+        match self.transport {
+            EmailTransportKind::Postmark => Arc::new(PostmarkTransport::new(
+                self.base_url,
+                sender_email,
+                self.authorisation_token,
+                timeout,
+            )),
+            EmailTransportKind::Smtp => {
+                let smtp = self.smtp.expect("Missing SMTP settings.");
+                let credentials = smtp.username.zip(smtp.password);
+                Arc::new(
+                    SmtpTransport::new(
+                        &smtp.host,
+                        smtp.port,
+                        smtp.starttls,
+                        credentials,
+                        sender_email,
+                        timeout,
+                    )
+                    .expect("Failed to build SMTP transport."),
+                )
+            },
+            EmailTransportKind::File => {
+                let directory = self.outbox_directory.expect("Missing outbox directory.");
+                Arc::new(
+                    FileTransport::new(directory, sender_email)
+                        .expect("Failed to create outbox directory."),
+                )
+            },
+        }
     }
 }
\ No newline at end of file
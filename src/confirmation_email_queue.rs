@@ -0,0 +1,139 @@
+use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName, SubscriberProfile};
+use crate::email_client::EmailTransport;
+use crate::email_templates::get_default_email_layout;
+use crate::issue_delivery_worker::{retry_delay, ExecutionOutcome, ISSUE_DELIVERY_CHANNEL, MAX_RETRIES};
+use crate::routes::send_confirmation_email;
+
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{field::display, Span};
+
+// For bulk signups, which would take too long to confirm within the request
+#[tracing::instrument(skip_all)]
+pub async fn enqueue_confirmation_email(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscription_token: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO confirmation_email_queue (subscription_token) VALUES ($1)",
+        subscription_token
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // Issue delivery workers also drain this queue
+    sqlx::query!(
+        "SELECT pg_notify($1, '')",
+        ISSUE_DELIVERY_CHANNEL,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(subscriber_email = tracing::field::Empty),
+    err
+)]
+pub async fn try_send_confirmation_email(
+    pool: &PgPool,
+    email_client: &dyn EmailTransport,
+    base_url: &String,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let task = sqlx::query!(
+        r#"
+        SELECT
+        confirmation_email_queue.subscription_token,
+        confirmation_email_queue.n_retries,
+        subscriptions.email,
+        subscriptions.name
+        FROM confirmation_email_queue
+        JOIN subscription_tokens ON
+        subscription_tokens.subscription_token = confirmation_email_queue.subscription_token
+        JOIN subscriptions ON subscriptions.id = subscription_tokens.subscriber_id
+        WHERE confirmation_email_queue.execute_after <= now()
+        FOR UPDATE OF confirmation_email_queue
+        SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(&mut transaction)
+    .await?;
+    let task = match task {
+        Some(task) => task,
+        None => return Ok(ExecutionOutcome::EmptyQueue),
+    };
+    Span::current().record("subscriber_email", display(&task.email));
+
+    let new_subscriber = SubscriberEmail::parse(task.email).and_then(|email| {
+        Ok(NewSubscriber {
+            email,
+            name: SubscriberName::parse(task.name)?,
+            profile: SubscriberProfile::default(),
+        })
+    });
+    match new_subscriber {
+        Ok(new_subscriber) => {
+            let layout = get_default_email_layout(pool).await?;
+            if let Err(e) = send_confirmation_email(
+                pool,
+                email_client,
+                layout.as_ref(),
+                new_subscriber,
+                base_url,
+                &task.subscription_token,
+            )
+            .await
+            {
+                let n_retries = task.n_retries + 1;
+                if n_retries < MAX_RETRIES {
+                    tracing::warn!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to send a confirmation email. Retrying later."
+                    );
+                    let execute_after =
+                        Utc::now() + chrono::Duration::from_std(retry_delay(n_retries))?;
+                    sqlx::query!(
+                        r#"
+                        UPDATE confirmation_email_queue
+                        SET
+                        n_retries = $2,
+                        execute_after = $3
+                        WHERE subscription_token = $1
+                        "#,
+                        task.subscription_token,
+                        n_retries,
+                        execute_after
+                    )
+                    .execute(&mut transaction)
+                    .await?;
+                    transaction.commit().await?;
+                    return Ok(ExecutionOutcome::TaskCompleted);
+                }
+                // The subscriber can still ask for a new link by subscribing again
+                tracing::error!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to send a confirmation email. Retry budget exhausted."
+                );
+            }
+        }
+        Err(e) => {
+            tracing::error!(
+                error.message = %e,
+                "Skipping a confirmation email as stored contact details are invalid."
+            );
+        }
+    }
+
+    sqlx::query!(
+        "DELETE FROM confirmation_email_queue WHERE subscription_token = $1",
+        task.subscription_token
+    )
+    .execute(&mut transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
@@ -0,0 +1,93 @@
+use super::merge_tags::{fill_content_slot, validate_layout_tags};
+
+// Branding wrapped around issues and confirmation emails
+#[derive(Debug)]
+pub struct EmailLayout {
+    html: String,
+    text: String,
+}
+
+impl EmailLayout {
+    // An empty text layout sends the plain text body unchanged
+    pub fn parse(html: String, text: String) -> Result<EmailLayout, String> {
+        let text = if text.trim().is_empty() {
+            "{{ content }}".to_owned()
+        } else {
+            text
+        };
+        validate_layout_tags(&html).map_err(|e| format!("HTML layout: {}", e))?;
+        validate_layout_tags(&text).map_err(|e| format!("Text layout: {}", e))?;
+        Ok(Self { html, text })
+    }
+
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Merge tags are rendered after wrapping, so a layout can use them too
+    pub fn wrap(layout: Option<&EmailLayout>, html: &str, text: &str) -> (String, String) {
+        match layout {
+            Some(layout) => (
+                fill_content_slot(&layout.html, html),
+                fill_content_slot(&layout.text, text),
+            ),
+            None => (html.to_owned(), text.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::EmailLayout;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn an_empty_text_layout_keeps_the_text_body_as_is() {
+        let layout = EmailLayout::parse("<main>{{ content }}</main>".into(), "".into()).unwrap();
+
+        let (html, text) = EmailLayout::wrap(Some(&layout), "<p>Hi</p>", "Hi");
+
+        assert_eq!(html, "<main><p>Hi</p></main>");
+        assert_eq!(text, "Hi");
+    }
+
+    #[test]
+    fn both_bodies_are_wrapped() {
+        let layout = EmailLayout::parse(
+            "<main>{{ content }}</main>".into(),
+            "{{ content }}\n--\nThe team".into(),
+        )
+        .unwrap();
+
+        let (html, text) = EmailLayout::wrap(Some(&layout), "<p>Hi</p>", "Hi");
+
+        assert_eq!(html, "<main><p>Hi</p></main>");
+        assert_eq!(text, "Hi\n--\nThe team");
+    }
+
+    #[test]
+    fn content_is_unchanged_without_a_layout() {
+        let (html, text) = EmailLayout::wrap(None, "<p>Hi</p>", "Hi");
+
+        assert_eq!(html, "<p>Hi</p>");
+        assert_eq!(text, "Hi");
+    }
+
+    #[test]
+    fn layouts_without_a_content_slot_are_rejected() {
+        assert_err!(EmailLayout::parse("<main></main>".into(), "".into()));
+        assert_err!(EmailLayout::parse("{{ content }}".into(), "The team".into()));
+    }
+
+    #[test]
+    fn layouts_may_use_merge_tags() {
+        assert_ok!(EmailLayout::parse(
+            "{{ content }}<a href=\"{{ unsubscribe_url }}\">Leave</a>".into(),
+            "{{ content }}\nSent to {{ email }}".into(),
+        ));
+    }
+}
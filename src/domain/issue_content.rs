@@ -0,0 +1,182 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+
+// The bodies of a newsletter issue, rendered from markdown when there is some
+pub struct IssueContent {
+    pub markdown: Option<String>,
+    pub html: String,
+    pub text: String,
+}
+
+impl IssueContent {
+    // Markdown takes precedence, the separate html and text fields are kept
+    // for authors who want full control over both bodies
+    pub fn new(markdown: String, html: String, text: String) -> IssueContent {
+        if markdown.trim().is_empty() {
+            Self { markdown: None, html, text }
+        } else {
+            Self {
+                html: render_html(&markdown),
+                text: render_text(&markdown),
+                markdown: Some(markdown),
+            }
+        }
+    }
+}
+
+// Issues are sent as they are, one without a body would reach every subscriber empty
+pub fn validate_issue_body(html: &str, text: &str) -> Result<(), String> {
+    if html.trim().is_empty() && text.trim().is_empty() {
+        return Err("The issue has no content, write it in Markdown, HTML or plain text.".into());
+    }
+    Ok(())
+}
+
+fn render_html(markdown: &str) -> String {
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, Parser::new_ext(markdown, Options::empty()));
+    // Raw HTML is allowed in markdown, strip anything unsafe to put in an email
+    ammonia::clean(&html_output)
+}
+
+fn render_text(markdown: &str) -> String {
+    let mut text = String::new();
+    // Destination of every open link, and where its text started
+    let mut links: Vec<(String, usize)> = Vec::new();
+    // Next number of every open list, None for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+
+    for event in Parser::new_ext(markdown, Options::empty()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::Rule => text.push_str("----\n\n"),
+            Event::Start(Tag::Link(_, destination, _)) => {
+                links.push((destination.to_string(), text.len()));
+            }
+            Event::End(Tag::Link(..)) => {
+                let (destination, start) = links.pop().unwrap();
+                if text[start..] != destination {
+                    text.push_str(&format!(" ({})", destination));
+                }
+            }
+            Event::Start(Tag::List(first_number)) => lists.push(first_number),
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    text.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => {
+                // Nested lists start on the same line as their parent item's text
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                let depth = lists.len().saturating_sub(1);
+                text.push_str(&"  ".repeat(depth));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        text.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::End(Tag::Item) if !text.ends_with('\n') => text.push('\n'),
+            Event::End(Tag::Paragraph) if !lists.is_empty() => text.push('\n'),
+            Event::End(Tag::Paragraph | Tag::Heading(..) | Tag::BlockQuote | Tag::CodeBlock(_)) => {
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    text.trim_end().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{validate_issue_body, IssueContent};
+    use claims::{assert_err, assert_none, assert_ok};
+
+    fn from_markdown(markdown: &str) -> IssueContent {
+        IssueContent::new(markdown.into(), String::new(), String::new())
+    }
+
+    #[test]
+    fn html_and_text_are_used_as_is_without_markdown() {
+        let content = IssueContent::new(" \n".into(), "<p>Hi</p>".into(), "Hi".into());
+
+        assert_none!(content.markdown);
+        assert_eq!(content.html, "<p>Hi</p>");
+        assert_eq!(content.text, "Hi");
+    }
+
+    #[test]
+    fn markdown_takes_precedence_over_html_and_text() {
+        let content = IssueContent::new("Hello".into(), "<p>Hi</p>".into(), "Hi".into());
+
+        assert_eq!(content.markdown.as_deref(), Some("Hello"));
+        assert_eq!(content.html, "<p>Hello</p>\n");
+        assert_eq!(content.text, "Hello");
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_html() {
+        let content = from_markdown("# Title\n\nSome *emphasis* and a [link](https://example.com).");
+
+        assert_eq!(
+            content.html,
+            "<h1>Title</h1>\n<p>Some <em>emphasis</em> and a \
+            <a href=\"https://example.com\" rel=\"noopener noreferrer\">link</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn unsafe_html_is_removed_from_the_rendered_html() {
+        let content = from_markdown(
+            "Hello <script>alert('hi')</script>\n\n<a href=\"javascript:alert(1)\" onclick=\"x()\">click</a>"
+        );
+
+        assert!(!content.html.contains("script"));
+        assert!(!content.html.contains("javascript"));
+        assert!(!content.html.contains("onclick"));
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_plain_text() {
+        let content = from_markdown(
+            "# Title\n\nSome *emphasis* and a [link](https://example.com).\n\n\
+            - first\n- second\n\n1. one\n2. two\n\nVisit <https://example.com>"
+        );
+
+        assert_eq!(
+            content.text,
+            "Title\n\nSome emphasis and a link (https://example.com).\n\n\
+            - first\n- second\n\n1. one\n2. two\n\nVisit https://example.com"
+        );
+    }
+
+    #[test]
+    fn nested_lists_are_indented_in_plain_text() {
+        let content = from_markdown("- outer\n  - inner\n- last");
+
+        assert_eq!(content.text, "- outer\n  - inner\n- last");
+    }
+
+    #[test]
+    fn raw_html_is_left_out_of_the_plain_text() {
+        let content = from_markdown("Hello <b>world</b>");
+
+        assert_eq!(content.text, "Hello world");
+    }
+
+    #[test]
+    fn an_issue_needs_an_html_or_a_text_body() {
+        assert_err!(validate_issue_body(" ", "\n"));
+        assert_ok!(validate_issue_body("", "Hi"));
+        assert_ok!(validate_issue_body("<p>Hi</p>", ""));
+    }
+}
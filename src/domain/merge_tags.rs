@@ -0,0 +1,294 @@
+use super::ProfileField;
+
+// Per-recipient placeholders such as `{{ name }}` in newsletter issues
+pub const KNOWN_MERGE_TAGS: [&str; 3] = ["name", "email", "unsubscribe_url"];
+
+// `{{ profile.company }}` renders the subscriber's value of the `company` field,
+// and nothing for subscribers without one
+pub const PROFILE_MERGE_TAG_PREFIX: &str = "profile.";
+
+pub struct MergeTags<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub unsubscribe_url: &'a str,
+    pub profile: &'a [(String, String)],
+}
+
+impl<'a> MergeTags<'a> {
+    pub fn render_text(&self, template: &str) -> String {
+        self.render(template, |value| value.to_owned())
+    }
+
+    // Values are escaped, subscriber names come straight from the signup form
+    pub fn render_html(&self, template: &str) -> String {
+        self.render(template, htmlescape::encode_minimal)
+    }
+
+    fn render(&self, template: &str, encode: impl Fn(&str) -> String) -> String {
+        let mut output = String::with_capacity(template.len());
+        for segment in segments(template) {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Tag { raw, name } => match self.value(name) {
+                    Some(value) => output.push_str(&encode(value)),
+                    // Unknown tags are rejected at publish time, leave them as written
+                    None => output.push_str(raw),
+                },
+            }
+        }
+        output
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        match name {
+            "name" => Some(self.name),
+            "email" => Some(self.email),
+            "unsubscribe_url" => Some(self.unsubscribe_url),
+            _ => {
+                let key = profile_field_key(name)?;
+                let value = self.profile.iter().find(|(k, _)| k == key);
+                Some(value.map(|(_, v)| v.as_str()).unwrap_or(""))
+            }
+        }
+    }
+}
+
+fn profile_field_key(tag: &str) -> Option<&str> {
+    tag.strip_prefix(PROFILE_MERGE_TAG_PREFIX).filter(|key| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    })
+}
+
+// Profile tags must name one of the given fields, a typo would render nothing for
+// every recipient. The error lists every unknown tag, escaped so it can be shown
+// in a flash message.
+pub fn validate_merge_tags(template: &str, profile_fields: &[ProfileField]) -> Result<(), String> {
+    let unknown = unknown_tags(template, |key| profile_fields.iter().any(|f| f.key == key));
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let available: Vec<String> = KNOWN_MERGE_TAGS
+        .iter()
+        .map(|t| t.to_string())
+        .chain(
+            profile_fields
+                .iter()
+                .map(|f| format!("{}{}", PROFILE_MERGE_TAG_PREFIX, f.key)),
+        )
+        .map(|t| format!("{{{{ {} }}}}", t))
+        .collect();
+    Err(format!(
+        "Unknown merge tags: {}. Available tags are {}.",
+        unknown.join(", "),
+        available.join(", ")
+    ))
+}
+
+fn unknown_tags(template: &str, is_profile_field: impl Fn(&str) -> bool) -> Vec<String> {
+    segments(template)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Tag { raw, name }
+                if !KNOWN_MERGE_TAGS.contains(&name)
+                    && !profile_field_key(name).is_some_and(&is_profile_field) =>
+            {
+                Some(htmlescape::encode_minimal(raw))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Where a layout puts the issue or email it wraps
+pub const CONTENT_SLOT: &str = "content";
+
+// Tags other than the content slot are left for MergeTags to render
+pub fn fill_content_slot(layout: &str, content: &str) -> String {
+    let mut output = String::with_capacity(layout.len() + content.len());
+    for segment in segments(layout) {
+        match segment {
+            Segment::Tag { name, .. } if name == CONTENT_SLOT => output.push_str(content),
+            Segment::Tag { raw, .. } | Segment::Text(raw) => output.push_str(raw),
+        }
+    }
+    output
+}
+
+// A layout needs exactly one content slot, any other tag must be a known merge tag.
+// Layouts outlive the fields they were written for, so any profile tag is accepted.
+pub fn validate_layout_tags(layout: &str) -> Result<(), String> {
+    let n_slots = segments(layout)
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Tag { name, .. } if *name == CONTENT_SLOT))
+        .count();
+    if n_slots != 1 {
+        return Err(format!(
+            "A layout must contain {{{{ {} }}}} exactly once.",
+            CONTENT_SLOT
+        ));
+    }
+    let unknown = unknown_tags(&fill_content_slot(layout, ""), |_| true);
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unknown merge tags: {}. Available tags are {} and {{{{ {}<field> }}}}.",
+            unknown.join(", "),
+            KNOWN_MERGE_TAGS.map(|t| format!("{{{{ {} }}}}", t)).join(", "),
+            PROFILE_MERGE_TAG_PREFIX
+        ))
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Tag { raw: &'a str, name: &'a str },
+}
+
+// An opening `{{` without a matching `}}` is kept as text
+fn segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(length) => start + 2 + length + 2,
+            None => break,
+        };
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        let raw = &rest[start..end];
+        segments.push(Segment::Tag { raw, name: raw[2..raw.len() - 2].trim() });
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{
+        fill_content_slot, validate_layout_tags, validate_merge_tags, MergeTags, ProfileField,
+        ProfileFieldType,
+    };
+    use claims::{assert_err, assert_ok};
+
+    fn merge_tags() -> MergeTags<'static> {
+        MergeTags {
+            name: "Ursula <Le Guin>",
+            email: "ursula@example.com",
+            unsubscribe_url: "https://example.com/unsubscribe?a=1&b=2",
+            profile: &[],
+        }
+    }
+
+    #[test]
+    fn known_tags_are_replaced_with_and_without_spaces() {
+        let rendered = merge_tags().render_text("Hi {{ name }}, this went to {{email}}.");
+
+        assert_eq!(rendered, "Hi Ursula <Le Guin>, this went to ursula@example.com.");
+    }
+
+    #[test]
+    fn values_are_escaped_in_html() {
+        let rendered = merge_tags()
+            .render_html(r#"<p>Hi {{ name }}</p><a href="{{ unsubscribe_url }}">Unsubscribe</a>"#);
+
+        assert_eq!(
+            rendered,
+            r#"<p>Hi Ursula &lt;Le Guin&gt;</p><a href="https://example.com/unsubscribe?a=1&amp;b=2">Unsubscribe</a>"#
+        );
+    }
+
+    #[test]
+    fn text_without_tags_is_unchanged() {
+        let template = "No tags here, not even {{ an unclosed one";
+
+        assert_eq!(merge_tags().render_text(template), template);
+        assert_ok!(validate_merge_tags(template, &[]));
+    }
+
+    #[test]
+    fn known_tags_are_valid() {
+        assert_ok!(validate_merge_tags("{{ name }} {{email}} {{ unsubscribe_url }}", &[]));
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        let error = validate_merge_tags("Hi {{ first_name }} {{ name }} {{<b>}}", &[]).unwrap_err();
+
+        assert!(error.starts_with("Unknown merge tags: {{ first_name }}, {{&lt;b&gt;}}."));
+    }
+
+    #[test]
+    fn the_content_slot_is_filled_and_other_tags_are_kept() {
+        let filled = fill_content_slot("<main>{{content}}</main><a href=\"{{ unsubscribe_url }}\">", "<p>Hi</p>");
+
+        assert_eq!(filled, "<main><p>Hi</p></main><a href=\"{{ unsubscribe_url }}\">");
+    }
+
+    #[test]
+    fn a_layout_needs_exactly_one_content_slot() {
+        assert_err!(validate_layout_tags("<main></main>"));
+        assert_err!(validate_layout_tags("{{ content }}{{ content }}"));
+        assert_ok!(validate_layout_tags("<main>{{ content }}</main>{{ unsubscribe_url }}"));
+    }
+
+    #[test]
+    fn a_layout_cannot_use_unknown_tags() {
+        assert_err!(validate_layout_tags("{{ content }}{{ footer }}"));
+    }
+
+    #[test]
+    fn empty_tags_are_rejected() {
+        assert_err!(validate_merge_tags("Hi {{}}", &[]));
+    }
+
+    #[test]
+    fn profile_tags_render_the_subscriber_value_or_nothing() {
+        let profile = vec![("company".to_string(), "Acme & Co".to_string())];
+        let merge_tags = MergeTags {
+            profile: &profile,
+            ..merge_tags()
+        };
+
+        let rendered = merge_tags.render_html("{{ profile.company }}|{{ profile.city }}");
+
+        assert_eq!(rendered, "Acme &amp; Co|");
+    }
+
+    fn profile_field(key: &str) -> ProfileField {
+        ProfileField {
+            key: key.into(),
+            label: key.into(),
+            field_type: ProfileFieldType::String,
+            is_required: false,
+        }
+    }
+
+    #[test]
+    fn profile_tags_need_an_existing_field() {
+        let fields = vec![profile_field("company_size")];
+
+        assert_ok!(validate_merge_tags("{{ profile.company_size }}", &fields));
+        assert_err!(validate_merge_tags("{{ profile. }}", &fields));
+        assert_err!(validate_merge_tags("{{ profile.Company_size }}", &fields));
+        let error = validate_merge_tags("{{ profile.compnay_size }}", &fields).unwrap_err();
+        assert_eq!(
+            error,
+            "Unknown merge tags: {{ profile.compnay_size }}. Available tags are {{ name }}, \
+            {{ email }}, {{ unsubscribe_url }}, {{ profile.company_size }}."
+        );
+    }
+
+    #[test]
+    fn layouts_may_use_any_profile_tag() {
+        assert_ok!(validate_layout_tags("{{ content }}{{ profile.company }}"));
+        assert_err!(validate_layout_tags("{{ content }}{{ profile.Company }}"));
+    }
+}
@@ -1,7 +1,20 @@
+mod email_layout;
+mod issue_content;
+mod merge_tags;
 mod new_subscriber;
 mod subscriber_name;
+mod subscriber_profile;
+mod subscriber_tag;
 mod subscriber_email;
 
+pub use issue_content::{validate_issue_body, IssueContent};
+pub use email_layout::EmailLayout;
+pub use merge_tags::{
+    fill_content_slot, validate_layout_tags, validate_merge_tags, MergeTags, CONTENT_SLOT,
+    KNOWN_MERGE_TAGS,
+};
 pub use new_subscriber::NewSubscriber;
 pub use subscriber_name::SubscriberName;
+pub use subscriber_profile::{ProfileField, ProfileFieldType, SubscriberProfile};
+pub use subscriber_tag::SubscriberTag;
 pub use subscriber_email::SubscriberEmail;
\ No newline at end of file
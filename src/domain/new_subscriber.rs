@@ -1,7 +1,9 @@
 use crate::domain::SubscriberName; 
 use crate::domain::SubscriberEmail;
+use crate::domain::SubscriberProfile;
 
 pub struct NewSubscriber {
     pub email: SubscriberEmail,
     pub name: SubscriberName,
+    pub profile: SubscriberProfile,
 }
@@ -0,0 +1,228 @@
+use chrono::NaiveDate;
+use unicode_segmentation::UnicodeSegmentation;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileFieldType {
+    String,
+    Number,
+    Boolean,
+    Date,
+    Enum(Vec<String>),
+}
+
+impl ProfileFieldType {
+    // Enum fields need at least one option, other types ignore them
+    pub fn parse(field_type: &str, options: Vec<String>) -> Result<ProfileFieldType, String> {
+        match field_type {
+            "string" => Ok(Self::String),
+            "number" => Ok(Self::Number),
+            "boolean" => Ok(Self::Boolean),
+            "date" => Ok(Self::Date),
+            "enum" if options.is_empty() => Err("A choice field needs at least one option.".into()),
+            "enum" => Ok(Self::Enum(options)),
+            other => Err(format!("{} is not a known field type.", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::Enum(_) => "enum",
+        }
+    }
+
+    pub fn options(&self) -> &[String] {
+        match self {
+            Self::Enum(options) => options,
+            _ => &[],
+        }
+    }
+
+    // Values are stored in a canonical form so segments can compare them as text
+    fn parse_value(&self, value: &str) -> Option<String> {
+        match self {
+            Self::String => {
+                let is_too_long = value.graphemes(true).count() > 256;
+                let contains_control_characters = value.chars().any(char::is_control);
+                (!is_too_long && !contains_control_characters).then(|| value.to_owned())
+            }
+            Self::Number => value
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(|n| n.to_string()),
+            Self::Boolean => match value.to_lowercase().as_str() {
+                "true" | "on" | "yes" | "1" => Some("true".into()),
+                "false" | "off" | "no" | "0" => Some("false".into()),
+                _ => None,
+            },
+            Self::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+            Self::Enum(options) => options.iter().find(|o| *o == value).cloned(),
+        }
+    }
+}
+
+pub struct ProfileField {
+    pub key: String,
+    pub label: String,
+    pub field_type: ProfileFieldType,
+    pub is_required: bool,
+}
+
+impl ProfileField {
+    // A missing or blank value is None, an unticked checkbox is false
+    pub fn parse_value(&self, value: Option<&str>) -> Result<Option<String>, String> {
+        let value = value.map(str::trim).filter(|v| !v.is_empty());
+        let value = match (value, &self.field_type) {
+            (None, ProfileFieldType::Boolean) => "false",
+            (None, _) if self.is_required => return Err(format!("{} is required.", self.label)),
+            (None, _) => return Ok(None),
+            (Some(value), _) => value,
+        };
+        match self.field_type.parse_value(value) {
+            Some(value) if self.is_required && value == "false" => {
+                Err(format!("{} is required.", self.label))
+            }
+            Some(value) => Ok(Some(value)),
+            None => Err(format!("{} is not a valid value for {}.", value, self.label)),
+        }
+    }
+}
+
+// Values of custom profile fields, keyed by field
+#[derive(Debug, Default)]
+pub struct SubscriberProfile(Vec<(String, String)>);
+
+impl SubscriberProfile {
+    pub fn parse(
+        fields: &[ProfileField],
+        values: &HashMap<String, String>,
+    ) -> Result<SubscriberProfile, String> {
+        let mut profile = Vec::new();
+        for field in fields {
+            if let Some(value) = field.parse_value(values.get(&field.key).map(String::as_str))? {
+                profile.push((field.key.clone(), value));
+            }
+        }
+        Ok(Self(profile))
+    }
+
+    pub fn values(&self) -> &[(String, String)] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{ProfileField, ProfileFieldType, SubscriberProfile};
+    use claims::{assert_err, assert_none, assert_ok};
+    use std::collections::HashMap;
+
+    fn field(field_type: ProfileFieldType, is_required: bool) -> ProfileField {
+        ProfileField {
+            key: "field".into(),
+            label: "Field".into(),
+            field_type,
+            is_required,
+        }
+    }
+
+    #[test]
+    fn choice_fields_need_options() {
+        assert_err!(ProfileFieldType::parse("enum", vec![]));
+        assert_ok!(ProfileFieldType::parse("enum", vec!["weekly".into()]));
+        assert_err!(ProfileFieldType::parse("colour", vec![]));
+    }
+
+    #[test]
+    fn missing_optional_values_are_skipped() {
+        let field = field(ProfileFieldType::String, false);
+        assert_none!(assert_ok!(field.parse_value(None)));
+        assert_none!(assert_ok!(field.parse_value(Some("  "))));
+    }
+
+    #[test]
+    fn missing_required_values_are_rejected() {
+        let field = field(ProfileFieldType::Date, true);
+        assert_eq!(field.parse_value(None).unwrap_err(), "Field is required.");
+    }
+
+    #[test]
+    fn strings_longer_than_256_graphemes_are_rejected() {
+        let field = field(ProfileFieldType::String, false);
+        assert_ok!(field.parse_value(Some(&"ё".repeat(256))));
+        assert_err!(field.parse_value(Some(&"a".repeat(257))));
+        assert_err!(field.parse_value(Some("line\nbreak")));
+    }
+
+    #[test]
+    fn numbers_are_stored_in_canonical_form() {
+        let field = field(ProfileFieldType::Number, false);
+        assert_eq!(field.parse_value(Some("1.50")).unwrap().unwrap(), "1.5");
+        assert_eq!(field.parse_value(Some(" 42 ")).unwrap().unwrap(), "42");
+        assert_err!(field.parse_value(Some("forty-two")));
+        assert_err!(field.parse_value(Some("inf")));
+    }
+
+    #[test]
+    fn unticked_checkboxes_are_false() {
+        let field = field(ProfileFieldType::Boolean, false);
+        assert_eq!(field.parse_value(None).unwrap().unwrap(), "false");
+        assert_eq!(field.parse_value(Some("on")).unwrap().unwrap(), "true");
+        assert_err!(field.parse_value(Some("maybe")));
+    }
+
+    #[test]
+    fn required_checkboxes_must_be_ticked() {
+        let field = field(ProfileFieldType::Boolean, true);
+        assert_err!(field.parse_value(None));
+        assert_ok!(field.parse_value(Some("true")));
+    }
+
+    #[test]
+    fn dates_must_be_valid_calendar_dates() {
+        let field = field(ProfileFieldType::Date, false);
+        assert_ok!(field.parse_value(Some("2024-02-29")));
+        assert_err!(field.parse_value(Some("2023-02-29")));
+        assert_err!(field.parse_value(Some("29/02/2024")));
+    }
+
+    #[test]
+    fn choices_must_be_one_of_the_options() {
+        let field = field(
+            ProfileFieldType::Enum(vec!["weekly".into(), "monthly".into()]),
+            false,
+        );
+        assert_ok!(field.parse_value(Some("monthly")));
+        assert_err!(field.parse_value(Some("daily")));
+    }
+
+    #[test]
+    fn profiles_only_keep_values_of_known_fields() {
+        let fields = vec![
+            ProfileField {
+                key: "company".into(),
+                ..field(ProfileFieldType::String, false)
+            },
+            ProfileField {
+                key: "age".into(),
+                ..field(ProfileFieldType::Number, false)
+            },
+        ];
+        let values = HashMap::from([
+            ("company".to_string(), "Acme".to_string()),
+            ("unknown".to_string(), "ignored".to_string()),
+        ]);
+
+        let profile = assert_ok!(SubscriberProfile::parse(&fields, &values));
+
+        assert_eq!(profile.values(), &[("company".to_string(), "Acme".to_string())]);
+    }
+}
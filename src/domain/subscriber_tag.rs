@@ -0,0 +1,74 @@
+#[derive(Debug)]
+pub struct SubscriberTag(String);
+
+impl AsRef<str> for SubscriberTag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl SubscriberTag {
+    // Tags are matched exactly, so casing and surrounding whitespace are dropped
+    pub fn parse(s: String) -> Result<SubscriberTag, String> {
+        let tag = s.trim().to_lowercase();
+        let is_valid = !tag.is_empty()
+            && tag.len() <= 64
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if is_valid {
+            Ok(Self(tag))
+        } else {
+            Err(format!("{} is not a valid tag.", s))
+        }
+    }
+
+    // Comma separated, as typed into a form field
+    pub fn parse_list(s: &str) -> Result<Vec<SubscriberTag>, String> {
+        s.split(',')
+            .filter(|tag| !tag.trim().is_empty())
+            .map(|tag| SubscriberTag::parse(tag.to_owned()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::SubscriberTag;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn tags_are_trimmed_and_lowercased() {
+        let tag = assert_ok!(SubscriberTag::parse(" Beta-Testers ".to_string()));
+        assert_eq!(tag.as_ref(), "beta-testers");
+    }
+
+    #[test]
+    fn empty_tags_are_rejected() {
+        assert_err!(SubscriberTag::parse(" ".to_string()));
+    }
+
+    #[test]
+    fn tags_longer_than_64_characters_are_rejected() {
+        assert_err!(SubscriberTag::parse("a".repeat(65)));
+    }
+
+    #[test]
+    fn tags_containing_an_invalid_character_are_rejected() {
+        for tag in &["vip customers", "beta,", "<b>", "café"] {
+            assert_err!(SubscriberTag::parse(tag.to_string()));
+        }
+    }
+
+    #[test]
+    fn tag_lists_skip_empty_entries() {
+        let tags = assert_ok!(SubscriberTag::parse_list("beta, ,vip,"));
+        let tags: Vec<_> = tags.iter().map(|t| t.as_ref()).collect();
+        assert_eq!(tags, vec!["beta", "vip"]);
+    }
+
+    #[test]
+    fn tag_lists_with_an_invalid_tag_are_rejected() {
+        assert_err!(SubscriberTag::parse_list("beta, early adopters"));
+    }
+}
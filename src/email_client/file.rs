@@ -0,0 +1,84 @@
+use crate::domain::SubscriberEmail;
+use super::{build_message, EmailHeader, EmailTransport};
+
+use axum::async_trait;
+use lettre::{AsyncFileTransport, AsyncTransport, Tokio1Executor};
+
+use std::path::Path;
+
+// Writes every email as an .eml file instead of sending it, for local development
+pub struct FileTransport {
+    mailer: AsyncFileTransport<Tokio1Executor>,
+    sender: SubscriberEmail,
+}
+
+impl FileTransport {
+    pub fn new(
+        directory: impl AsRef<Path>,
+        sender: SubscriberEmail,
+    ) -> Result<Self, std::io::Error> {
+        std::fs::create_dir_all(directory.as_ref())?;
+        Ok(Self {
+            mailer: AsyncFileTransport::new(directory),
+            sender,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for FileTransport {
+    async fn send_email(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[EmailHeader<'_>],
+    ) -> Result<(), anyhow::Error> {
+        let message = build_message(
+            &self.sender,
+            recipient,
+            subject,
+            html_content,
+            text_content,
+            headers,
+        )?;
+        self.mailer.send(message).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::SubscriberEmail;
+    use crate::email_client::{EmailTransport, FileTransport};
+    use claims::assert_ok;
+
+    #[tokio::test]
+    async fn send_email_writes_an_eml_file_to_the_outbox_directory() {
+        // Arrange
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let sender = SubscriberEmail::parse("sender@example.com".into()).unwrap();
+        let recipient = SubscriberEmail::parse("ursula@example.com".into()).unwrap();
+        let transport = FileTransport::new(&directory, sender).unwrap();
+
+        // Act
+        let outcome = transport
+            .send_email(&recipient, "Newsletter title", "<p>Hello</p>", "Hello", &[])
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "eml");
+        let contents = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(contents.contains("Subject: Newsletter title"));
+        assert!(contents.contains("To: ursula@example.com"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+mod file;
+mod postmark;
+mod rate_limit;
+mod smtp;
+
+pub use file::FileTransport;
+pub use postmark::PostmarkTransport;
+pub use rate_limit::RateLimitedTransport;
+pub use smtp::SmtpTransport;
+
+use crate::domain::SubscriberEmail;
+
+use axum::async_trait;
+use lettre::message::{header::{HeaderName, HeaderValue}, MultiPart};
+use lettre::Message;
+
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send_email(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[EmailHeader<'_>],
+    ) -> Result<(), anyhow::Error>;
+
+    // Returns once an email could be sent without waiting, so that callers can
+    // hold off before locking anything. It does not reserve the send itself.
+    async fn wait_until_ready(&self) {}
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EmailHeader<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+// Shared by the transports that hand a full MIME message to lettre
+fn build_message(
+    sender: &SubscriberEmail,
+    recipient: &SubscriberEmail,
+    subject: &str,
+    html_content: &str,
+    text_content: &str,
+    headers: &[EmailHeader<'_>],
+) -> Result<Message, anyhow::Error> {
+    let mut message = Message::builder()
+        .from(sender.as_ref().parse()?)
+        .to(recipient.as_ref().parse()?)
+        .subject(subject)
+        .multipart(MultiPart::alternative_plain_html(
+            text_content.to_owned(),
+            html_content.to_owned(),
+        ))?;
+    for header in headers {
+        let name = HeaderName::new_from_ascii(header.name.to_owned())?;
+        message
+            .headers_mut()
+            .insert_raw(HeaderValue::new(name, header.value.to_owned()));
+    }
+    Ok(message)
+}
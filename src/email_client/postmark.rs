@@ -1,15 +1,18 @@
 use crate::domain::SubscriberEmail;
+use super::{EmailHeader, EmailTransport};
+
+use axum::async_trait;
 use reqwest::Client;
 use secrecy::{Secret, ExposeSecret};
 
-pub struct EmailClient {
+pub struct PostmarkTransport {
     http_client: Client,
     base_url: String,
     sender: SubscriberEmail,
     authorisation_token: Secret<String>
 }
 
-impl EmailClient {
+impl PostmarkTransport {
     pub fn new(
         base_url: String,
         sender: SubscriberEmail,
@@ -27,21 +30,26 @@ impl EmailClient {
             authorisation_token,
         }
     }
+}
 
-    pub async fn send_email(
+#[async_trait]
+impl EmailTransport for PostmarkTransport {
+    async fn send_email(
         &self,
         recipient: &SubscriberEmail,
         subject: &str,
         html_content: &str,
-        text_content: &str
-    ) -> Result<(), reqwest::Error> {
+        text_content: &str,
+        headers: &[EmailHeader<'_>],
+    ) -> Result<(), anyhow::Error> {
         let url = format!("{}/email", self.base_url);
         let request_body = SendEmailRequest {
             from: self.sender.as_ref(),
             to: recipient.as_ref(),
-            subject: subject,
+            subject,
             html_body: html_content,
             text_body: text_content,
+            headers,
         };
         let _ = self.http_client
             .post(url)
@@ -65,12 +73,14 @@ struct SendEmailRequest<'a> {
     subject: &'a str,
     html_body: &'a str,
     text_body: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    headers: &'a [EmailHeader<'a>],
 }
 
 #[cfg(test)]
 mod tests {
     use crate::domain::SubscriberEmail;
-    use crate::email_client::EmailClient;
+    use crate::email_client::{EmailHeader, EmailTransport, PostmarkTransport};
     use claims::{assert_err, assert_ok};
     use fake::faker::internet::en::SafeEmail;
     use fake::faker::lorem::en::{Paragraph, Sentence};
@@ -96,6 +106,20 @@ mod tests {
         }
     }
 
+    struct HeadersBodyMatcher;
+
+    impl wiremock::Match for HeadersBodyMatcher {
+        fn matches(&self, request: &Request) -> bool {
+            let result: Result<serde_json::Value, _> = serde_json::from_slice(&request.body);
+            if let Ok(body) = result {
+                body["Headers"][0]["Name"] == "List-Unsubscribe"
+                    && body["Headers"][0]["Value"] == "<https://example.com/unsubscribe>"
+            } else {
+                false
+            }
+        }
+    }
+
     fn subject() -> String {
         Sentence(1..2).fake()
     }
@@ -108,8 +132,8 @@ mod tests {
         SubscriberEmail::parse(SafeEmail().fake()).unwrap()
     }
 
-    fn email_client(base_url: String) -> EmailClient {
-        EmailClient::new(
+    fn email_client(base_url: String) -> PostmarkTransport {
+        PostmarkTransport::new(
             base_url,
             email(),
             Secret::new(Faker.fake()),
@@ -135,7 +159,33 @@ mod tests {
 
         // Act
         let _ = email_client
-            .send_email(&email(), &subject(), &content(), &content())
+            .send_email(&email(), &subject(), &content(), &content(), &[])
+            .await;
+
+        // Assert
+        // Mock expectations are checked on drop
+    }
+
+    #[tokio::test]
+    async fn send_email_includes_custom_headers_in_the_request() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+
+        Mock::given(HeadersBodyMatcher)
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let headers = [EmailHeader {
+            name: "List-Unsubscribe",
+            value: "<https://example.com/unsubscribe>",
+        }];
+
+        // Act
+        let _ = email_client
+            .send_email(&email(), &subject(), &content(), &content(), &headers)
             .await;
 
         // Assert
@@ -156,7 +206,7 @@ mod tests {
 
         // Act
         let outcome = email_client
-            .send_email(&email(), &subject(), &content(), &content())
+            .send_email(&email(), &subject(), &content(), &content(), &[])
             .await;
 
         // Assert
@@ -177,7 +227,7 @@ mod tests {
 
         // Act
         let outcome = email_client
-            .send_email(&email(), &subject(), &content(), &content())
+            .send_email(&email(), &subject(), &content(), &content(), &[])
             .await;
 
         // Assert
@@ -200,7 +250,7 @@ mod tests {
 
         // Act
         let outcome = email_client
-            .send_email(&email(), &subject(), &content(), &content())
+            .send_email(&email(), &subject(), &content(), &content(), &[])
             .await;
 
         // Assert
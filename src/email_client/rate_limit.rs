@@ -0,0 +1,168 @@
+use crate::domain::SubscriberEmail;
+use super::{EmailHeader, EmailTransport};
+
+use axum::async_trait;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Token bucket shared by every caller of the wrapped transport, so concurrent
+// senders together stay within the provider's messages-per-second quota
+pub struct RateLimitedTransport {
+    inner: Arc<dyn EmailTransport>,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl RateLimitedTransport {
+    pub fn new(inner: Arc<dyn EmailTransport>, max_emails_per_second: u32) -> Self {
+        Self {
+            inner,
+            bucket: Mutex::new(TokenBucket::new(max_emails_per_second, Instant::now())),
+        }
+    }
+
+    async fn acquire(&self) {
+        loop {
+            let wait = match self.bucket.lock().unwrap().try_acquire(Instant::now()) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for RateLimitedTransport {
+    async fn send_email(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[EmailHeader<'_>],
+    ) -> Result<(), anyhow::Error> {
+        self.acquire().await;
+        self.inner
+            .send_email(recipient, subject, html_content, text_content, headers)
+            .await
+    }
+
+    async fn wait_until_ready(&self) {
+        loop {
+            let wait = match self.bucket.lock().unwrap().time_until_available(Instant::now()) {
+                None => return,
+                Some(wait) => wait,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(max_per_second: u32, now: Instant) -> Self {
+        let capacity = max_per_second.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_second: capacity,
+            last_refill: now,
+        }
+    }
+
+    // Takes a token if one is available, otherwise returns how long until one is
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.time_until_available(now) {
+            None => {
+                self.tokens -= 1.0;
+                Ok(())
+            }
+            Some(wait) => Err(wait),
+        }
+    }
+
+    // Same as try_acquire, without taking the token
+    fn time_until_available(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            None
+        } else {
+            let missing = 1.0 - self.tokens;
+            Some(Duration::from_secs_f64(missing / self.refill_per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBucket;
+    use claims::{assert_err, assert_ok};
+
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn a_full_bucket_allows_a_burst_up_to_its_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(3, now);
+
+        for _ in 0..3 {
+            assert_ok!(bucket.try_acquire(now));
+        }
+        assert_err!(bucket.try_acquire(now));
+    }
+
+    #[test]
+    fn an_empty_bucket_reports_how_long_to_wait() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+        bucket.try_acquire(now).unwrap();
+        bucket.try_acquire(now).unwrap();
+
+        let wait = bucket.try_acquire(now).unwrap_err();
+
+        assert_eq!(wait, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn checking_for_a_token_does_not_take_it() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(1, now);
+
+        assert_eq!(bucket.time_until_available(now), None);
+        assert_eq!(bucket.time_until_available(now), None);
+        assert_ok!(bucket.try_acquire(now));
+        assert_eq!(bucket.time_until_available(now), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn tokens_are_refilled_over_time() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+        bucket.try_acquire(now).unwrap();
+        bucket.try_acquire(now).unwrap();
+
+        assert_ok!(bucket.try_acquire(now + Duration::from_millis(500)));
+        assert_err!(bucket.try_acquire(now + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn tokens_do_not_accumulate_beyond_capacity() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+        let later = now + Duration::from_secs(60);
+
+        assert_ok!(bucket.try_acquire(later));
+        assert_ok!(bucket.try_acquire(later));
+        assert_err!(bucket.try_acquire(later));
+    }
+}
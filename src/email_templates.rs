@@ -0,0 +1,101 @@
+use crate::domain::EmailLayout;
+use crate::error::error_chain_fmt;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct EmailTemplate {
+    pub email_template_id: Uuid,
+    pub name: String,
+    pub html_layout: String,
+    pub text_layout: String,
+    pub is_default: bool,
+}
+
+impl EmailTemplate {
+    pub fn layout(&self) -> Result<EmailLayout, anyhow::Error> {
+        EmailLayout::parse(self.html_layout.clone(), self.text_layout.clone())
+            .map_err(anyhow::Error::msg)
+    }
+}
+
+#[tracing::instrument(name = "Get email templates", skip(pool))]
+pub async fn get_email_templates(pool: &PgPool) -> Result<Vec<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as!(
+        EmailTemplate,
+        r#"
+        SELECT email_template_id, name, html_layout, text_layout, is_default
+        FROM email_templates
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Get email template", skip(pool))]
+pub async fn get_email_template(
+    pool: &PgPool,
+    email_template_id: Uuid,
+) -> Result<Option<EmailTemplate>, sqlx::Error> {
+    sqlx::query_as!(
+        EmailTemplate,
+        r#"
+        SELECT email_template_id, name, html_layout, text_layout, is_default
+        FROM email_templates
+        WHERE email_template_id = $1
+        "#,
+        email_template_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Get default email layout", skip(pool))]
+pub async fn get_default_email_layout(
+    pool: &PgPool,
+) -> Result<Option<EmailLayout>, anyhow::Error> {
+    let template = sqlx::query_as!(
+        EmailTemplate,
+        r#"
+        SELECT email_template_id, name, html_layout, text_layout, is_default
+        FROM email_templates
+        WHERE is_default
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+    template.map(|t| t.layout()).transpose()
+}
+
+#[derive(thiserror::Error)]
+pub enum SelectLayoutError {
+    #[error("The selected layout no longer exists.")]
+    UnknownLayout,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for SelectLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+// Looks up the layout picked in a form, an empty value means no layout
+#[tracing::instrument(name = "Get selected email layout", skip(pool))]
+pub async fn get_selected_email_layout(
+    pool: &PgPool,
+    email_template_id: &str,
+) -> Result<Option<(Uuid, EmailLayout)>, SelectLayoutError> {
+    if email_template_id.is_empty() {
+        return Ok(None);
+    }
+    let email_template_id = Uuid::parse_str(email_template_id)
+        .map_err(|_| SelectLayoutError::UnknownLayout)?;
+    let template = get_email_template(pool, email_template_id)
+        .await
+        .map_err(anyhow::Error::from)?
+        .ok_or(SelectLayoutError::UnknownLayout)?;
+    Ok(Some((email_template_id, template.layout()?)))
+}
@@ -1,38 +1,213 @@
+use crate::archive::{archive_url, assign_archive_slug};
 use crate::configuration::Settings;
-use crate::domain::SubscriberEmail;
-use crate::email_client::EmailClient;
+use crate::confirmation_email_queue::try_send_confirmation_email;
+use crate::domain::{EmailLayout, MergeTags, SubscriberEmail};
+use crate::email_client::{EmailHeader, EmailTransport};
+use crate::issue_scheduler::scheduler_loop;
+use crate::profile_fields::get_subscriber_profile;
+use crate::shutdown::ShutdownSignal;
 use crate::startup::get_connection_pool;
+use crate::suppression::is_email_suppressed;
+use crate::tracking::{store_tracking_tokens, track_html, TrackingToken};
 
-use sqlx::{PgPool, Postgres, Transaction};
+use chrono::Utc;
+use rand::Rng;
+use sqlx::{postgres::PgListener, PgPool, Postgres, Transaction};
+use tokio::task::JoinSet;
 use tracing::{field::display, Span};
 use uuid::Uuid;
 
+use std::sync::Arc;
 use std::time::Duration;
 
+// The email client is shared with the API, so that both stay within one send rate limit
 pub async fn run_worker_until_stopped(
     configuration: Settings,
+    email_client: Arc<dyn EmailTransport>,
+    shutdown: ShutdownSignal,
 ) -> Result<(), anyhow::Error> {
     let connection_pool = get_connection_pool(&configuration.database);
-    let email_client = configuration.email_client.client();
 
-    worker_loop(connection_pool, email_client).await
+    // Consumers never block each other, dequeue_task skips rows locked by another consumer
+    let mut workers = JoinSet::new();
+    for _ in 0..configuration.worker.concurrency.max(1) {
+        workers.spawn(worker_loop(
+            connection_pool.clone(),
+            email_client.clone(),
+            configuration.application.base_url.clone(),
+            configuration.application.tracking_enabled,
+            configuration.worker.poll_interval(),
+            shutdown.clone(),
+        ));
+    }
+    workers.spawn(scheduler_loop(
+        connection_pool.clone(),
+        configuration.worker.scheduler_interval(),
+        shutdown.clone(),
+    ));
+    while let Some(outcome) = workers.join_next().await {
+        outcome??;
+    }
+    Ok(())
 }
 
+pub const ISSUE_DELIVERY_CHANNEL: &str = "issue_delivery_queue";
+
 async fn worker_loop(
     pool: PgPool,
-    email_client: EmailClient,
+    email_client: Arc<dyn EmailTransport>,
+    base_url: String,
+    tracking_enabled: bool,
+    poll_interval: Duration,
+    mut shutdown: ShutdownSignal,
 ) -> Result<(), anyhow::Error> {
-    loop {
-        match try_execute_task(&pool, &email_client).await {
+    let mut listener = None;
+    // Shutdown is only checked between tasks, an in-flight send always completes
+    while !shutdown.is_triggered() {
+        // Waiting for the send rate limit before a task is dequeued keeps consumers from
+        // holding a locked row and a connection while they wait
+        email_client.wait_until_ready().await;
+        // Issues go first, confirmation emails are sent once their queue is empty
+        let outcome = match try_execute_task(&pool, email_client.as_ref(), &base_url, tracking_enabled).await {
+            Ok(ExecutionOutcome::EmptyQueue) => {
+                try_send_confirmation_email(&pool, email_client.as_ref(), &base_url).await
+            }
+            outcome => outcome,
+        };
+        match outcome {
             Ok(ExecutionOutcome::EmptyQueue) => {
-                tokio::time::sleep(Duration::from_secs(10)).await;
+                if listener.is_none() {
+                    listener = listen_for_new_tasks(&pool).await;
+                }
+                match listener.as_mut() {
+                    Some(l) => wait_for_new_tasks(l, poll_interval, &mut shutdown).await,
+                    None => shutdown.sleep(poll_interval).await,
+                }
             },
             Err(_) => {
-                tokio::time::sleep(Duration::from_secs(1)).await;
+                shutdown.sleep(Duration::from_secs(1)).await;
             }
             Ok(ExecutionOutcome::TaskCompleted) => {},
         }
     }
+    Ok(())
+}
+
+async fn listen_for_new_tasks(pool: &PgPool) -> Option<PgListener> {
+    let listener = async {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(ISSUE_DELIVERY_CHANNEL).await?;
+        Ok::<_, sqlx::Error>(listener)
+    };
+    match listener.await {
+        Ok(listener) => Some(listener),
+        Err(e) => {
+            tracing::warn!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to listen for new delivery tasks. Falling back to polling."
+            );
+            None
+        }
+    }
+}
+
+// Wakes up on a notification, after the poll interval, or on shutdown.
+// Whichever comes first, the caller goes back to checking the queue.
+async fn wait_for_new_tasks(
+    listener: &mut PgListener,
+    poll_interval: Duration,
+    shutdown: &mut ShutdownSignal,
+) {
+    tokio::select! {
+        notification = listener.recv() => {
+            if let Err(e) = notification {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to receive a delivery task notification."
+                );
+            }
+        },
+        _ = shutdown.sleep(poll_interval) => {},
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn enqueue_delivery_tasks(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let n_tasks = sqlx::query!(
+        r#"
+        INSERT INTO issue_delivery_queue (
+        newsletter_issue_id,
+        subscriber_email
+        )
+        SELECT $1, email
+        FROM subscriptions
+        JOIN list_subscriptions ON list_subscriptions.subscriber_id = subscriptions.id
+        JOIN newsletter_issues ON newsletter_issues.list_id = list_subscriptions.list_id
+        LEFT JOIN segments ON segments.segment_id = newsletter_issues.segment_id
+        WHERE
+        newsletter_issues.newsletter_issue_id = $1 AND
+        subscriptions.status IN ('pending_confirmation', 'confirmed') AND
+        list_subscriptions.status = COALESCE(segments.subscriber_status, 'confirmed') AND
+        (subscriptions.paused_until IS NULL OR subscriptions.paused_until <= now()) AND
+        (
+            segments.segment_id IS NULL OR (
+                segments.required_tags <@ ARRAY(
+                    SELECT tag FROM subscriber_tags WHERE subscriber_id = subscriptions.id
+                ) AND
+                NOT segments.excluded_tags && ARRAY(
+                    SELECT tag FROM subscriber_tags WHERE subscriber_id = subscriptions.id
+                ) AND
+                list_subscriptions.subscribed_at >= COALESCE(segments.subscribed_after, '-infinity') AND
+                list_subscriptions.subscribed_at < COALESCE(segments.subscribed_before, 'infinity') AND
+                (
+                    segments.profile_field_key IS NULL OR EXISTS (
+                        SELECT 1
+                        FROM subscriber_profile_values
+                        JOIN profile_fields ON
+                        profile_fields.profile_field_id = subscriber_profile_values.profile_field_id
+                        WHERE
+                        subscriber_profile_values.subscriber_id = subscriptions.id AND
+                        profile_fields.key = segments.profile_field_key AND
+                        subscriber_profile_values.value = segments.profile_field_value
+                    )
+                )
+            )
+        )
+        "#,
+        newsletter_issue_id,
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    // With nobody to deliver to, the issue is sent as soon as it is published
+    let status = if n_tasks == 0 { "sent" } else { "sending" };
+    sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+        status = $2,
+        published_at = now()
+        WHERE newsletter_issue_id = $1
+        "#,
+        newsletter_issue_id,
+        status
+    )
+    .execute(&mut *transaction)
+    .await?;
+    assign_archive_slug(&mut *transaction, newsletter_issue_id).await?;
+    // Delivered to listening workers once the transaction commits
+    sqlx::query!(
+        "SELECT pg_notify($1, '')",
+        ISSUE_DELIVERY_CHANNEL,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
 }
 
 pub enum ExecutionOutcome {
@@ -50,48 +225,178 @@ pub enum ExecutionOutcome {
 )]
 pub async fn try_execute_task(
     pool: &PgPool,
-    email_client: &EmailClient
+    email_client: &dyn EmailTransport,
+    base_url: &str,
+    tracking_enabled: bool,
 ) -> Result<ExecutionOutcome, anyhow::Error> {
     let task = dequeue_task(pool).await?;
     if task.is_none() {
         return Ok(ExecutionOutcome::EmptyQueue);
     }
-    let (transaction, issue_id, email) = task.unwrap();
+    let (mut transaction, issue_id, email, n_retries) = task.unwrap();
 
     Span::current()
-        .record("newsletter_issue_id", &display(issue_id))
-        .record("subscriber_email", &display(&email));
+        .record("newsletter_issue_id", display(issue_id))
+        .record("subscriber_email", display(&email));
 
-    match SubscriberEmail::parse(email.clone()) {
-        Ok(email) => {
-            let issue = get_issue(pool, issue_id).await?;
-            if let Err(e) = email_client
-                .send_email(
-                    &email, 
-                    &issue.title,
-                    &issue.html_content,
-                    &issue.text_content
-                )
-                .await
-            {
+    let (outcome, tracking_tokens) = match deliver_issue(
+        pool,
+        email_client,
+        base_url,
+        tracking_enabled,
+        issue_id,
+        &email,
+    )
+    .await
+    {
+        Ok(delivery) => delivery,
+        Err(e) => {
+            let n_retries = n_retries + 1;
+            if n_retries >= MAX_RETRIES {
                 tracing::error!(
                     error.cause_chain = ?e,
                     error.message = %e,
-                    "Failed to deliver issue to a confirmed subscriber. Skipping."
+                    "Failed to deliver issue to a confirmed subscriber. Retry budget exhausted."
                 );
+                fail_task(transaction, issue_id, &email, n_retries, &e.to_string()).await?;
+                mark_issue_as_sent_if_delivered(pool, issue_id).await?;
+            } else {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to deliver issue to a confirmed subscriber. Retrying later."
+                );
+                reschedule_task(transaction, issue_id, &email, n_retries).await?;
             }
+            return Ok(ExecutionOutcome::TaskCompleted);
+        }
+    };
+    // A failed attempt renders fresh tokens when it is retried, only the sent ones are kept
+    store_tracking_tokens(&mut transaction, issue_id, &email, &tracking_tokens).await?;
+    delete_task(transaction, issue_id, &email, n_retries, outcome).await?;
+    mark_issue_as_sent_if_delivered(pool, issue_id).await?;
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+// Any error, whether it comes from rendering the issue or from sending it,
+// counts as a failed attempt against MAX_RETRIES
+async fn deliver_issue(
+    pool: &PgPool,
+    email_client: &dyn EmailTransport,
+    base_url: &str,
+    tracking_enabled: bool,
+    issue_id: Uuid,
+    email: &str,
+) -> Result<(DeliveryOutcome, Vec<TrackingToken>), anyhow::Error> {
+    let subscriber = get_confirmed_subscriber(pool, issue_id, email).await?;
+    let suppressed = is_email_suppressed(pool, email).await?;
+    let delivery = match (SubscriberEmail::parse(email.to_owned()), subscriber) {
+        (Ok(_), Some(_)) if suppressed => {
+            tracing::info!(
+                "Skipping a confirmed subscriber whose address is on the suppression list."
+            );
+            (DeliveryOutcome::Skipped, Vec::new())
+        }
+        (Ok(email), Some(subscriber)) => {
+            let issue = get_issue(pool, issue_id).await?;
+            let unsubscribe_link = format!(
+                "{}/subscriptions/unsubscribe?unsubscribe_token={}&list={}",
+                base_url,
+                subscriber.unsubscribe_token,
+                urlencoding::encode(&subscriber.list_name)
+            );
+            let preferences_link = format!(
+                "{}/subscriptions/preferences?unsubscribe_token={}",
+                base_url,
+                subscriber.unsubscribe_token
+            );
+            let profile = get_subscriber_profile(pool, email.as_ref()).await?;
+            let merge_tags = MergeTags {
+                name: &subscriber.name,
+                email: email.as_ref(),
+                unsubscribe_url: &unsubscribe_link,
+                profile: &profile,
+            };
+            let title = merge_tags.render_text(&issue.title);
+            let mut html_content = format!(
+                "{}<p><a href=\"{}\">Manage your preferences</a> | <a href=\"{}\">Unsubscribe</a></p>",
+                issue.html_content,
+                preferences_link,
+                unsubscribe_link
+            );
+            let mut text_content = format!(
+                "{}\n\nTo manage your preferences, visit {}\nTo unsubscribe, visit {}",
+                issue.text_content,
+                preferences_link,
+                unsubscribe_link
+            );
+            if let Some(slug) = issue.slug.as_ref().filter(|_| issue.is_public) {
+                let archive_link = archive_url(base_url, slug);
+                html_content = format!(
+                    "<p><a href=\"{}\">View this issue in your browser</a></p>{}",
+                    archive_link,
+                    html_content
+                );
+                text_content = format!(
+                    "View this issue in your browser: {}\n\n{}",
+                    archive_link,
+                    text_content
+                );
+            }
+            let (html_content, text_content) = EmailLayout::wrap(
+                issue.layout()?.as_ref(),
+                &html_content,
+                &text_content,
+            );
+            let html_content = merge_tags.render_html(&html_content);
+            let text_content = merge_tags.render_text(&text_content);
+            let (html_content, tracking_tokens) = if tracking_enabled && issue.is_tracked {
+                track_html(
+                    &html_content,
+                    base_url,
+                    &[&unsubscribe_link, &preferences_link],
+                )
+            } else {
+                (html_content, Vec::new())
+            };
+            let list_unsubscribe = format!("<{}>", unsubscribe_link);
+            let headers = [
+                EmailHeader {
+                    name: "List-Unsubscribe",
+                    value: &list_unsubscribe,
+                },
+                EmailHeader {
+                    name: "List-Unsubscribe-Post",
+                    value: "List-Unsubscribe=One-Click",
+                },
+            ];
+            email_client
+                .send_email(
+                    &email, 
+                    &title,
+                    &html_content,
+                    &text_content,
+                    &headers,
+                )
+                .await?;
+            (DeliveryOutcome::Delivered, tracking_tokens)
         },
-        Err(e) => {
+        (Ok(_), None) => {
+            tracing::info!(
+                "Skipping a subscriber who is no longer confirmed."
+            );
+            (DeliveryOutcome::Skipped, Vec::new())
+        },
+        (Err(e), _) => {
             tracing::error!(
                 error.cause_chain = ?e,
                 error.message = %e,
                 "Skipping a confirmed subscriber as stored contact details are invalid."
             );
+            (DeliveryOutcome::Skipped, Vec::new())
         }
-    }
-
-    delete_task(transaction, issue_id, &email).await?;
-    Ok(ExecutionOutcome::TaskCompleted)
+    };
+    Ok(delivery)
 }
 
 type PgTransaction = Transaction<'static, Postgres>;
@@ -99,12 +404,13 @@ type PgTransaction = Transaction<'static, Postgres>;
 #[tracing::instrument(skip_all)]
 async fn dequeue_task(
     pool: &PgPool,
-) -> Result<Option<(PgTransaction, Uuid, String)>, anyhow::Error> {
+) -> Result<Option<(PgTransaction, Uuid, String, i16)>, anyhow::Error> {
     let mut transaction = pool.begin().await?;
     let r = sqlx::query!(
         r#"
-        SELECT newsletter_issue_id, subscriber_email
+        SELECT newsletter_issue_id, subscriber_email, n_retries
         FROM issue_delivery_queue
+        WHERE execute_after <= now()
         FOR UPDATE
         SKIP LOCKED
         LIMIT 1
@@ -118,18 +424,57 @@ async fn dequeue_task(
             transaction,
             r.newsletter_issue_id,
             r.subscriber_email,
+            r.n_retries,
         )))
     } else {
         Ok(None)
     }
 }
 
+enum DeliveryOutcome {
+    Delivered,
+    Failed,
+    Skipped,
+}
+
+impl DeliveryOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryOutcome::Delivered => "delivered",
+            DeliveryOutcome::Failed => "failed",
+            DeliveryOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+// The outcome is logged in the same transaction, so every task that
+// leaves the queue is accounted for in issue_deliveries
 #[tracing::instrument(skip_all)]
 async fn delete_task(
     mut transaction: PgTransaction,
     issue_id: Uuid,
     email: &str,
+    n_retries: i16,
+    outcome: DeliveryOutcome,
 ) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO issue_deliveries (
+        newsletter_issue_id,
+        subscriber_email,
+        outcome,
+        n_retries,
+        completed_at
+        )
+        VALUES ($1, $2, $3, $4, now())
+        "#,
+        issue_id,
+        email,
+        outcome.as_str(),
+        n_retries
+    )
+    .execute(&mut transaction)
+    .await?;
     sqlx::query!(
         r#"
         DELETE FROM issue_delivery_queue
@@ -147,10 +492,171 @@ async fn delete_task(
     Ok(())
 }
 
+// Runs after the task has been committed, so whichever worker removes the
+// last task for an issue is guaranteed to see an empty queue
+#[tracing::instrument(skip_all)]
+async fn mark_issue_as_sent_if_delivered(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET status = 'sent'
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'sending' AND
+        NOT EXISTS (
+            SELECT 1 FROM issue_delivery_queue
+            WHERE newsletter_issue_id = $1
+        )
+        "#,
+        issue_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+struct ConfirmedSubscriber {
+    name: String,
+    unsubscribe_token: String,
+    list_name: String,
+}
+
+// Still subscribed to the list the issue was published to, with the status
+// its segment picks, and not paused
+#[tracing::instrument(skip_all)]
+async fn get_confirmed_subscriber(
+    pool: &PgPool,
+    issue_id: Uuid,
+    email: &str,
+) -> Result<Option<ConfirmedSubscriber>, anyhow::Error> {
+    let subscriber = sqlx::query_as!(
+        ConfirmedSubscriber,
+        r#"
+        SELECT
+        subscriptions.name,
+        unsubscribe_tokens.unsubscribe_token,
+        lists.name AS list_name
+        FROM unsubscribe_tokens
+        JOIN subscriptions ON subscriptions.id = unsubscribe_tokens.subscriber_id
+        JOIN list_subscriptions ON list_subscriptions.subscriber_id = subscriptions.id
+        JOIN lists ON lists.list_id = list_subscriptions.list_id
+        JOIN newsletter_issues ON newsletter_issues.list_id = lists.list_id
+        LEFT JOIN segments ON segments.segment_id = newsletter_issues.segment_id
+        WHERE
+        newsletter_issues.newsletter_issue_id = $1 AND
+        subscriptions.email = $2 AND
+        subscriptions.status IN ('pending_confirmation', 'confirmed') AND
+        list_subscriptions.status = COALESCE(segments.subscriber_status, 'confirmed') AND
+        (subscriptions.paused_until IS NULL OR subscriptions.paused_until <= now())
+        "#,
+        issue_id,
+        email
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscriber)
+}
+
+// Attempts made before a task is moved to issue_delivery_failures
+pub const MAX_RETRIES: i16 = 5;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+// Exponential backoff, with up to 50% random jitter to spread retries out
+pub fn retry_delay(n_retries: i16) -> Duration {
+    let exponent = n_retries.clamp(1, 16) as u32 - 1;
+    let delay = BASE_RETRY_DELAY
+        .saturating_mul(2u32.pow(exponent))
+        .min(MAX_RETRY_DELAY);
+    let jitter = rand::thread_rng().gen_range(0.0..0.5);
+    delay.mul_f64(1.0 + jitter)
+}
+
+#[tracing::instrument(skip_all)]
+async fn reschedule_task(
+    mut transaction: PgTransaction,
+    issue_id: Uuid,
+    email: &str,
+    n_retries: i16,
+) -> Result<(), anyhow::Error> {
+    let execute_after = Utc::now() + chrono::Duration::from_std(retry_delay(n_retries))?;
+    sqlx::query!(
+        r#"
+        UPDATE issue_delivery_queue
+        SET
+        n_retries = $3,
+        execute_after = $4
+        WHERE
+        newsletter_issue_id = $1 AND
+        subscriber_email = $2
+        "#,
+        issue_id,
+        email,
+        n_retries,
+        execute_after
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn fail_task(
+    mut transaction: PgTransaction,
+    issue_id: Uuid,
+    email: &str,
+    n_retries: i16,
+    last_error: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO issue_delivery_failures (
+        newsletter_issue_id,
+        subscriber_email,
+        n_retries,
+        last_error,
+        failed_at
+        )
+        VALUES ($1, $2, $3, $4, now())
+        "#,
+        issue_id,
+        email,
+        n_retries,
+        last_error
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    delete_task(transaction, issue_id, email, n_retries, DeliveryOutcome::Failed).await
+}
+
 struct NewsletterIssue {
     title: String,
     text_content: String,
     html_content: String,
+    is_public: bool,
+    // Opted in to open and click tracking when published
+    is_tracked: bool,
+    slug: Option<String>,
+    html_layout: Option<String>,
+    text_layout: Option<String>,
+}
+
+impl NewsletterIssue {
+    fn layout(&self) -> Result<Option<EmailLayout>, anyhow::Error> {
+        match (&self.html_layout, &self.text_layout) {
+            (Some(html), Some(text)) => EmailLayout::parse(html.clone(), text.clone())
+                .map(Some)
+                .map_err(anyhow::Error::msg),
+            _ => Ok(None),
+        }
+    }
 }
 
 #[tracing::instrument(skip_all)]
@@ -161,8 +667,17 @@ async fn get_issue(
     let issue = sqlx::query_as!(
         NewsletterIssue,
         r#"
-        SELECT title, text_content, html_content
+        SELECT
+        newsletter_issues.title,
+        newsletter_issues.text_content,
+        newsletter_issues.html_content,
+        newsletter_issues.is_public,
+        newsletter_issues.is_tracked,
+        newsletter_issues.slug,
+        email_templates.html_layout as "html_layout?",
+        email_templates.text_layout as "text_layout?"
         FROM newsletter_issues
+        LEFT JOIN email_templates USING (email_template_id)
         WHERE
         newsletter_issue_id = $1
         "#,
@@ -172,4 +687,26 @@ async fn get_issue(
     .await?;
 
     Ok(issue)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_delay, BASE_RETRY_DELAY, MAX_RETRY_DELAY};
+
+    #[test]
+    fn retry_delay_grows_exponentially() {
+        for n_retries in 1..5 {
+            let delay = retry_delay(n_retries);
+            let expected = BASE_RETRY_DELAY * 2u32.pow(n_retries as u32 - 1);
+            assert!(delay >= expected);
+            assert!(delay <= expected.mul_f64(1.5));
+        }
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        let delay = retry_delay(i16::MAX);
+        assert!(delay >= MAX_RETRY_DELAY);
+        assert!(delay <= MAX_RETRY_DELAY.mul_f64(1.5));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
+use crate::shutdown::ShutdownSignal;
+
+use sqlx::PgPool;
+
+use std::time::Duration;
+
+pub async fn scheduler_loop(
+    pool: PgPool,
+    interval: Duration,
+    mut shutdown: ShutdownSignal,
+) -> Result<(), anyhow::Error> {
+    while !shutdown.is_triggered() {
+        if let Err(e) = promote_due_issues(&pool).await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to publish scheduled newsletter issues."
+            );
+        }
+        shutdown.sleep(interval).await;
+    }
+    Ok(())
+}
+
+// Publishes every scheduled issue whose send time has passed, returning how many were published
+#[tracing::instrument(skip_all, err)]
+pub async fn promote_due_issues(pool: &PgPool) -> Result<usize, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let due_issues = sqlx::query!(
+        r#"
+        SELECT newsletter_issue_id
+        FROM newsletter_issues
+        WHERE
+        status = 'scheduled' AND
+        scheduled_for <= now()
+        FOR UPDATE
+        SKIP LOCKED
+        "#,
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+
+    for issue in &due_issues {
+        enqueue_delivery_tasks(&mut transaction, issue.newsletter_issue_id).await?;
+        tracing::info!(
+            newsletter_issue_id = %issue.newsletter_issue_id,
+            "Published a scheduled newsletter issue."
+        );
+    }
+
+    transaction.commit().await?;
+    Ok(due_issues.len())
+}
@@ -1,11 +1,23 @@
 pub mod authentication;
 pub mod configuration;
+pub mod confirmation_email_queue;
 pub mod domain;
 pub mod email_client;
+pub mod archive;
+pub mod email_templates;
 pub mod routes;
 pub mod startup;
+pub mod suppression;
+pub mod tracking;
 pub mod telemetry;
 pub mod session_state;
+pub mod shutdown;
 pub mod error;
 pub mod idempotency;
-pub mod issue_delivery_worker;
\ No newline at end of file
+pub mod issue_delivery_worker;
+pub mod issue_scheduler;
+pub mod lists;
+pub mod profile_fields;
+pub mod segments;
+pub mod subscriber_export;
+pub mod subscriber_import;
\ No newline at end of file
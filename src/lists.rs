@@ -0,0 +1,84 @@
+use crate::error::error_chain_fmt;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct MailingList {
+    pub list_id: Uuid,
+    pub name: String,
+    pub title: String,
+    pub is_default: bool,
+}
+
+#[tracing::instrument(name = "Get lists", skip(pool))]
+pub async fn get_lists(pool: &PgPool) -> Result<Vec<MailingList>, sqlx::Error> {
+    sqlx::query_as!(
+        MailingList,
+        r#"
+        SELECT list_id, name, title, is_default
+        FROM lists
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// An empty name stands for the default list
+#[tracing::instrument(name = "Get list by name", skip(pool))]
+pub async fn get_list_by_name(
+    pool: &PgPool,
+    name: &str,
+) -> Result<Option<MailingList>, sqlx::Error> {
+    sqlx::query_as!(
+        MailingList,
+        r#"
+        SELECT list_id, name, title, is_default
+        FROM lists
+        WHERE ($1 = '' AND is_default) OR name = $1
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+#[derive(thiserror::Error)]
+pub enum SelectListError {
+    #[error("The selected list no longer exists.")]
+    UnknownList,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for SelectListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+// Looks up the list picked in a form, an empty value means the default list
+#[tracing::instrument(name = "Get selected list", skip(pool))]
+pub async fn get_selected_list(
+    pool: &PgPool,
+    list_id: &str,
+) -> Result<Uuid, SelectListError> {
+    let list_id = if list_id.is_empty() {
+        None
+    } else {
+        Some(Uuid::parse_str(list_id).map_err(|_| SelectListError::UnknownList)?)
+    };
+    let list = sqlx::query!(
+        r#"
+        SELECT list_id
+        FROM lists
+        WHERE ($1::uuid IS NULL AND is_default) OR list_id = $1
+        "#,
+        list_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(anyhow::Error::from)?
+    .ok_or(SelectListError::UnknownList)?;
+    Ok(list.list_id)
+}
@@ -2,6 +2,7 @@ use zero2prod::startup::Application;
 use zero2prod::configuration::get_configuration;
 use zero2prod::telemetry::{get_subscriber, init_subscriber};
 use zero2prod::issue_delivery_worker::run_worker_until_stopped;
+use zero2prod::shutdown::{shutdown_channel, wait_for_shutdown_signal};
 
 use tokio::task::JoinError;
 
@@ -15,14 +16,47 @@ async fn main() -> anyhow::Result<()> {
 
     // Setup server
     let configuration = get_configuration().expect("Failed to read configuration");
-    let application = Application::build(configuration.clone()).await?;
-    let application_task = tokio::spawn(application.run_until_stopped());
-    let worker_task = tokio::spawn(run_worker_until_stopped(configuration));
+    let shutdown_timeout = configuration.application.shutdown_timeout();
+    let (shutdown_trigger, shutdown_signal) = shutdown_channel();
+    let email_client = configuration.email_client.clone().client();
+    let application = Application::build(configuration.clone(), email_client.clone()).await?;
+    let mut application_task = tokio::spawn(application.run_until_stopped(shutdown_signal.clone()));
+    let mut worker_task = tokio::spawn(
+        run_worker_until_stopped(configuration, email_client, shutdown_signal)
+    );
 
+    let mut application_exited = false;
+    let mut worker_exited = false;
     tokio::select! {
-        o = application_task => report_exit("API", o),
-        o = worker_task => report_exit("Background worker", o),
+        o = &mut application_task => {
+            report_exit("API", o);
+            application_exited = true;
+        },
+        o = &mut worker_task => {
+            report_exit("Background worker", o);
+            worker_exited = true;
+        },
+        _ = wait_for_shutdown_signal() => {
+            tracing::info!("Shutdown signal received, draining in-flight work");
+        },
+    };
+
+    // Stop whichever task is still running, allowing it to finish its current work
+    shutdown_trigger.trigger();
+    let drain = async {
+        if !application_exited {
+            report_exit("API", application_task.await);
+        }
+        if !worker_exited {
+            report_exit("Background worker", worker_task.await);
+        }
     };
+    if tokio::time::timeout(shutdown_timeout, drain).await.is_err() {
+        tracing::warn!(
+            "Tasks did not finish within {} seconds, exiting anyway",
+            shutdown_timeout.as_secs()
+        );
+    }
 
     Ok(())
 }
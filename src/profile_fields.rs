@@ -0,0 +1,80 @@
+use crate::domain::{ProfileField, ProfileFieldType, SubscriberProfile};
+
+use anyhow::Context;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[tracing::instrument(name = "Get profile fields", skip(pool))]
+pub async fn get_profile_fields(pool: &PgPool) -> Result<Vec<ProfileField>, anyhow::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT key, label, field_type, options, is_required
+        FROM profile_fields
+        ORDER BY created_at, key
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to retrieve profile fields.")?;
+    rows.into_iter()
+        .map(|r| {
+            let field_type = ProfileFieldType::parse(&r.field_type, r.options)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("The {} profile field is invalid.", r.key))?;
+            Ok(ProfileField {
+                key: r.key,
+                label: r.label,
+                field_type,
+                is_required: r.is_required,
+            })
+        })
+        .collect()
+}
+
+#[tracing::instrument(name = "Store subscriber profile", skip(transaction, profile))]
+pub async fn store_subscriber_profile(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    profile: &SubscriberProfile,
+) -> Result<(), sqlx::Error> {
+    for (key, value) in profile.values() {
+        sqlx::query!(
+            r#"
+            INSERT INTO subscriber_profile_values (subscriber_id, profile_field_id, value)
+            SELECT $1, profile_field_id, $3
+            FROM profile_fields
+            WHERE key = $2
+            ON CONFLICT (subscriber_id, profile_field_id) DO UPDATE
+            SET value = EXCLUDED.value
+            "#,
+            subscriber_id,
+            key,
+            value
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    Ok(())
+}
+
+// Key and value pairs, as expected by MergeTags
+#[tracing::instrument(name = "Get subscriber profile", skip(executor))]
+pub async fn get_subscriber_profile(
+    executor: impl PgExecutor<'_>,
+    email: &str,
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT profile_fields.key, subscriber_profile_values.value
+        FROM subscriber_profile_values
+        JOIN profile_fields ON
+        profile_fields.profile_field_id = subscriber_profile_values.profile_field_id
+        JOIN subscriptions ON subscriptions.id = subscriber_profile_values.subscriber_id
+        WHERE subscriptions.email = $1
+        "#,
+        email
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.key, r.value)).collect())
+}
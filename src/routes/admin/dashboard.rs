@@ -48,6 +48,18 @@ pub async fn admin_dashboard(
                         </form>
                     </li>
                     <li><a href="/admin/newsletters">Send a newsletter</a></li>
+                    <li><a href="/admin/drafts">Drafts</a></li>
+                    <li><a href="/admin/templates">Email layouts</a></li>
+                    <li><a href="/admin/newsletters/scheduled">View scheduled newsletters</a></li>
+                    <li><a href="/admin/issues">View published newsletters</a></li>
+                    <li><a href="/admin/delivery_failures">View delivery failures</a></li>
+                    <li><a href="/admin/lists">Lists</a></li>
+                    <li><a href="/admin/subscribers/import">Import subscribers</a></li>
+                    <li><a href="/admin/subscribers/export">Export subscribers</a></li>
+                    <li><a href="/admin/segments">Segments</a></li>
+                    <li><a href="/admin/profile_fields">Profile fields</a></li>
+                    <li><a href="/admin/tags">Tags</a></li>
+                    <li><a href="/admin/suppressions">Suppression list</a></li>
                 </ol>
             </body>
             </html>"#,
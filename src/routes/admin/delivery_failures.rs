@@ -0,0 +1,91 @@
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    http::{StatusCode, header::{self, HeaderMap, HeaderValue}},
+    response::IntoResponse,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn delivery_failures(
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let failures = get_delivery_failures(&pool).await?;
+
+    let mut rows_html = String::new();
+    for failure in failures {
+        writeln!(
+            rows_html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            htmlescape::encode_minimal(&failure.title),
+            htmlescape::encode_minimal(&failure.subscriber_email),
+            failure.n_retries,
+            failure.failed_at.to_rfc3339(),
+            htmlescape::encode_minimal(&failure.last_error),
+        ).unwrap();
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Delivery failures</title>
+            </head>
+            <body>
+                <p>Deliveries that could not be completed after all retries:</p>
+                <table>
+                    <tr><th>Issue</th><th>Subscriber</th><th>Attempts</th><th>Failed at</th><th>Last error</th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ))
+}
+
+struct DeliveryFailure {
+    title: String,
+    subscriber_email: String,
+    n_retries: i16,
+    failed_at: DateTime<Utc>,
+    last_error: String,
+}
+
+#[tracing::instrument(name = "Get delivery failures", skip(pool))]
+async fn get_delivery_failures(
+    pool: &PgPool,
+) -> Result<Vec<DeliveryFailure>, anyhow::Error> {
+    let failures = sqlx::query_as!(
+        DeliveryFailure,
+        r#"
+        SELECT
+        newsletter_issues.title,
+        issue_delivery_failures.subscriber_email,
+        issue_delivery_failures.n_retries,
+        issue_delivery_failures.failed_at,
+        issue_delivery_failures.last_error
+        FROM issue_delivery_failures
+        JOIN newsletter_issues USING (newsletter_issue_id)
+        ORDER BY issue_delivery_failures.failed_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve delivery failures.")?;
+    Ok(failures)
+}
@@ -0,0 +1,335 @@
+use crate::email_templates::get_email_templates;
+use crate::error::ResponseError;
+use crate::lists::get_lists;
+use crate::routes::admin::lists::list_options_html;
+use crate::routes::admin::segments::segment_options_html;
+use crate::segments::get_segments;
+use crate::routes::admin::templates::email_template_options_html;
+use crate::startup::TrackingEnabled;
+
+use anyhow::Context;
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::{self, HeaderMap, HeaderValue}},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn drafts(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let drafts = get_drafts(&pool).await?;
+    let mut rows_html = String::new();
+    for draft in drafts {
+        let id = draft.newsletter_issue_id;
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td><a href="/admin/drafts/{id}/edit">Edit</a></td>
+                <td><a href="/admin/drafts/{id}/preview">Preview</a></td>
+                <td>
+                    <form action="/admin/drafts/{id}/delete" method="post">
+                        <button type="submit">Delete</button>
+                    </form>
+                </td>
+            </tr>"#,
+            htmlescape::encode_minimal(&draft.title),
+        ).unwrap();
+    }
+
+    let msg_html = flash_messages_html(&flash_messages);
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Drafts</title>
+            </head>
+            <body>
+                {msg_html}
+                <p><a href="/admin/drafts/new">New draft</a></p>
+                <table>
+                    <tr><th>Title</th><th></th><th></th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ))
+}
+
+pub async fn new_draft_form(
+    flash_messages: IncomingFlashes,
+) -> impl IntoResponse {
+    let msg_html = flash_messages_html(&flash_messages);
+    let form_html = draft_form_html("/admin/drafts", &Draft::default());
+    (
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>New draft</title>
+            </head>
+            <body>
+                {msg_html}
+                {form_html}
+                <p><a href="/admin/drafts">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    )
+}
+
+pub async fn edit_draft_form(
+    flash_messages: IncomingFlashes,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let draft = match get_draft(&pool, issue_id).await? {
+        Some(draft) => draft,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+
+    let msg_html = flash_messages_html(&flash_messages);
+    let form_html = draft_form_html(&format!("/admin/drafts/{}", issue_id), &draft);
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Edit draft</title>
+            </head>
+            <body>
+                {msg_html}
+                {form_html}
+                <p><a href="/admin/drafts/{issue_id}/preview">Preview</a></p>
+                <p><a href="/admin/drafts">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ).into_response())
+}
+
+pub async fn preview_draft(
+    flash_messages: IncomingFlashes,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(tracking_enabled): Extension<TrackingEnabled>,
+) -> Result<Response, ResponseError> {
+    let draft = match get_draft(&pool, issue_id).await? {
+        Some(draft) => draft,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+
+    let templates = get_email_templates(&pool)
+        .await
+        .context("Failed to retrieve email templates.")?;
+    let template_options_html = email_template_options_html(&templates);
+    let lists = get_lists(&pool)
+        .await
+        .context("Failed to retrieve lists.")?;
+    let list_options_html = list_options_html(&lists);
+    let segments = get_segments(&pool)
+        .await
+        .context("Failed to retrieve segments.")?;
+    let segment_options_html = segment_options_html(&segments);
+    // Issues cannot opt in while tracking is disabled in the settings
+    let tracking_html = if tracking_enabled.0 {
+        r#"<label>Track opens and clicks
+                        <input type="checkbox" name="is_tracked">
+                    </label>"#
+    } else {
+        ""
+    };
+    let msg_html = flash_messages_html(&flash_messages);
+    let title = htmlescape::encode_minimal(&draft.title);
+    // Sandboxed so the issue's markup and styles cannot affect the admin page
+    let html_content = htmlescape::encode_attribute(&draft.html_content);
+    let text_content = htmlescape::encode_minimal(&draft.text_content);
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Preview draft</title>
+            </head>
+            <body>
+                {msg_html}
+                <h1>{title}</h1>
+                <h2>HTML</h2>
+                <iframe sandbox srcdoc="{html_content}" width="100%" height="400"></iframe>
+                <h2>Plain text</h2>
+                <pre>{text_content}</pre>
+                <form action="/admin/drafts/{issue_id}/publish" method="post">
+                    <label>Send at (UTC, leave empty to send now)
+                        <input type="datetime-local" name="scheduled_for">
+                    </label>
+                    <label>List
+                        <select name="list_id">{list_options_html}</select>
+                    </label>
+                    <label>Segment
+                        <select name="segment_id">{segment_options_html}</select>
+                    </label>
+                    <label>Layout
+                        <select name="email_template_id">{template_options_html}</select>
+                    </label>
+                    <label>Show in the public archive
+                        <input type="checkbox" name="is_public">
+                    </label>
+                    {tracking_html}
+                    <button type="submit">Publish newsletter</button>
+                </form>
+                <form action="/admin/drafts/{issue_id}/test" method="post">
+                    <label>Test recipients
+                        <input
+                            type="text"
+                            placeholder="Comma separated email addresses"
+                            name="test_recipients"
+                        >
+                    </label>
+                    <label>Layout
+                        <select name="email_template_id">{template_options_html}</select>
+                    </label>
+                    <button type="submit">Send test copy</button>
+                </form>
+                <p><a href="/admin/drafts/{issue_id}/edit">Edit</a></p>
+                <p><a href="/admin/drafts">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ).into_response())
+}
+
+fn draft_form_html(action: &str, draft: &Draft) -> String {
+    format!(
+        r#"<form action="{action}" method="post">
+            <label>Title
+                <input
+                    type="text"
+                    placeholder="Enter newsletter title"
+                    name="title"
+                    value="{}"
+                >
+            </label>
+            <br>
+            <label>Contents in Markdown
+                <textarea
+                    placeholder="Enter content in Markdown, or fill in both fields below instead"
+                    name="markdown"
+                >{}</textarea>
+            </label>
+            <br>
+            <label>Contents
+                <textarea
+                    placeholder="Enter content in HTML form"
+                    name="html"
+                >{}</textarea>
+            </label>
+            <br>
+            <label>Contents raw text
+                <textarea
+                    placeholder="Enter content in text form"
+                    name="text"
+                >{}</textarea>
+            </label>
+            <br>
+            <button type="submit">Save draft</button>
+        </form>"#,
+        htmlescape::encode_attribute(&draft.title),
+        htmlescape::encode_minimal(draft.markdown_content.as_deref().unwrap_or_default()),
+        htmlescape::encode_minimal(&draft.html_content),
+        htmlescape::encode_minimal(&draft.text_content),
+    )
+}
+
+fn flash_messages_html(flash_messages: &IncomingFlashes) -> String {
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    msg_html
+}
+
+fn html_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    headers
+}
+
+#[derive(Default)]
+pub struct Draft {
+    pub title: String,
+    pub text_content: String,
+    pub html_content: String,
+    pub markdown_content: Option<String>,
+}
+
+#[tracing::instrument(name = "Get draft", skip(pool))]
+pub async fn get_draft(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<Option<Draft>, anyhow::Error> {
+    let draft = sqlx::query_as!(
+        Draft,
+        r#"
+        SELECT title, text_content, html_content, markdown_content
+        FROM newsletter_issues
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'draft'
+        "#,
+        issue_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to perform a query to retrieve a draft.")?;
+    Ok(draft)
+}
+
+struct DraftSummary {
+    newsletter_issue_id: Uuid,
+    title: String,
+}
+
+#[tracing::instrument(name = "Get drafts", skip(pool))]
+async fn get_drafts(
+    pool: &PgPool,
+) -> Result<Vec<DraftSummary>, anyhow::Error> {
+    let drafts = sqlx::query_as!(
+        DraftSummary,
+        r#"
+        SELECT newsletter_issue_id, title
+        FROM newsletter_issues
+        WHERE status = 'draft'
+        ORDER BY title
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve drafts.")?;
+    Ok(drafts)
+}
@@ -0,0 +1,332 @@
+use crate::domain::{validate_issue_body, validate_merge_tags, EmailLayout, IssueContent};
+use crate::email_client::EmailTransport;
+use crate::email_templates::{get_selected_email_layout, SelectLayoutError};
+use crate::error::ResponseError;
+use crate::lists::{get_selected_list, SelectListError};
+use crate::profile_fields::get_profile_fields;
+use crate::segments::{get_selected_segment, SelectSegmentError};
+use crate::startup::ApplicationBaseUrl;
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
+use crate::routes::admin::newsletters::{parse_scheduled_for, send_test_copy, SCHEDULED_FOR_FORMAT};
+
+use anyhow::Context;
+use super::get::get_draft;
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+    Extension,
+    Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct DraftFormData {
+    title: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    html: String,
+    #[serde(default)]
+    markdown: String,
+}
+
+#[tracing::instrument(name = "Create a draft", skip_all)]
+pub async fn create_draft(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<DraftFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let issue_id = Uuid::new_v4();
+    let content = IssueContent::new(form.markdown, form.html, form.text);
+    sqlx::query!(
+        r#"
+        INSERT INTO newsletter_issues (
+        newsletter_issue_id,
+        title,
+        text_content,
+        html_content,
+        markdown_content,
+        list_id,
+        status
+        )
+        VALUES ($1, $2, $3, $4, $5, (SELECT list_id FROM lists WHERE is_default), 'draft')
+        "#,
+        issue_id,
+        form.title,
+        content.text,
+        content.html,
+        content.markdown
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to store draft.")?;
+
+    let flash = flash.info("The draft has been saved.");
+    Ok((flash, Redirect::to(&format!("/admin/drafts/{}/edit", issue_id))))
+}
+
+#[tracing::instrument(name = "Update a draft", skip(flash, pool, form))]
+pub async fn update_draft(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<DraftFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let content = IssueContent::new(form.markdown, form.html, form.text);
+    let updated = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+        title = $2,
+        text_content = $3,
+        html_content = $4,
+        markdown_content = $5
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'draft'
+        "#,
+        issue_id,
+        form.title,
+        content.text,
+        content.html,
+        content.markdown
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to update draft.")?
+    .rows_affected() > 0;
+
+    if !updated {
+        let flash = flash.error("The newsletter issue is no longer a draft.");
+        return Ok((flash, Redirect::to("/admin/drafts")));
+    }
+    let flash = flash.info("The draft has been saved.");
+    Ok((flash, Redirect::to(&format!("/admin/drafts/{}/edit", issue_id))))
+}
+
+#[tracing::instrument(name = "Delete a draft", skip(flash, pool))]
+pub async fn delete_draft(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM newsletter_issues
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'draft'
+        "#,
+        issue_id
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to delete draft.")?
+    .rows_affected() > 0;
+
+    let flash = if deleted {
+        flash.info("The draft has been deleted.")
+    } else {
+        flash.error("The newsletter issue is no longer a draft.")
+    };
+    Ok((flash, Redirect::to("/admin/drafts")))
+}
+
+#[derive(serde::Deserialize)]
+pub struct PublishDraftFormData {
+    #[serde(default)]
+    scheduled_for: String,
+    #[serde(default)]
+    email_template_id: String,
+    #[serde(default)]
+    list_id: String,
+    #[serde(default)]
+    segment_id: String,
+    is_public: Option<String>,
+    is_tracked: Option<String>,
+}
+
+// Only a draft can be published, so submitting the form twice sends the issue once
+#[tracing::instrument(name = "Publish a draft", skip(flash, pool, form))]
+pub async fn publish_draft(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<PublishDraftFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let preview_url = format!("/admin/drafts/{}/preview", issue_id);
+    let scheduled_for = match parse_scheduled_for(&form.scheduled_for) {
+        Ok(scheduled_for) => scheduled_for,
+        Err(e) => return Ok((flash.error(e), Redirect::to(&preview_url))),
+    };
+    let email_template_id = match get_selected_email_layout(&pool, &form.email_template_id).await {
+        Ok(layout) => layout.map(|(id, _)| id),
+        Err(e @ SelectLayoutError::UnknownLayout) => {
+            return Ok((flash.error(e.to_string()), Redirect::to(&preview_url)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let list_id = match get_selected_list(&pool, &form.list_id).await {
+        Ok(list_id) => list_id,
+        Err(e @ SelectListError::UnknownList) => {
+            return Ok((flash.error(e.to_string()), Redirect::to(&preview_url)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let segment_id = match get_selected_segment(&pool, &form.segment_id).await {
+        Ok(segment_id) => segment_id,
+        Err(e @ SelectSegmentError::UnknownSegment) => {
+            return Ok((flash.error(e.to_string()), Redirect::to(&preview_url)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let draft = sqlx::query!(
+        r#"
+        SELECT title, text_content, html_content
+        FROM newsletter_issues
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'draft'
+        FOR UPDATE
+        "#,
+        issue_id
+    )
+    .fetch_optional(&mut transaction)
+    .await
+    .context("Failed to retrieve draft.")?;
+    let draft = match draft {
+        Some(draft) => draft,
+        None => {
+            let flash = flash.error("The newsletter issue is no longer a draft.");
+            return Ok((flash, Redirect::to("/admin/drafts")));
+        }
+    };
+    let profile_fields = get_profile_fields(&pool).await?;
+    if let Err(e) = validate_issue_body(&draft.html_content, &draft.text_content).and_then(|_| {
+        [&draft.title, &draft.html_content, &draft.text_content]
+            .into_iter()
+            .try_for_each(|t| validate_merge_tags(t, &profile_fields))
+    }) {
+        return Ok((flash.error(e), Redirect::to(&preview_url)));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+        email_template_id = $2,
+        list_id = $3,
+        segment_id = $4,
+        is_public = $5,
+        is_tracked = $6
+        WHERE newsletter_issue_id = $1
+        "#,
+        issue_id,
+        email_template_id,
+        list_id,
+        segment_id,
+        form.is_public.is_some(),
+        form.is_tracked.is_some()
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to set the publishing options of a draft.")?;
+    let flash = match scheduled_for {
+        Some(scheduled_for) => {
+            sqlx::query!(
+                r#"
+                UPDATE newsletter_issues
+                SET
+                status = 'scheduled',
+                scheduled_for = $2
+                WHERE newsletter_issue_id = $1
+                "#,
+                issue_id,
+                scheduled_for
+            )
+            .execute(&mut transaction)
+            .await
+            .context("Failed to schedule draft.")?;
+            flash.info(format!(
+                "The newsletter issue has been scheduled for {} UTC.",
+                scheduled_for.format(SCHEDULED_FOR_FORMAT)
+            ))
+        }
+        None => {
+            enqueue_delivery_tasks(&mut transaction, issue_id)
+                .await
+                .context("Failed to enqueue delivery tasks")?;
+            flash.info("The newsletter issue has been accepted - emails will go out shortly.")
+        }
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to publish a draft.")?;
+    Ok((flash, Redirect::to("/admin/drafts")))
+}
+
+#[derive(serde::Deserialize)]
+pub struct TestDraftFormData {
+    test_recipients: String,
+    #[serde(default)]
+    email_template_id: String,
+}
+
+#[tracing::instrument(name = "Send a test copy of a draft", skip(flash, pool, email_client, base_url, form))]
+pub async fn send_test_draft(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(email_client): Extension<Arc<dyn EmailTransport>>,
+    Extension(base_url): Extension<ApplicationBaseUrl>,
+    Form(form): Form<TestDraftFormData>,
+) -> Result<Response, ResponseError> {
+    let draft = match get_draft(&pool, issue_id).await? {
+        Some(draft) => draft,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let redirect = Redirect::to(&format!("/admin/drafts/{}/preview", issue_id));
+    let profile_fields = get_profile_fields(&pool).await?;
+    if let Err(e) = [&draft.title, &draft.html_content, &draft.text_content]
+        .into_iter()
+        .try_for_each(|t| validate_merge_tags(t, &profile_fields))
+    {
+        return Ok((flash.error(e), redirect).into_response());
+    }
+    let layout = match get_selected_email_layout(&pool, &form.email_template_id).await {
+        Ok(layout) => layout.map(|(_, layout)| layout),
+        Err(e @ SelectLayoutError::UnknownLayout) => {
+            return Ok((flash.error(e.to_string()), redirect).into_response());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let (html_content, text_content) = EmailLayout::wrap(
+        layout.as_ref(),
+        &draft.html_content,
+        &draft.text_content,
+    );
+    let flash = send_test_copy(
+        flash,
+        &pool,
+        email_client.as_ref(),
+        &base_url.0,
+        &form.test_recipients,
+        &draft.title,
+        &html_content,
+        &text_content,
+    )
+    .await;
+    Ok((flash, redirect).into_response())
+}
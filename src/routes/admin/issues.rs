@@ -0,0 +1,336 @@
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::{self, HeaderMap, HeaderValue}},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn issues(
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let issues = get_published_issues(&pool).await?;
+
+    let mut rows_html = String::new();
+    for issue in issues {
+        writeln!(
+            rows_html,
+            r#"<tr><td><a href="/admin/issues/{}">{}</a></td><td>{}</td><td>{}</td></tr>"#,
+            issue.newsletter_issue_id,
+            htmlescape::encode_minimal(&issue.title),
+            issue.status,
+            issue.published_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ).unwrap();
+    }
+
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Published newsletters</title>
+            </head>
+            <body>
+                <table>
+                    <tr><th>Title</th><th>Status</th><th>Published at</th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ).into_response())
+}
+
+pub async fn issue_delivery_status(
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let issue = match get_issue(&pool, issue_id).await? {
+        Some(issue) => issue,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let stats = get_delivery_stats(&pool, issue_id).await?;
+
+    let mut failures_html = String::new();
+    for failure in get_issue_delivery_failures(&pool, issue_id).await? {
+        writeln!(
+            failures_html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            htmlescape::encode_minimal(&failure.subscriber_email),
+            failure.n_retries,
+            failure.failed_at.to_rfc3339(),
+            htmlescape::encode_minimal(&failure.last_error),
+        ).unwrap();
+    }
+
+    let opened = get_open_count(&pool, issue_id).await?;
+    let open_rate = if stats.delivered > 0 {
+        format!("{:.1}%", opened as f64 * 100.0 / stats.delivered as f64)
+    } else {
+        "n/a".into()
+    };
+    let mut clicks_html = String::new();
+    for link in get_link_clicks(&pool, issue_id).await? {
+        writeln!(
+            clicks_html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            htmlescape::encode_minimal(&link.url),
+            link.clicks,
+            link.unique_clicks,
+        ).unwrap();
+    }
+
+    let title = htmlescape::encode_minimal(&issue.title);
+    let status = issue.status;
+    let published_at = issue.published_at.map(|t| t.to_rfc3339()).unwrap_or_default();
+    let total = stats.delivered + stats.failed + stats.skipped + stats.pending;
+    let DeliveryStats { delivered, failed, skipped, pending, retrying } = stats;
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>{title}</title>
+            </head>
+            <body>
+                <h1>{title}</h1>
+                <p>Status: {status}</p>
+                <p>Published at: {published_at}</p>
+                <table>
+                    <tr><th>Total recipients</th><td>{total}</td></tr>
+                    <tr><th>Delivered</th><td>{delivered}</td></tr>
+                    <tr><th>Failed</th><td>{failed}</td></tr>
+                    <tr><th>Skipped</th><td>{skipped}</td></tr>
+                    <tr><th>Pending</th><td>{pending}</td></tr>
+                    <tr><th>Pending after a failed attempt</th><td>{retrying}</td></tr>
+                </table>
+                <h2>Engagement</h2>
+                <p>Only recorded when open and click tracking is enabled.</p>
+                <table>
+                    <tr><th>Opened</th><td>{opened}</td></tr>
+                    <tr><th>Open rate</th><td>{open_rate}</td></tr>
+                </table>
+                <table>
+                    <tr><th>Link</th><th>Clicks</th><th>Unique clicks</th></tr>
+                    {clicks_html}
+                </table>
+                <h2>Failed deliveries</h2>
+                <table>
+                    <tr><th>Subscriber</th><th>Attempts</th><th>Failed at</th><th>Last error</th></tr>
+                    {failures_html}
+                </table>
+                <p><a href="/admin/issues">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ).into_response())
+}
+
+fn html_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    headers
+}
+
+struct PublishedIssue {
+    newsletter_issue_id: Uuid,
+    title: String,
+    status: String,
+    published_at: Option<DateTime<Utc>>,
+}
+
+#[tracing::instrument(name = "Get published issues", skip(pool))]
+async fn get_published_issues(
+    pool: &PgPool,
+) -> Result<Vec<PublishedIssue>, anyhow::Error> {
+    let issues = sqlx::query_as!(
+        PublishedIssue,
+        r#"
+        SELECT
+        newsletter_issue_id,
+        title,
+        status,
+        published_at::timestamptz as published_at
+        FROM newsletter_issues
+        WHERE status IN ('sending', 'sent')
+        ORDER BY published_at::timestamptz DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to retrieve published issues.")?;
+    Ok(issues)
+}
+
+#[tracing::instrument(name = "Get issue", skip(pool))]
+async fn get_issue(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<Option<PublishedIssue>, anyhow::Error> {
+    let issue = sqlx::query_as!(
+        PublishedIssue,
+        r#"
+        SELECT
+        newsletter_issue_id,
+        title,
+        status,
+        published_at::timestamptz as published_at
+        FROM newsletter_issues
+        WHERE newsletter_issue_id = $1
+        "#,
+        issue_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve issue.")?;
+    Ok(issue)
+}
+
+struct DeliveryStats {
+    delivered: i64,
+    failed: i64,
+    skipped: i64,
+    pending: i64,
+    retrying: i64,
+}
+
+// Completed tasks are logged in issue_deliveries, the rest are still queued
+#[tracing::instrument(name = "Get delivery stats", skip(pool))]
+async fn get_delivery_stats(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<DeliveryStats, anyhow::Error> {
+    let stats = sqlx::query_as!(
+        DeliveryStats,
+        r#"
+        SELECT
+        (
+            SELECT COUNT(*) FROM issue_deliveries
+            WHERE newsletter_issue_id = $1 AND outcome = 'delivered'
+        ) as "delivered!",
+        (
+            SELECT COUNT(*) FROM issue_deliveries
+            WHERE newsletter_issue_id = $1 AND outcome = 'failed'
+        ) as "failed!",
+        (
+            SELECT COUNT(*) FROM issue_deliveries
+            WHERE newsletter_issue_id = $1 AND outcome = 'skipped'
+        ) as "skipped!",
+        (
+            SELECT COUNT(*) FROM issue_delivery_queue
+            WHERE newsletter_issue_id = $1
+        ) as "pending!",
+        (
+            SELECT COUNT(*) FROM issue_delivery_queue
+            WHERE newsletter_issue_id = $1 AND n_retries > 0
+        ) as "retrying!"
+        "#,
+        issue_id
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to retrieve delivery stats.")?;
+    Ok(stats)
+}
+
+struct IssueDeliveryFailure {
+    subscriber_email: String,
+    n_retries: i16,
+    failed_at: DateTime<Utc>,
+    last_error: String,
+}
+
+#[tracing::instrument(name = "Get issue delivery failures", skip(pool))]
+async fn get_issue_delivery_failures(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<Vec<IssueDeliveryFailure>, anyhow::Error> {
+    let failures = sqlx::query_as!(
+        IssueDeliveryFailure,
+        r#"
+        SELECT subscriber_email, n_retries, failed_at, last_error
+        FROM issue_delivery_failures
+        WHERE newsletter_issue_id = $1
+        ORDER BY failed_at DESC
+        "#,
+        issue_id
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to retrieve delivery failures.")?;
+    Ok(failures)
+}
+
+// Clicking a link means the email was opened, even if images were blocked
+#[tracing::instrument(name = "Get open count", skip(pool))]
+async fn get_open_count(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<i64, anyhow::Error> {
+    let opened = sqlx::query!(
+        r#"
+        SELECT COUNT(DISTINCT tracking_tokens.subscriber_email) as "opened!"
+        FROM tracking_events
+        JOIN tracking_tokens USING (tracking_token)
+        WHERE tracking_tokens.newsletter_issue_id = $1
+        "#,
+        issue_id
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to retrieve open count.")?
+    .opened;
+    Ok(opened)
+}
+
+struct LinkClicks {
+    url: String,
+    clicks: i64,
+    unique_clicks: i64,
+}
+
+#[tracing::instrument(name = "Get link clicks", skip(pool))]
+async fn get_link_clicks(
+    pool: &PgPool,
+    issue_id: Uuid,
+) -> Result<Vec<LinkClicks>, anyhow::Error> {
+    let clicks = sqlx::query_as!(
+        LinkClicks,
+        r#"
+        SELECT
+        tracking_tokens.url as "url!",
+        COUNT(*) as "clicks!",
+        COUNT(DISTINCT tracking_tokens.subscriber_email) as "unique_clicks!"
+        FROM tracking_events
+        JOIN tracking_tokens USING (tracking_token)
+        WHERE
+        tracking_tokens.newsletter_issue_id = $1 AND
+        tracking_tokens.url IS NOT NULL
+        GROUP BY tracking_tokens.url
+        ORDER BY 2 DESC, 1
+        "#,
+        issue_id
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to retrieve link clicks.")?;
+    Ok(clicks)
+}
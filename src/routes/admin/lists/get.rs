@@ -0,0 +1,131 @@
+use crate::error::ResponseError;
+use crate::lists::MailingList;
+
+use anyhow::Context;
+use axum::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn lists(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let lists = get_list_summaries(&pool)
+        .await
+        .context("Failed to retrieve lists.")?;
+    let mut rows_html = String::new();
+    for list in lists {
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+            </tr>"#,
+            htmlescape::encode_minimal(&list.name),
+            htmlescape::encode_minimal(&list.title),
+            list.n_confirmed,
+            if list.is_default { "Default" } else { "" },
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Lists</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Subscribe forms pick a list with a <code>list</code> field holding its name,
+                subscribers join the default list when it is left out.</p>
+                <form action="/admin/lists" method="post">
+                    <label>Name
+                        <input type="text" placeholder="e.g. weekly-digest" name="name">
+                    </label>
+                    <label>Title
+                        <input type="text" placeholder="e.g. Weekly digest" name="title">
+                    </label>
+                    <label>Default list
+                        <input type="checkbox" name="is_default">
+                    </label>
+                    <button type="submit">Create list</button>
+                </form>
+                <table>
+                    <tr><th>Name</th><th>Title</th><th>Confirmed subscribers</th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
+
+pub fn list_options_html(lists: &[MailingList]) -> String {
+    let mut options_html = String::new();
+    for list in lists {
+        write!(
+            options_html,
+            r#"<option value="{}"{}>{}</option>"#,
+            list.list_id,
+            if list.is_default { " selected" } else { "" },
+            htmlescape::encode_minimal(&list.title),
+        )
+        .unwrap();
+    }
+    options_html
+}
+
+struct ListSummary {
+    name: String,
+    title: String,
+    is_default: bool,
+    n_confirmed: i64,
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_list_summaries(pool: &PgPool) -> Result<Vec<ListSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        ListSummary,
+        r#"
+        SELECT
+        lists.name,
+        lists.title,
+        lists.is_default,
+        COUNT(list_subscriptions.subscriber_id)
+            FILTER (WHERE list_subscriptions.status = 'confirmed') AS "n_confirmed!"
+        FROM lists
+        LEFT JOIN list_subscriptions ON list_subscriptions.list_id = lists.list_id
+        GROUP BY lists.list_id
+        ORDER BY lists.name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
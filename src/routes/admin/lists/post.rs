@@ -0,0 +1,83 @@
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension, Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct ListFormData {
+    name: String,
+    title: String,
+    // Checkboxes are only submitted when ticked
+    is_default: Option<String>,
+}
+
+#[tracing::instrument(name = "Create a list", skip_all)]
+pub async fn create_list(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<ListFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/lists");
+    let name = form.name.trim();
+    // Names end up in subscribe forms and unsubscribe links
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        let flash = flash.error("List names may only contain lowercase letters, digits and hyphens.");
+        return Ok((flash, redirect));
+    }
+    let title = form.title.trim();
+    if title.is_empty() {
+        return Ok((flash.error("The list needs a title."), redirect));
+    }
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let is_default = form.is_default.is_some();
+    if is_default {
+        sqlx::query!("UPDATE lists SET is_default = false WHERE is_default")
+            .execute(&mut transaction)
+            .await
+            .context("Failed to clear the default list.")?;
+    }
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO lists (list_id, name, title, is_default)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        Uuid::new_v4(),
+        name,
+        title,
+        is_default
+    )
+    .execute(&mut transaction)
+    .await;
+    match saved {
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("lists_name_key") => {
+            let flash = flash.error("A list with this name already exists.");
+            return Ok((flash, redirect));
+        }
+        saved => {
+            saved.context("Failed to save list.")?;
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to save a list.")?;
+
+    let flash = flash.info("The list has been created.");
+    Ok((flash, redirect))
+}
@@ -11,5 +11,5 @@ where
 {
     session.logout();
     let flash = flash.info("You have been successfully logged out.");
-    return (flash, axum::response::Redirect::to("/login")).into_response();
+    (flash, axum::response::Redirect::to("/login")).into_response()
 }
\ No newline at end of file
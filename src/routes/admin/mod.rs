@@ -1,9 +1,29 @@
 mod dashboard;
+mod delivery_failures;
+mod drafts;
+mod issues;
+mod lists;
 mod password;
+mod profile_fields;
+mod segments;
+mod subscribers;
+mod templates;
 mod logout;
 mod newsletters;
+mod suppressions;
+mod tags;
 
 pub use dashboard::admin_dashboard;
+pub use delivery_failures::delivery_failures;
+pub use drafts::*;
+pub use issues::{issue_delivery_status, issues};
+pub use lists::{create_list, lists};
 pub use password::*;
+pub use profile_fields::*;
+pub use segments::*;
+pub use subscribers::*;
+pub use templates::*;
 pub use logout::*;
-pub use newsletters::*;
\ No newline at end of file
+pub use newsletters::*;
+pub use suppressions::*;
+pub use tags::*;
\ No newline at end of file
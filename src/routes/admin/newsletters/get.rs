@@ -1,17 +1,32 @@
+use crate::email_templates::get_email_templates;
+use crate::error::ResponseError;
+use crate::lists::get_lists;
+use crate::routes::admin::lists::list_options_html;
+use crate::routes::admin::segments::segment_options_html;
+use crate::segments::get_segments;
+use crate::routes::admin::templates::email_template_options_html;
+use crate::startup::TrackingEnabled;
+
+use anyhow::Context;
 use axum::{
     http::{
         header::{self, HeaderValue, HeaderMap},
         StatusCode
     },
-    response::IntoResponse,
+    response::{IntoResponse, Response},
+    Extension,
 };
 use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
 
 use std::fmt::Write;
+use std::sync::Arc;
 
 pub async fn publish_newsletter_form<T>(
     flash_messages: IncomingFlashes,
-) -> impl IntoResponse
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(tracking_enabled): Extension<TrackingEnabled>,
+) -> Result<Response, ResponseError>
 where
     T: axum_session::DatabasePool + Clone + std::fmt::Debug + Sync + Send + 'static
 {
@@ -25,6 +40,27 @@ where
     }
 
     let idempotency_key = uuid::Uuid::new_v4();
+    let templates = get_email_templates(&pool)
+        .await
+        .context("Failed to retrieve email templates.")?;
+    let template_options_html = email_template_options_html(&templates);
+    let lists = get_lists(&pool)
+        .await
+        .context("Failed to retrieve lists.")?;
+    let list_options_html = list_options_html(&lists);
+    let segments = get_segments(&pool)
+        .await
+        .context("Failed to retrieve segments.")?;
+    let segment_options_html = segment_options_html(&segments);
+    // Issues cannot opt in while tracking is disabled in the settings
+    let tracking_html = if tracking_enabled.0 {
+        r#"<label>Track opens and clicks
+                <input type="checkbox" name="is_tracked">
+            </label>
+            <br>"#
+    } else {
+        ""
+    };
 
     let html = format!(
         r#"<!DOCTYPE html>
@@ -35,6 +71,7 @@ where
         </head>
         <body>
         {msg_html}
+        <p>Personalize the title and contents with {{{{ name }}}}, {{{{ email }}}} and {{{{ unsubscribe_url }}}}.</p>
         <form action="/admin/newsletters" method="post">
             <label>Title
                 <input
@@ -44,6 +81,13 @@ where
                 >
             </label>
             <br>
+            <label>Contents in Markdown
+                <textarea
+                    placeholder="Enter content in Markdown, or fill in both fields below instead"
+                    name="markdown"
+                ></textarea>
+            </label>
+            <br>
             <label>Contents
                 <input
                     type="text"
@@ -60,9 +104,43 @@ where
                 >
             </label>
             <br>
+            <label>Send at (UTC, leave empty to send now)
+                <input
+                    type="datetime-local"
+                    name="scheduled_for"
+                >
+            </label>
+            <br>
+            <label>List
+                <select name="list_id">{list_options_html}</select>
+            </label>
+            <br>
+            <label>Segment
+                <select name="segment_id">{segment_options_html}</select>
+            </label>
+            <br>
+            <label>Layout
+                <select name="email_template_id">{template_options_html}</select>
+            </label>
+            <br>
+            <label>Show in the public archive
+                <input type="checkbox" name="is_public">
+            </label>
+            <br>
+            {tracking_html}
             <input hidden type="text" name="idempotency_key" value="{idempotency_key}">
             <button type="submit">Publish newsletter</button>
+            <br>
+            <label>Test recipients
+                <input
+                    type="text"
+                    placeholder="Comma separated email addresses"
+                    name="test_recipients"
+                >
+            </label>
+            <button type="submit" formaction="/admin/newsletters/test">Send test copy</button>
         </form>
+        <p><a href="/admin/newsletters/scheduled">View scheduled newsletters</a></p>
         <p><a href="/admin/dashboard">&lt;- Back</a></p>
         </body>
         </html>"#
@@ -72,5 +150,5 @@ where
         header::CONTENT_TYPE,
         HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
     );
-    (StatusCode::OK, headers, html).into_response()
+    Ok((StatusCode::OK, headers, html).into_response())
 }
\ No newline at end of file
@@ -1,5 +1,9 @@
 mod get;
 mod post;
+mod scheduled;
+mod test_copy;
 
 pub use get::*;
-pub use post::*;
\ No newline at end of file
+pub use post::*;
+pub use scheduled::*;
+pub use test_copy::*;
\ No newline at end of file
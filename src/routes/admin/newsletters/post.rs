@@ -1,6 +1,12 @@
 use crate::{authentication::UserId, error::ResponseError};
+use crate::domain::{validate_issue_body, validate_merge_tags, IssueContent};
+use crate::email_templates::{get_selected_email_layout, SelectLayoutError};
+use crate::lists::{get_selected_list, SelectListError};
+use crate::profile_fields::get_profile_fields;
+use crate::segments::{get_selected_segment, SelectSegmentError};
 use crate::error::error_chain_fmt;
 use crate::idempotency::{IdempotencyKey, save_response, try_processing, NextAction};
+use crate::issue_delivery_worker::enqueue_delivery_tasks;
 
 use anyhow::Context;
 use axum::{
@@ -10,6 +16,7 @@ use axum::{
     response::{IntoResponse, Response}
 };
 use axum_flash::Flash;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use hyper::header;
 use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
@@ -19,9 +26,30 @@ use std::sync::Arc;
 #[derive(serde::Deserialize)]
 pub struct NewsletterFormData {
     title: String,
+    #[serde(default)]
     text: String,
+    #[serde(default)]
     html: String,
+    // Rendered into both html and text when present
+    #[serde(default)]
+    markdown: String,
+    // Empty to send without a layout
+    #[serde(default)]
+    email_template_id: String,
+    // Empty to send to the default list
+    #[serde(default)]
+    list_id: String,
+    // Empty to send to the whole list
+    #[serde(default)]
+    segment_id: String,
+    // Checkbox, only submitted when ticked
+    is_public: Option<String>,
+    // Checkbox, opens and clicks are not tracked unless ticked
+    is_tracked: Option<String>,
     idempotency_key: String,
+    // Empty to send immediately
+    #[serde(default)]
+    scheduled_for: String,
 }
 
 #[derive(thiserror::Error)]
@@ -68,7 +96,51 @@ pub async fn publish_newsletter<T>(
 where
     T: axum_session::DatabasePool + Clone + std::fmt::Debug + Sync + Send + 'static
 {
-    let NewsletterFormData { title, text, html, idempotency_key } = form;
+    let NewsletterFormData {
+        title, text, html, markdown, email_template_id, list_id, segment_id, is_public,
+        is_tracked, idempotency_key, scheduled_for
+    } = form;
+    let scheduled_for = match parse_scheduled_for(&scheduled_for) {
+        Ok(scheduled_for) => scheduled_for,
+        Err(e) => {
+            let flash = flash.error(e);
+            return Ok((flash, axum::response::Redirect::to("/admin/newsletters")).into_response());
+        }
+    };
+    let content = IssueContent::new(markdown, html, text);
+    let profile_fields = get_profile_fields(&pool).await?;
+    if let Err(e) = validate_issue_body(&content.html, &content.text).and_then(|_| {
+        [&title, &content.html, &content.text]
+            .into_iter()
+            .try_for_each(|t| validate_merge_tags(t, &profile_fields))
+    }) {
+        let flash = flash.error(e);
+        return Ok((flash, axum::response::Redirect::to("/admin/newsletters")).into_response());
+    }
+    let email_template_id = match get_selected_email_layout(&pool, &email_template_id).await {
+        Ok(layout) => layout.map(|(id, _)| id),
+        Err(SelectLayoutError::UnknownLayout) => {
+            let flash = flash.error(SelectLayoutError::UnknownLayout.to_string());
+            return Ok((flash, axum::response::Redirect::to("/admin/newsletters")).into_response());
+        }
+        Err(SelectLayoutError::UnexpectedError(e)) => return Err(PublishError::UnexpectedError(e)),
+    };
+    let list_id = match get_selected_list(&pool, &list_id).await {
+        Ok(list_id) => list_id,
+        Err(SelectListError::UnknownList) => {
+            let flash = flash.error(SelectListError::UnknownList.to_string());
+            return Ok((flash, axum::response::Redirect::to("/admin/newsletters")).into_response());
+        }
+        Err(SelectListError::UnexpectedError(e)) => return Err(PublishError::UnexpectedError(e)),
+    };
+    let segment_id = match get_selected_segment(&pool, &segment_id).await {
+        Ok(segment_id) => segment_id,
+        Err(SelectSegmentError::UnknownSegment) => {
+            let flash = flash.error(SelectSegmentError::UnknownSegment.to_string());
+            return Ok((flash, axum::response::Redirect::to("/admin/newsletters")).into_response());
+        }
+        Err(SelectSegmentError::UnexpectedError(e)) => return Err(PublishError::UnexpectedError(e)),
+    };
     let idempotency_key: IdempotencyKey =
         match idempotency_key.try_into(){
             Ok(key) => key,
@@ -91,19 +163,34 @@ where
     let issue_id = insert_newsletter_issue(
         &mut transaction,
         &title,
-        &text, 
-        &html,
+        &content,
+        email_template_id,
+        list_id,
+        segment_id,
+        is_public.is_some(),
+        is_tracked.is_some(),
+        if scheduled_for.is_some() { "scheduled" } else { "sending" },
+        scheduled_for,
     )
     .await
     .context("Failed to store newsletter issue details")
     .map_err(PublishError::UnexpectedError)?;
 
-    enqueue_delivery_tasks(&mut transaction, issue_id)
-        .await
-        .context("Failed to enqueue delivery tasks")
-        .map_err(PublishError::UnexpectedError)?;
-
-    let flash = flash.info("The newsletter issue has been accepted - emails will go out shortly.");
+    // Scheduled issues are enqueued by the scheduler once they are due,
+    // enqueueing is also what marks an issue as published
+    let flash = match scheduled_for {
+        Some(scheduled_for) => flash.info(format!(
+            "The newsletter issue has been scheduled for {} UTC.",
+            scheduled_for.format(SCHEDULED_FOR_FORMAT)
+        )),
+        None => {
+            enqueue_delivery_tasks(&mut transaction, issue_id)
+                .await
+                .context("Failed to enqueue delivery tasks")
+                .map_err(PublishError::UnexpectedError)?;
+            flash.info("The newsletter issue has been accepted - emails will go out shortly.")
+        }
+    };
     let redirect = axum::response::Redirect::to("/admin/newsletters");
     let response = (flash, redirect).into_response();   
     let response = save_response(transaction, &idempotency_key, *user_id, response)
@@ -112,12 +199,19 @@ where
     Ok(response)
 }
 
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip_all)]
 async fn insert_newsletter_issue(
     transaction: &mut Transaction<'_, Postgres>,
     title: &str,
-    text_content: &str,
-    html_content: &str,
+    content: &IssueContent,
+    email_template_id: Option<Uuid>,
+    list_id: Uuid,
+    segment_id: Option<Uuid>,
+    is_public: bool,
+    is_tracked: bool,
+    status: &str,
+    scheduled_for: Option<DateTime<Utc>>,
 ) -> Result<Uuid, sqlx::Error> {
     let newsletter_issue_id = Uuid::new_v4();
     sqlx::query!(
@@ -127,38 +221,93 @@ async fn insert_newsletter_issue(
         title,
         text_content,
         html_content,
-        published_at
+        markdown_content,
+        email_template_id,
+        list_id,
+        segment_id,
+        is_public,
+        is_tracked,
+        status,
+        scheduled_for
         )
-        VALUES ($1, $2, $3, $4, now())
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         "#,
         newsletter_issue_id,
         title,
-        text_content,
-        html_content
+        content.text,
+        content.html,
+        content.markdown,
+        email_template_id,
+        list_id,
+        segment_id,
+        is_public,
+        is_tracked,
+        status,
+        scheduled_for
     )
     .execute(transaction)
     .await?;
     Ok(newsletter_issue_id)
 }
 
-#[tracing::instrument(skip_all)]
-async fn enqueue_delivery_tasks(
-    transaction: &mut Transaction<'_, Postgres>,
-    newsletter_issue_id: Uuid,
-) -> Result<(), sqlx::Error> {
-    sqlx::query!(
-        r#"
-        INSERT INTO issue_delivery_queue (
-        newsletter_issue_id,
-        subscriber_email
-        )
-        SELECT $1, email
-        FROM subscriptions
-        WHERE status = 'confirmed'
-        "#,
-        newsletter_issue_id,
-    )
-    .execute(transaction)
-    .await?;
-    Ok(())
-}
\ No newline at end of file
+// Matches the value of an <input type="datetime-local">, interpreted as UTC
+pub const SCHEDULED_FOR_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+pub fn parse_scheduled_for(value: &str) -> Result<Option<DateTime<Utc>>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let scheduled_for = NaiveDateTime::parse_from_str(value, SCHEDULED_FOR_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .map(|naive| Utc.from_utc_datetime(&naive))
+        .map_err(|_| format!("{} is not a valid send time.", htmlescape::encode_minimal(value)))?;
+    if scheduled_for <= Utc::now() {
+        return Err("The send time must be in the future.".into());
+    }
+    Ok(Some(scheduled_for))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_scheduled_for;
+    use chrono::{Duration, Utc};
+    use claims::{assert_err, assert_none, assert_ok};
+
+    #[test]
+    fn an_empty_send_time_means_send_now() {
+        assert_none!(parse_scheduled_for("").unwrap());
+        assert_none!(parse_scheduled_for("  ").unwrap());
+    }
+
+    #[test]
+    fn a_future_send_time_is_accepted() {
+        let tomorrow = Utc::now() + Duration::days(1);
+        let value = tomorrow.format("%Y-%m-%dT%H:%M").to_string();
+
+        let scheduled_for = assert_ok!(parse_scheduled_for(&value)).unwrap();
+
+        assert_eq!(scheduled_for.format("%Y-%m-%dT%H:%M").to_string(), value);
+    }
+
+    #[test]
+    fn a_send_time_with_seconds_is_accepted() {
+        let tomorrow = Utc::now() + Duration::days(1);
+        let value = tomorrow.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+        assert_ok!(parse_scheduled_for(&value));
+    }
+
+    #[test]
+    fn a_past_send_time_is_rejected() {
+        let yesterday = Utc::now() - Duration::days(1);
+        let value = yesterday.format("%Y-%m-%dT%H:%M").to_string();
+
+        assert_err!(parse_scheduled_for(&value));
+    }
+
+    #[test]
+    fn a_malformed_send_time_is_rejected() {
+        assert_err!(parse_scheduled_for("next tuesday"));
+    }
+}
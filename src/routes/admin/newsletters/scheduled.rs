@@ -0,0 +1,188 @@
+use crate::error::ResponseError;
+use super::{parse_scheduled_for, SCHEDULED_FOR_FORMAT};
+
+use anyhow::Context;
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::{self, HeaderMap, HeaderValue}},
+    response::{IntoResponse, Redirect},
+    Extension,
+    Form,
+};
+use axum_flash::{Flash, IncomingFlashes};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn scheduled_newsletters(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+
+    let issues = get_scheduled_issues(&pool).await?;
+    let mut rows_html = String::new();
+    for issue in issues {
+        let id = issue.newsletter_issue_id;
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{} UTC</td>
+                <td>
+                    <form action="/admin/newsletters/{id}/reschedule" method="post">
+                        <input type="datetime-local" name="scheduled_for" value="{}">
+                        <button type="submit">Reschedule</button>
+                    </form>
+                </td>
+                <td>
+                    <form action="/admin/newsletters/{id}/cancel" method="post">
+                        <button type="submit">Cancel</button>
+                    </form>
+                </td>
+            </tr>"#,
+            htmlescape::encode_minimal(&issue.title),
+            issue.scheduled_for.format("%Y-%m-%d %H:%M"),
+            issue.scheduled_for.format(SCHEDULED_FOR_FORMAT),
+        ).unwrap();
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Scheduled newsletters</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Newsletter issues waiting to be sent:</p>
+                <table>
+                    <tr><th>Issue</th><th>Send at</th><th></th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/newsletters">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ))
+}
+
+#[derive(serde::Deserialize)]
+pub struct RescheduleFormData {
+    scheduled_for: String,
+}
+
+#[tracing::instrument(name = "Reschedule a newsletter issue", skip(flash, pool, form))]
+pub async fn reschedule_newsletter(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<RescheduleFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/newsletters/scheduled");
+    let scheduled_for = match parse_scheduled_for(&form.scheduled_for) {
+        Ok(Some(scheduled_for)) => scheduled_for,
+        Ok(None) => {
+            let flash = flash.error("Pick a new send time.");
+            return Ok((flash, redirect));
+        }
+        Err(e) => return Ok((flash.error(e), redirect)),
+    };
+
+    let rescheduled = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET scheduled_for = $2
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'scheduled'
+        "#,
+        issue_id,
+        scheduled_for
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to reschedule newsletter issue.")?
+    .rows_affected() > 0;
+
+    let flash = if rescheduled {
+        flash.info(format!(
+            "The newsletter issue has been rescheduled for {} UTC.",
+            scheduled_for.format(SCHEDULED_FOR_FORMAT)
+        ))
+    } else {
+        flash.error("The newsletter issue is no longer scheduled.")
+    };
+    Ok((flash, redirect))
+}
+
+#[tracing::instrument(name = "Cancel a scheduled newsletter issue", skip(flash, pool))]
+pub async fn cancel_newsletter(
+    flash: Flash,
+    Path(issue_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let cancelled = sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET
+        status = 'draft',
+        scheduled_for = NULL
+        WHERE
+        newsletter_issue_id = $1 AND
+        status = 'scheduled'
+        "#,
+        issue_id
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to cancel newsletter issue.")?
+    .rows_affected() > 0;
+
+    let flash = if cancelled {
+        flash.info("The newsletter issue has been cancelled and moved back to drafts.")
+    } else {
+        flash.error("The newsletter issue is no longer scheduled.")
+    };
+    Ok((flash, Redirect::to("/admin/newsletters/scheduled")))
+}
+
+struct ScheduledIssue {
+    newsletter_issue_id: Uuid,
+    title: String,
+    scheduled_for: DateTime<Utc>,
+}
+
+#[tracing::instrument(name = "Get scheduled newsletter issues", skip(pool))]
+async fn get_scheduled_issues(
+    pool: &PgPool,
+) -> Result<Vec<ScheduledIssue>, anyhow::Error> {
+    let issues = sqlx::query_as!(
+        ScheduledIssue,
+        r#"
+        SELECT newsletter_issue_id, title, scheduled_for as "scheduled_for!"
+        FROM newsletter_issues
+        WHERE status = 'scheduled'
+        ORDER BY scheduled_for
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to perform a query to retrieve scheduled newsletter issues.")?;
+    Ok(issues)
+}
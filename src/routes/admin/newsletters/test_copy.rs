@@ -0,0 +1,201 @@
+use crate::domain::{validate_merge_tags, EmailLayout, IssueContent, MergeTags, SubscriberEmail};
+use crate::email_client::EmailTransport;
+use crate::email_templates::{get_selected_email_layout, SelectLayoutError};
+use crate::error::ResponseError;
+use crate::profile_fields::get_profile_fields;
+use crate::startup::ApplicationBaseUrl;
+use crate::suppression::is_email_suppressed;
+
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension,
+    Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct TestCopyFormData {
+    title: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    html: String,
+    #[serde(default)]
+    markdown: String,
+    #[serde(default)]
+    email_template_id: String,
+    test_recipients: String,
+}
+
+// Goes straight to the email client, never through the delivery queue or the
+// idempotency table, so the same issue can be tested as many times as needed
+#[tracing::instrument(name = "Send a test copy of a newsletter issue", skip_all)]
+pub async fn send_test_newsletter(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(email_client): Extension<Arc<dyn EmailTransport>>,
+    Extension(base_url): Extension<ApplicationBaseUrl>,
+    Form(form): Form<TestCopyFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/newsletters");
+    let content = IssueContent::new(form.markdown, form.html, form.text);
+    let profile_fields = get_profile_fields(&pool).await?;
+    if let Err(e) = [&form.title, &content.html, &content.text]
+        .into_iter()
+        .try_for_each(|t| validate_merge_tags(t, &profile_fields))
+    {
+        return Ok((flash.error(e), redirect));
+    }
+    let layout = match get_selected_email_layout(&pool, &form.email_template_id).await {
+        Ok(layout) => layout.map(|(_, layout)| layout),
+        Err(e @ SelectLayoutError::UnknownLayout) => return Ok((flash.error(e.to_string()), redirect)),
+        Err(e) => return Err(e.into()),
+    };
+    let (html_content, text_content) = EmailLayout::wrap(layout.as_ref(), &content.html, &content.text);
+    let flash = send_test_copy(
+        flash,
+        &pool,
+        email_client.as_ref(),
+        &base_url.0,
+        &form.test_recipients,
+        &form.title,
+        &html_content,
+        &text_content,
+    )
+    .await;
+    Ok((flash, redirect))
+}
+
+// Contents are expected to be wrapped in their layout already, with merge
+// tags validated before wrapping. Reports the outcome as a flash message, for the caller to redirect with
+#[allow(clippy::too_many_arguments)]
+pub async fn send_test_copy(
+    flash: Flash,
+    pool: &PgPool,
+    email_client: &dyn EmailTransport,
+    base_url: &str,
+    test_recipients: &str,
+    title: &str,
+    html_content: &str,
+    text_content: &str,
+) -> Flash {
+    let recipients = match parse_test_recipients(test_recipients) {
+        Ok(recipients) => recipients,
+        Err(e) => return flash.error(e),
+    };
+    for recipient in &recipients {
+        match is_email_suppressed(pool, recipient.as_ref()).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return flash.error(format!(
+                    "{} is on the suppression list.",
+                    htmlescape::encode_minimal(recipient.as_ref())
+                ));
+            }
+            Err(e) => {
+                tracing::error!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to check the suppression list."
+                );
+                return flash.error("Failed to check the suppression list.");
+            }
+        }
+    }
+
+    // Sample values, the unsubscribe link does not belong to any subscriber
+    let unsubscribe_url = format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token=test",
+        base_url
+    );
+    for recipient in &recipients {
+        let merge_tags = MergeTags {
+            name: "Test Subscriber",
+            email: recipient.as_ref(),
+            unsubscribe_url: &unsubscribe_url,
+            profile: &[],
+        };
+        let subject = format!("[TEST] {}", merge_tags.render_text(title));
+        if let Err(e) = email_client
+            .send_email(
+                recipient,
+                &subject,
+                &merge_tags.render_html(html_content),
+                &merge_tags.render_text(text_content),
+                &[],
+            )
+            .await
+        {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to send a test copy."
+            );
+            return flash.error(format!("Failed to send a test copy to {}.", recipient));
+        }
+    }
+
+    let recipients: Vec<&str> = recipients.iter().map(|r| r.as_ref()).collect();
+    flash.info(format!("A test copy has been sent to {}.", recipients.join(", ")))
+}
+
+const MAX_TEST_RECIPIENTS: usize = 10;
+
+// Addresses may be separated by commas, whitespace or new lines
+pub fn parse_test_recipients(value: &str) -> Result<Vec<SubscriberEmail>, String> {
+    let recipients = value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|r| !r.is_empty())
+        .map(|r| SubscriberEmail::parse(r.to_owned()))
+        .collect::<Result<Vec<_>, _>>()
+        // Flash messages are rendered as is
+        .map_err(|e| htmlescape::encode_minimal(&e))?;
+    if recipients.is_empty() {
+        return Err("Enter at least one address to send a test copy to.".into());
+    }
+    if recipients.len() > MAX_TEST_RECIPIENTS {
+        return Err(format!(
+            "A test copy can be sent to at most {} addresses.",
+            MAX_TEST_RECIPIENTS
+        ));
+    }
+    Ok(recipients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_test_recipients, MAX_TEST_RECIPIENTS};
+    use claims::assert_err;
+
+    #[test]
+    fn recipients_can_be_separated_by_commas_spaces_or_new_lines() {
+        let recipients = parse_test_recipients("a@example.com, b@example.com\nc@example.com")
+            .unwrap();
+
+        let recipients: Vec<&str> = recipients.iter().map(|r| r.as_ref()).collect();
+        assert_eq!(recipients, ["a@example.com", "b@example.com", "c@example.com"]);
+    }
+
+    #[test]
+    fn at_least_one_recipient_is_required() {
+        assert_err!(parse_test_recipients(" , \n"));
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        assert_err!(parse_test_recipients("a@example.com, not-an-email"));
+    }
+
+    #[test]
+    fn the_number_of_recipients_is_capped() {
+        let recipients = (0..=MAX_TEST_RECIPIENTS)
+            .map(|i| format!("admin{}@example.com", i))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        assert_err!(parse_test_recipients(&recipients));
+    }
+}
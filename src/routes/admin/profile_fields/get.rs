@@ -0,0 +1,99 @@
+use crate::error::ResponseError;
+use crate::profile_fields::get_profile_fields;
+
+use axum::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn profile_fields(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let fields = get_profile_fields(&pool).await?;
+    let mut rows_html = String::new();
+    for field in fields {
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+            </tr>"#,
+            field.key,
+            htmlescape::encode_minimal(&field.label),
+            field.field_type.as_str(),
+            htmlescape::encode_minimal(&field.field_type.options().join(", ")),
+            if field.is_required { "Required" } else { "" },
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Profile fields</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Subscribe forms fill a profile field with a form field named after its key,
+                issues show it with <code>{{{{ profile.&lt;key&gt; }}}}</code>.</p>
+                <form action="/admin/profile_fields" method="post">
+                    <label>Key
+                        <input type="text" placeholder="e.g. company" name="key">
+                    </label>
+                    <label>Label
+                        <input type="text" placeholder="e.g. Company" name="label">
+                    </label>
+                    <label>Type
+                        <select name="field_type">
+                            <option value="string">Text</option>
+                            <option value="number">Number</option>
+                            <option value="boolean">Checkbox</option>
+                            <option value="date">Date</option>
+                            <option value="enum">Choice</option>
+                        </select>
+                    </label>
+                    <label>Options of a choice
+                        <input type="text" placeholder="Comma separated" name="options">
+                    </label>
+                    <label>Required
+                        <input type="checkbox" name="is_required">
+                    </label>
+                    <button type="submit">Create field</button>
+                </form>
+                <table>
+                    <tr><th>Key</th><th>Label</th><th>Type</th><th>Options</th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
@@ -0,0 +1,95 @@
+use crate::domain::ProfileFieldType;
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension, Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+// Fields of the subscribe form itself
+const RESERVED_KEYS: [&str; 3] = ["email", "name", "list"];
+
+#[derive(serde::Deserialize)]
+pub struct ProfileFieldFormData {
+    key: String,
+    label: String,
+    field_type: String,
+    #[serde(default)]
+    options: String,
+    // Checkboxes are only submitted when ticked
+    is_required: Option<String>,
+}
+
+#[tracing::instrument(name = "Create a profile field", skip_all)]
+pub async fn create_profile_field(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<ProfileFieldFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/profile_fields");
+    let key = form.key.trim();
+    // Keys end up in subscribe forms and merge tags
+    if key.is_empty()
+        || key.len() > 64
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        let flash = flash.error(
+            "Field keys may only contain up to 64 lowercase letters, digits and underscores.",
+        );
+        return Ok((flash, redirect));
+    }
+    if RESERVED_KEYS.contains(&key) {
+        let flash = flash.error(format!("{} is already a field of the subscribe form.", key));
+        return Ok((flash, redirect));
+    }
+    let label = form.label.trim();
+    if label.is_empty() {
+        return Ok((flash.error("The field needs a label."), redirect));
+    }
+    let mut options: Vec<String> = Vec::new();
+    for option in form.options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        if !options.iter().any(|o| o == option) {
+            options.push(option.to_owned());
+        }
+    }
+    let field_type = match ProfileFieldType::parse(&form.field_type, options) {
+        Ok(field_type) => field_type,
+        // Flash messages are rendered as is
+        Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect)),
+    };
+
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO profile_fields (profile_field_id, key, label, field_type, options, is_required)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        key,
+        label,
+        field_type.as_str(),
+        field_type.options(),
+        form.is_required.is_some()
+    )
+    .execute(&*pool)
+    .await;
+    match saved {
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("profile_fields_key_key") => {
+            let flash = flash.error("A field with this key already exists.");
+            return Ok((flash, redirect));
+        }
+        saved => {
+            saved.context("Failed to save profile field.")?;
+        }
+    }
+
+    let flash = flash.info("The profile field has been created.");
+    Ok((flash, redirect))
+}
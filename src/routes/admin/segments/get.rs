@@ -0,0 +1,135 @@
+use crate::error::ResponseError;
+use crate::profile_fields::get_profile_fields;
+use crate::segments::{get_segments, Segment};
+
+use anyhow::Context;
+use axum::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn segments(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let segments = get_segments(&pool)
+        .await
+        .context("Failed to retrieve segments.")?;
+    let profile_fields = get_profile_fields(&pool).await?;
+    let mut field_options_html = String::from(r#"<option value="">Any profile</option>"#);
+    for field in profile_fields {
+        write!(
+            field_options_html,
+            r#"<option value="{}">{}</option>"#,
+            field.key,
+            htmlescape::encode_minimal(&field.label),
+        )
+        .unwrap();
+    }
+    let mut rows_html = String::new();
+    for segment in segments {
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+            </tr>"#,
+            htmlescape::encode_minimal(&segment.name),
+            htmlescape::encode_minimal(&segment.describe()),
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Segments</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Segments send an issue to part of a list. Leave a field empty to skip that rule.</p>
+                <form action="/admin/segments" method="post">
+                    <label>Name
+                        <input type="text" placeholder="e.g. Beta testers" name="name">
+                    </label>
+                    <br>
+                    <label>Tagged with all of
+                        <input type="text" placeholder="Comma separated tags" name="required_tags">
+                    </label>
+                    <br>
+                    <label>Tagged with none of
+                        <input type="text" placeholder="Comma separated tags" name="excluded_tags">
+                    </label>
+                    <br>
+                    <label>Joined the list on or after (UTC)
+                        <input type="date" name="subscribed_after">
+                    </label>
+                    <br>
+                    <label>Joined the list before (UTC)
+                        <input type="date" name="subscribed_before">
+                    </label>
+                    <br>
+                    <label>Profile field
+                        <select name="profile_field_key">{field_options_html}</select>
+                    </label>
+                    <label>equal to
+                        <input type="text" name="profile_field_value">
+                    </label>
+                    <br>
+                    <label>Members who
+                        <select name="subscriber_status">
+                            <option value="confirmed">confirmed the list</option>
+                            <option value="pending_confirmation">have not confirmed the list yet</option>
+                        </select>
+                    </label>
+                    <br>
+                    <button type="submit">Create segment</button>
+                </form>
+                <table>
+                    <tr><th>Name</th><th>Members</th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/tags">Manage tags</a></p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
+
+pub fn segment_options_html(segments: &[Segment]) -> String {
+    let mut options_html = String::from(r#"<option value="">Everyone on the list</option>"#);
+    for segment in segments {
+        write!(
+            options_html,
+            r#"<option value="{}">{}</option>"#,
+            segment.segment_id,
+            htmlescape::encode_minimal(&segment.name),
+        )
+        .unwrap();
+    }
+    options_html
+}
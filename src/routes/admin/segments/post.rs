@@ -0,0 +1,144 @@
+use crate::domain::SubscriberTag;
+use crate::error::ResponseError;
+use crate::profile_fields::get_profile_fields;
+use crate::segments::{parse_date, parse_subscriber_status};
+
+use anyhow::Context;
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension, Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct SegmentFormData {
+    name: String,
+    #[serde(default)]
+    required_tags: String,
+    #[serde(default)]
+    excluded_tags: String,
+    // Dates of an <input type="date">, empty to leave the range open
+    #[serde(default)]
+    subscribed_after: String,
+    #[serde(default)]
+    subscribed_before: String,
+    // Empty to ignore profiles
+    #[serde(default)]
+    profile_field_key: String,
+    #[serde(default)]
+    profile_field_value: String,
+    // Confirmed members when empty
+    #[serde(default)]
+    subscriber_status: String,
+}
+
+#[tracing::instrument(name = "Create a segment", skip_all)]
+pub async fn create_segment(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<SegmentFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/segments");
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Ok((flash.error("The segment needs a name."), redirect));
+    }
+    let rules = (|| {
+        Ok::<_, String>((
+            SubscriberTag::parse_list(&form.required_tags)?,
+            SubscriberTag::parse_list(&form.excluded_tags)?,
+            parse_date(&form.subscribed_after)?,
+            parse_date(&form.subscribed_before)?,
+            parse_subscriber_status(&form.subscriber_status)?,
+        ))
+    })();
+    let (
+        required_tags,
+        excluded_tags,
+        subscribed_after,
+        subscribed_before,
+        subscriber_status,
+    ) = match rules {
+        Ok(rules) => rules,
+        // Flash messages are rendered as is
+        Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect)),
+    };
+    if let (Some(after), Some(before)) = (subscribed_after, subscribed_before) {
+        if after >= before {
+            return Ok((flash.error("The date range of the segment is empty."), redirect));
+        }
+    }
+    let (profile_field_key, profile_field_value) =
+        match parse_profile_rule(&pool, &form.profile_field_key, &form.profile_field_value).await? {
+            Ok(rule) => rule.unzip(),
+            Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect)),
+        };
+    let required_tags: Vec<String> = required_tags.iter().map(|t| t.as_ref().to_owned()).collect();
+    let excluded_tags: Vec<String> = excluded_tags.iter().map(|t| t.as_ref().to_owned()).collect();
+
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO segments (
+        segment_id,
+        name,
+        required_tags,
+        excluded_tags,
+        subscribed_after,
+        subscribed_before,
+        profile_field_key,
+        profile_field_value,
+        subscriber_status
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        Uuid::new_v4(),
+        name,
+        &required_tags,
+        &excluded_tags,
+        subscribed_after,
+        subscribed_before,
+        profile_field_key,
+        profile_field_value,
+        subscriber_status
+    )
+    .execute(&*pool)
+    .await;
+    match saved {
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("segments_name_key") => {
+            let flash = flash.error("A segment with this name already exists.");
+            return Ok((flash, redirect));
+        }
+        saved => {
+            saved.context("Failed to save segment.")?;
+        }
+    }
+
+    let flash = flash.info("The segment has been created.");
+    Ok((flash, redirect))
+}
+
+// Values are compared in the canonical form they are stored in
+async fn parse_profile_rule(
+    pool: &PgPool,
+    key: &str,
+    value: &str,
+) -> Result<Result<Option<(String, String)>, String>, anyhow::Error> {
+    if key.is_empty() {
+        return Ok(Ok(None));
+    }
+    let fields = get_profile_fields(pool).await?;
+    let field = match fields.iter().find(|f| f.key == key) {
+        Some(field) => field,
+        None => return Ok(Err("The selected profile field no longer exists.".into())),
+    };
+    let value = match field.parse_value(Some(value)) {
+        Ok(Some(value)) => value,
+        Ok(None) => return Ok(Err(format!("Give a value for {}.", field.label))),
+        Err(e) => return Ok(Err(e)),
+    };
+    Ok(Ok(Some((field.key.clone(), value))))
+}
@@ -0,0 +1,200 @@
+use crate::error::ResponseError;
+use crate::lists::get_lists;
+use crate::routes::admin::lists::list_options_html;
+use crate::segments::parse_date;
+use crate::subscriber_export::{stream_subscribers, ExportFilter, ExportFormat, SUBSCRIBER_STATUSES};
+use crate::subscriber_import::{error_report_csv, get_import_errors, get_recent_imports};
+
+use anyhow::Context;
+use axum::{
+    body::StreamBody,
+    extract::{Path, Query},
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn import_subscribers_form(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let lists = get_lists(&pool)
+        .await
+        .context("Failed to retrieve lists.")?;
+    let imports = get_recent_imports(&pool)
+        .await
+        .context("Failed to retrieve subscriber imports.")?;
+    let mut rows_html = String::new();
+    for import in imports {
+        let n_skipped = import.n_rows - import.n_imported;
+        let report_html = if n_skipped > 0 {
+            format!(
+                r#"<a href="/admin/subscribers/import/{}/report">Download report</a>"#,
+                import.import_id
+            )
+        } else {
+            String::new()
+        };
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+            </tr>"#,
+            import.imported_at.format("%Y-%m-%d %H:%M UTC"),
+            htmlescape::encode_minimal(&import.file_name),
+            import.n_rows,
+            import.n_imported,
+            n_skipped,
+            report_html,
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let list_options_html = list_options_html(&lists);
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Import subscribers</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Upload a CSV file whose first row names an <code>email</code> and a
+                <code>name</code> column. Addresses that are already subscribed are skipped.</p>
+                <form action="/admin/subscribers/import" method="post" enctype="multipart/form-data">
+                    <label>List
+                        <select name="list_id">{list_options_html}</select>
+                    </label>
+                    <br>
+                    <label>Already confirmed with the previous tool, do not send confirmation emails
+                        <input type="checkbox" name="pre_confirmed">
+                    </label>
+                    <br>
+                    <input type="file" name="file" accept=".csv,text/csv">
+                    <button type="submit">Import</button>
+                </form>
+                <table>
+                    <tr><th>Imported at</th><th>File</th><th>Rows</th><th>Imported</th><th>Skipped</th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
+
+pub async fn import_report(
+    Path(import_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let errors = match get_import_errors(&pool, import_id)
+        .await
+        .context("Failed to retrieve the errors of a subscriber import.")?
+    {
+        Some(errors) => errors,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/csv; charset=utf-8"),
+    );
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"import-{}-report.csv\"",
+            import_id
+        ))
+        .unwrap(),
+    );
+    Ok((StatusCode::OK, headers, error_report_csv(&errors)).into_response())
+}
+
+#[derive(serde::Deserialize)]
+pub struct ExportParameters {
+    // csv or ndjson, csv when empty
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    status: String,
+    // Dates, the range includes subscribed_after and excludes subscribed_before
+    #[serde(default)]
+    subscribed_after: String,
+    #[serde(default)]
+    subscribed_before: String,
+}
+
+pub async fn export_subscribers(
+    Query(parameters): Query<ExportParameters>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Response {
+    let export = (|| {
+        let status = match parameters.status.trim() {
+            "" => None,
+            status if SUBSCRIBER_STATUSES.contains(&status) => Some(status.to_owned()),
+            status => return Err(format!("{} is not a subscriber status.", status)),
+        };
+        Ok::<_, String>((
+            ExportFormat::parse(parameters.format.trim())?,
+            ExportFilter {
+                status,
+                subscribed_after: parse_date(&parameters.subscribed_after)?,
+                subscribed_before: parse_date(&parameters.subscribed_before)?,
+            },
+        ))
+    })();
+    let (format, filter) = match export {
+        Ok(export) => export,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"subscribers-{}.{}\"",
+            Utc::now().format("%Y-%m-%d"),
+            format.extension()
+        ))
+        .unwrap(),
+    );
+    (
+        StatusCode::OK,
+        headers,
+        StreamBody::new(stream_subscribers(pool, filter, format)),
+    )
+        .into_response()
+}
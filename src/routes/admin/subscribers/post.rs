@@ -0,0 +1,163 @@
+use crate::error::ResponseError;
+use crate::lists::{get_selected_list, SelectListError};
+use crate::subscriber_import::{
+    import_subscriber, parse_row, store_import, CsvRecords, ImportColumns, ImportError, SkippedRow,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{multipart::Field, Multipart},
+    response::{IntoResponse, Redirect},
+    Extension,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// Uploads larger than this are cut off by the route's body limit
+pub const MAX_IMPORT_SIZE: usize = 20 * 1024 * 1024;
+
+// The file is imported as it is read, so the list and pre_confirmed
+// fields must come before it in the form
+#[tracing::instrument(name = "Import subscribers", skip_all)]
+pub async fn import_subscribers(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/subscribers/import");
+    let mut list_id = String::new();
+    let mut pre_confirmed = false;
+    let mut summary = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(_) => return Ok((flash.error("The upload could not be read."), redirect)),
+        };
+        match field.name() {
+            Some("list_id") => {
+                list_id = match field.text().await {
+                    Ok(list_id) => list_id,
+                    Err(_) => return Ok((flash.error("The upload could not be read."), redirect)),
+                };
+            }
+            Some("pre_confirmed") => pre_confirmed = true,
+            Some("file") => match import_file(&pool, field, &list_id, pre_confirmed).await {
+                Ok(s) => summary = Some(s),
+                // Flash messages are rendered as is
+                Err(ImportError::InvalidUpload(e)) => {
+                    return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect));
+                }
+                Err(e) => return Err(e.into()),
+            },
+            _ => {}
+        }
+    }
+
+    let flash = match summary {
+        None => flash.error("Choose a CSV file to import."),
+        Some((n_rows, n_imported)) if n_rows == n_imported => {
+            flash.info(format!("Imported {} of {} rows.", n_imported, n_rows))
+        }
+        Some((n_rows, n_imported)) => flash.info(format!(
+            "Imported {} of {} rows. {} rows were skipped, download the report for details.",
+            n_imported,
+            n_rows,
+            n_rows - n_imported
+        )),
+    };
+    Ok((flash, redirect))
+}
+
+// Everything is imported in one transaction, a broken upload imports nothing.
+// Returns the number of rows read and imported.
+async fn import_file(
+    pool: &PgPool,
+    mut field: Field<'_>,
+    list_id: &str,
+    pre_confirmed: bool,
+) -> Result<(i32, i32), ImportError> {
+    let list_id = match get_selected_list(pool, list_id).await {
+        Ok(list_id) => list_id,
+        Err(SelectListError::UnknownList) => {
+            return Err(ImportError::InvalidUpload(SelectListError::UnknownList.to_string()))
+        }
+        Err(SelectListError::UnexpectedError(e)) => return Err(e.into()),
+    };
+    let file_name = field.file_name().unwrap_or("upload.csv").to_owned();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let mut records = CsvRecords::default();
+    let mut columns: Option<ImportColumns> = None;
+    // Rows are numbered like in a spreadsheet, the header is row 1
+    let mut row_number = 0;
+    let mut n_rows = 0;
+    let mut n_imported = 0;
+    let mut skipped_rows = Vec::new();
+    // Lowercased, the same address may be written in another case further down
+    let mut seen_emails = HashSet::new();
+    loop {
+        let chunk = field
+            .chunk()
+            .await
+            .map_err(|_| ImportError::InvalidUpload("The upload could not be read.".into()))?;
+        for record in records.feed(chunk.as_deref().unwrap_or_default()) {
+            row_number += 1;
+            if columns.is_none() {
+                columns = Some(ImportColumns::parse(&record).map_err(ImportError::InvalidUpload)?);
+                continue;
+            }
+            let (email, name) = columns.as_ref().unwrap().row(&record);
+            if email.is_empty() && name.is_empty() {
+                continue;
+            }
+            n_rows += 1;
+            let skipped = match parse_row(email, name) {
+                Err(e) => Some(e),
+                Ok((email, _)) if !seen_emails.insert(email.as_ref().to_lowercase()) => {
+                    Some(format!("{} appears earlier in the file.", email.as_ref()))
+                }
+                Ok((email, name)) => {
+                    import_subscriber(&mut transaction, &email, &name, list_id, pre_confirmed)
+                        .await?
+                }
+            };
+            match skipped {
+                Some(error) => skipped_rows.push(SkippedRow {
+                    row_number,
+                    email: email.to_owned(),
+                    error,
+                }),
+                None => n_imported += 1,
+            }
+        }
+        if chunk.is_none() {
+            break;
+        }
+    }
+    if columns.is_none() {
+        return Err(ImportError::InvalidUpload("The file is empty.".into()));
+    }
+
+    store_import(
+        &mut transaction,
+        Uuid::new_v4(),
+        &file_name,
+        n_rows,
+        n_imported,
+        &skipped_rows,
+    )
+    .await
+    .context("Failed to store a subscriber import.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to import subscribers.")?;
+    Ok((n_rows, n_imported))
+}
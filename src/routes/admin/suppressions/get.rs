@@ -0,0 +1,91 @@
+use crate::error::ResponseError;
+use crate::suppression::get_suppressed_emails;
+
+use anyhow::Context;
+use axum::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn suppressed_emails(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let suppressed = get_suppressed_emails(&pool)
+        .await
+        .context("Failed to retrieve the suppression list.")?;
+    let mut rows_html = String::new();
+    for entry in suppressed {
+        let email = htmlescape::encode_minimal(&entry.email);
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{email}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>
+                    <form action="/admin/suppressions/delete" method="post">
+                        <input hidden type="text" name="email" value="{}">
+                        <button type="submit">Remove</button>
+                    </form>
+                </td>
+            </tr>"#,
+            htmlescape::encode_minimal(&entry.reason),
+            entry.source,
+            entry.suppressed_at.to_rfc3339(),
+            htmlescape::encode_attribute(&entry.email),
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Suppression list</title>
+            </head>
+            <body>
+                {msg_html}
+                <p>Addresses on this list are never emailed, even if they subscribe again.</p>
+                <form action="/admin/suppressions" method="post">
+                    <label>Email
+                        <input type="text" placeholder="Enter an email address" name="email">
+                    </label>
+                    <label>Reason
+                        <input type="text" placeholder="e.g. asked to be forgotten" name="reason">
+                    </label>
+                    <button type="submit">Suppress</button>
+                </form>
+                <table>
+                    <tr><th>Email</th><th>Reason</th><th>Source</th><th>Suppressed at</th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
@@ -0,0 +1,73 @@
+use crate::domain::SubscriberEmail;
+use crate::error::ResponseError;
+use crate::suppression::{suppress_email, unsuppress_email};
+
+use anyhow::Context;
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension, Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct SuppressionFormData {
+    email: String,
+    #[serde(default)]
+    reason: String,
+}
+
+#[tracing::instrument(name = "Add an email address to the suppression list", skip_all)]
+pub async fn add_suppressed_email(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<SuppressionFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/suppressions");
+    let email = match SubscriberEmail::parse(form.email.trim().to_owned()) {
+        Ok(email) => email,
+        // Flash messages are rendered as is
+        Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect)),
+    };
+    let reason = form.reason.trim();
+    if reason.is_empty() {
+        return Ok((
+            flash.error("Give a reason for suppressing the address."),
+            redirect,
+        ));
+    }
+
+    let added = suppress_email(&*pool, email.as_ref(), reason, "admin")
+        .await
+        .context("Failed to add an email address to the suppression list.")?;
+    let email = htmlescape::encode_minimal(email.as_ref());
+    let flash = if added {
+        flash.info(format!("{} has been added to the suppression list.", email))
+    } else {
+        flash.error(format!("{} is already on the suppression list.", email))
+    };
+    Ok((flash, redirect))
+}
+
+#[tracing::instrument(name = "Remove an email address from the suppression list", skip_all)]
+pub async fn remove_suppressed_email(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<SuppressionFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let removed = unsuppress_email(&pool, &form.email)
+        .await
+        .context("Failed to remove an email address from the suppression list.")?;
+    let email = htmlescape::encode_minimal(&form.email);
+    let flash = if removed {
+        flash.info(format!(
+            "{} has been removed from the suppression list.",
+            email
+        ))
+    } else {
+        flash.error(format!("{} is not on the suppression list.", email))
+    };
+    Ok((flash, Redirect::to("/admin/suppressions")))
+}
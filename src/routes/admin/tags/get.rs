@@ -0,0 +1,108 @@
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    response::IntoResponse,
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn subscriber_tags(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let tags = get_subscriber_tags(&pool)
+        .await
+        .context("Failed to retrieve subscriber tags.")?;
+    let mut rows_html = String::new();
+    for tag in tags {
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>
+                    <form action="/admin/tags/delete" method="post">
+                        <input hidden type="text" name="email" value="{}">
+                        <input hidden type="text" name="tag" value="{}">
+                        <button type="submit">Remove</button>
+                    </form>
+                </td>
+            </tr>"#,
+            htmlescape::encode_minimal(&tag.tag),
+            htmlescape::encode_minimal(&tag.email),
+            htmlescape::encode_attribute(&tag.email),
+            htmlescape::encode_attribute(&tag.tag),
+        )
+        .unwrap();
+    }
+
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Tags</title>
+            </head>
+            <body>
+                {msg_html}
+                <form action="/admin/tags" method="post">
+                    <label>Email
+                        <input type="text" placeholder="Enter a subscriber's email address" name="email">
+                    </label>
+                    <label>Tag
+                        <input type="text" placeholder="e.g. beta" name="tag">
+                    </label>
+                    <button type="submit">Tag subscriber</button>
+                </form>
+                <table>
+                    <tr><th>Tag</th><th>Email</th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/segments">Manage segments</a></p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        ),
+    ))
+}
+
+struct TaggedSubscriber {
+    tag: String,
+    email: String,
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_subscriber_tags(pool: &PgPool) -> Result<Vec<TaggedSubscriber>, sqlx::Error> {
+    sqlx::query_as!(
+        TaggedSubscriber,
+        r#"
+        SELECT subscriber_tags.tag, subscriptions.email
+        FROM subscriber_tags
+        JOIN subscriptions ON subscriptions.id = subscriber_tags.subscriber_id
+        ORDER BY subscriber_tags.tag, subscriptions.email
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,102 @@
+use crate::domain::SubscriberTag;
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    response::{IntoResponse, Redirect},
+    Extension, Form,
+};
+use axum_flash::Flash;
+use sqlx::PgPool;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct SubscriberTagFormData {
+    email: String,
+    tag: String,
+}
+
+#[tracing::instrument(name = "Tag a subscriber", skip_all)]
+pub async fn add_subscriber_tag(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<SubscriberTagFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let redirect = Redirect::to("/admin/tags");
+    let tag = match SubscriberTag::parse(form.tag) {
+        Ok(tag) => tag,
+        // Flash messages are rendered as is
+        Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect)),
+    };
+    let email = form.email.trim();
+    let added = sqlx::query!(
+        r#"
+        INSERT INTO subscriber_tags (subscriber_id, tag)
+        SELECT id, $2 FROM subscriptions WHERE email = $1
+        ON CONFLICT DO NOTHING
+        RETURNING subscriber_id
+        "#,
+        email,
+        tag.as_ref()
+    )
+    .fetch_optional(&*pool)
+    .await
+    .context("Failed to tag a subscriber.")?;
+
+    let flash = if added.is_some() {
+        flash.info(format!(
+            "{} has been tagged {}.",
+            htmlescape::encode_minimal(email),
+            tag.as_ref()
+        ))
+    } else if is_subscriber(&pool, email).await? {
+        flash.error(format!(
+            "{} is already tagged {}.",
+            htmlescape::encode_minimal(email),
+            tag.as_ref()
+        ))
+    } else {
+        flash.error(format!("{} is not a subscriber.", htmlescape::encode_minimal(email)))
+    };
+    Ok((flash, redirect))
+}
+
+#[tracing::instrument(name = "Remove a tag from a subscriber", skip_all)]
+pub async fn remove_subscriber_tag(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<SubscriberTagFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM subscriber_tags
+        WHERE
+        tag = $2 AND
+        subscriber_id IN (SELECT id FROM subscriptions WHERE email = $1)
+        "#,
+        form.email,
+        form.tag
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to remove a tag from a subscriber.")?
+    .rows_affected();
+
+    let email = htmlescape::encode_minimal(&form.email);
+    let tag = htmlescape::encode_minimal(&form.tag);
+    let flash = if removed > 0 {
+        flash.info(format!("{} is no longer tagged {}.", email, tag))
+    } else {
+        flash.error(format!("{} is not tagged {}.", email, tag))
+    };
+    Ok((flash, Redirect::to("/admin/tags")))
+}
+
+async fn is_subscriber(pool: &PgPool, email: &str) -> Result<bool, anyhow::Error> {
+    let subscriber = sqlx::query!("SELECT id FROM subscriptions WHERE email = $1", email)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to look up a subscriber.")?;
+    Ok(subscriber.is_some())
+}
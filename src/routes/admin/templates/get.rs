@@ -0,0 +1,208 @@
+use crate::email_templates::{get_email_template, get_email_templates, EmailTemplate};
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    extract::Path,
+    http::{StatusCode, header::{self, HeaderMap, HeaderValue}},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_flash::IncomingFlashes;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+pub async fn list_email_templates(
+    flash_messages: IncomingFlashes,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let templates = get_email_templates(&pool)
+        .await
+        .context("Failed to retrieve email templates.")?;
+    let mut rows_html = String::new();
+    for template in templates {
+        let id = template.email_template_id;
+        writeln!(
+            rows_html,
+            r#"<tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td><a href="/admin/templates/{id}/edit">Edit</a></td>
+                <td>
+                    <form action="/admin/templates/{id}/delete" method="post">
+                        <button type="submit">Delete</button>
+                    </form>
+                </td>
+            </tr>"#,
+            htmlescape::encode_minimal(&template.name),
+            if template.is_default { "Default" } else { "" },
+        ).unwrap();
+    }
+
+    let msg_html = flash_messages_html(&flash_messages);
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Email layouts</title>
+            </head>
+            <body>
+                {msg_html}
+                <p><a href="/admin/templates/new">New layout</a></p>
+                <table>
+                    <tr><th>Name</th><th></th><th></th><th></th></tr>
+                    {rows_html}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ))
+}
+
+pub async fn new_email_template_form(
+    flash_messages: IncomingFlashes,
+) -> impl IntoResponse {
+    let msg_html = flash_messages_html(&flash_messages);
+    let form_html = email_template_form_html(
+        "/admin/templates",
+        &EmailTemplate {
+            email_template_id: Uuid::nil(),
+            name: String::new(),
+            html_layout: "{{ content }}".into(),
+            text_layout: "{{ content }}".into(),
+            is_default: false,
+        },
+    );
+    (
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>New layout</title>
+            </head>
+            <body>
+                {msg_html}
+                {form_html}
+                <p><a href="/admin/templates">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    )
+}
+
+pub async fn edit_email_template_form(
+    flash_messages: IncomingFlashes,
+    Path(email_template_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let template = match get_email_template(&pool, email_template_id)
+        .await
+        .context("Failed to retrieve email template.")?
+    {
+        Some(template) => template,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+
+    let msg_html = flash_messages_html(&flash_messages);
+    let form_html = email_template_form_html(
+        &format!("/admin/templates/{}", email_template_id),
+        &template,
+    );
+    Ok((
+        StatusCode::OK,
+        html_headers(),
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Edit layout</title>
+            </head>
+            <body>
+                {msg_html}
+                {form_html}
+                <p><a href="/admin/templates">&lt;- Back</a></p>
+            </body>
+            </html>"#,
+        )
+    ).into_response())
+}
+
+// Choices for the layout field of the forms that send email, the default layout is preselected
+pub fn email_template_options_html(templates: &[EmailTemplate]) -> String {
+    let mut options_html = String::from(r#"<option value="">No layout</option>"#);
+    for template in templates {
+        write!(
+            options_html,
+            r#"<option value="{}"{}>{}</option>"#,
+            template.email_template_id,
+            if template.is_default { " selected" } else { "" },
+            htmlescape::encode_minimal(&template.name),
+        ).unwrap();
+    }
+    options_html
+}
+
+fn email_template_form_html(action: &str, template: &EmailTemplate) -> String {
+    format!(
+        r#"<p>Layouts must contain {{{{ content }}}} where the email goes, and may use
+        {{{{ name }}}}, {{{{ email }}}} and {{{{ unsubscribe_url }}}}. The unsubscribe link is
+        left empty in confirmation emails.</p>
+        <form action="{action}" method="post">
+            <label>Name
+                <input
+                    type="text"
+                    placeholder="Enter layout name"
+                    name="name"
+                    value="{}"
+                >
+            </label>
+            <br>
+            <label>HTML layout
+                <textarea name="html_layout">{}</textarea>
+            </label>
+            <br>
+            <label>Text layout
+                <textarea name="text_layout">{}</textarea>
+            </label>
+            <br>
+            <label>Use for confirmation emails and preselect when publishing
+                <input type="checkbox" name="is_default"{}>
+            </label>
+            <br>
+            <button type="submit">Save layout</button>
+        </form>"#,
+        htmlescape::encode_attribute(&template.name),
+        htmlescape::encode_minimal(&template.html_layout),
+        htmlescape::encode_minimal(&template.text_layout),
+        if template.is_default { " checked" } else { "" },
+    )
+}
+
+fn flash_messages_html(flash_messages: &IncomingFlashes) -> String {
+    let mut msg_html = String::new();
+    for (_, msg) in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    msg_html
+}
+
+fn html_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    headers
+}
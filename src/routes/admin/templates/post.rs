@@ -0,0 +1,138 @@
+use crate::domain::EmailLayout;
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Redirect},
+    Extension,
+    Form,
+};
+use axum_flash::Flash;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct EmailTemplateFormData {
+    name: String,
+    html_layout: String,
+    #[serde(default)]
+    text_layout: String,
+    // Checkboxes are only submitted when ticked
+    is_default: Option<String>,
+}
+
+#[tracing::instrument(name = "Create an email template", skip_all)]
+pub async fn create_email_template(
+    flash: Flash,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<EmailTemplateFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    save_email_template(flash, &pool, Uuid::new_v4(), form, "/admin/templates/new").await
+}
+
+#[tracing::instrument(name = "Update an email template", skip(flash, pool, form))]
+pub async fn update_email_template(
+    flash: Flash,
+    Path(email_template_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Form(form): Form<EmailTemplateFormData>,
+) -> Result<impl IntoResponse, ResponseError> {
+    let edit_url = format!("/admin/templates/{}/edit", email_template_id);
+    save_email_template(flash, &pool, email_template_id, form, &edit_url).await
+}
+
+#[tracing::instrument(name = "Delete an email template", skip(flash, pool))]
+pub async fn delete_email_template(
+    flash: Flash,
+    Path(email_template_id): Path<Uuid>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, ResponseError> {
+    // Issues using the layout are sent without one from now on
+    sqlx::query!(
+        "DELETE FROM email_templates WHERE email_template_id = $1",
+        email_template_id
+    )
+    .execute(&*pool)
+    .await
+    .context("Failed to delete email template.")?;
+
+    let flash = flash.info("The layout has been deleted.");
+    Ok((flash, Redirect::to("/admin/templates")))
+}
+
+async fn save_email_template(
+    flash: Flash,
+    pool: &PgPool,
+    email_template_id: Uuid,
+    form: EmailTemplateFormData,
+    form_url: &str,
+) -> Result<(Flash, Redirect), ResponseError> {
+    let name = form.name.trim();
+    if name.is_empty() {
+        let flash = flash.error("The layout needs a name.");
+        return Ok((flash, Redirect::to(form_url)));
+    }
+    let layout = match EmailLayout::parse(form.html_layout, form.text_layout) {
+        Ok(layout) => layout,
+        Err(e) => return Ok((flash.error(e), Redirect::to(form_url))),
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let is_default = form.is_default.is_some();
+    if is_default {
+        clear_default_email_template(&mut transaction)
+            .await
+            .context("Failed to clear the default email template.")?;
+    }
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO email_templates (email_template_id, name, html_layout, text_layout, is_default)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (email_template_id) DO UPDATE
+        SET
+        name = EXCLUDED.name,
+        html_layout = EXCLUDED.html_layout,
+        text_layout = EXCLUDED.text_layout,
+        is_default = EXCLUDED.is_default
+        "#,
+        email_template_id,
+        name,
+        layout.html(),
+        layout.text(),
+        is_default
+    )
+    .execute(&mut transaction)
+    .await;
+    match saved {
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("email_templates_name_key") => {
+            let flash = flash.error("A layout with this name already exists.");
+            return Ok((flash, Redirect::to(form_url)));
+        }
+        saved => {
+            saved.context("Failed to save email template.")?;
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to save an email template.")?;
+
+    let flash = flash.info("The layout has been saved.");
+    Ok((flash, Redirect::to("/admin/templates")))
+}
+
+#[tracing::instrument(skip_all)]
+async fn clear_default_email_template(
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!("UPDATE email_templates SET is_default = false WHERE is_default")
+        .execute(transaction)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::archive::{get_archived_issue, get_archived_issues, ARCHIVE_MERGE_TAGS};
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    extract::Path,
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+#[tracing::instrument(name = "Show the newsletter archive", skip(pool))]
+pub async fn archive(
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let issues = get_archived_issues(&pool, None)
+        .await
+        .context("Failed to retrieve archived issues.")?;
+
+    let issues_html = if issues.is_empty() {
+        "<p>No issues have been published yet.</p>".to_string()
+    } else {
+        let mut issues_html = String::from("<ul>");
+        for issue in &issues {
+            writeln!(
+                issues_html,
+                r#"<li><a href="/archive/{}">{}</a> - {}</li>"#,
+                issue.slug,
+                htmlescape::encode_minimal(&ARCHIVE_MERGE_TAGS.render_text(&issue.title)),
+                issue.published_at.format("%Y-%m-%d"),
+            ).unwrap();
+        }
+        issues_html.push_str("</ul>");
+        issues_html
+    };
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>Archive</title>
+            <link rel="alternate" type="application/rss+xml" title="RSS" href="/feed.rss">
+            <link rel="alternate" type="application/atom+xml" title="Atom" href="/feed.atom">
+        </head>
+        <body>
+        <h1>Past issues</h1>
+        {issues_html}
+        </body>
+        </html>"#
+    );
+    Ok((StatusCode::OK, html_headers(), html).into_response())
+}
+
+#[tracing::instrument(name = "Show an archived issue", skip(pool))]
+pub async fn archived_issue(
+    Path(slug): Path<String>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let issue = match get_archived_issue(&pool, &slug)
+        .await
+        .context("Failed to retrieve archived issue.")?
+    {
+        Some(issue) => issue,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+
+    let title = htmlescape::encode_minimal(&ARCHIVE_MERGE_TAGS.render_text(&issue.title));
+    let published_at = issue.published_at.format("%Y-%m-%d");
+    let html_content = ARCHIVE_MERGE_TAGS.render_html(&issue.html_content);
+    let html = format!(
+        r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>{title}</title>
+        </head>
+        <body>
+        <h1>{title}</h1>
+        <p>Published on {published_at}</p>
+        {html_content}
+        <p><a href="/archive">&lt;- All issues</a></p>
+        </body>
+        </html>"#
+    );
+    Ok((StatusCode::OK, html_headers(), html).into_response())
+}
+
+fn html_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    headers
+}
@@ -0,0 +1,131 @@
+use crate::archive::{archive_url, get_archived_issues, ArchivedIssue, ARCHIVE_MERGE_TAGS};
+use crate::error::ResponseError;
+use crate::startup::ApplicationBaseUrl;
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use htmlescape::encode_minimal;
+use sqlx::PgPool;
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+// Number of most recent public issues included in a feed
+const FEED_LENGTH: i64 = 20;
+const FEED_TITLE: &str = "Newsletter";
+
+#[tracing::instrument(name = "Serve the RSS feed", skip_all)]
+pub async fn rss_feed(
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(base_url): Extension<ApplicationBaseUrl>,
+) -> Result<Response, ResponseError> {
+    let issues = get_feed_issues(&pool).await?;
+
+    let mut items = String::new();
+    for issue in &issues {
+        let link = archive_url(&base_url.0, &issue.slug);
+        writeln!(
+            items,
+            r#"<item>
+<title>{}</title>
+<link>{}</link>
+<guid isPermaLink="false">urn:uuid:{}</guid>
+<pubDate>{}</pubDate>
+<description>{}</description>
+</item>"#,
+            encode_minimal(&ARCHIVE_MERGE_TAGS.render_text(&issue.title)),
+            encode_minimal(&link),
+            issue.newsletter_issue_id,
+            issue.published_at.to_rfc2822(),
+            encode_minimal(&ARCHIVE_MERGE_TAGS.render_html(&issue.html_content)),
+        ).unwrap();
+    }
+
+    let archive_link = encode_minimal(&format!("{}/archive", base_url.0));
+    let feed_link = encode_minimal(&format!("{}/feed.rss", base_url.0));
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+<title>{FEED_TITLE}</title>
+<link>{archive_link}</link>
+<description>Past issues of our newsletter</description>
+<atom:link href="{feed_link}" rel="self" type="application/rss+xml"/>
+{items}</channel>
+</rss>"#
+    );
+    Ok((StatusCode::OK, feed_headers("application/rss+xml"), xml).into_response())
+}
+
+#[tracing::instrument(name = "Serve the Atom feed", skip_all)]
+pub async fn atom_feed(
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(base_url): Extension<ApplicationBaseUrl>,
+) -> Result<Response, ResponseError> {
+    let issues = get_feed_issues(&pool).await?;
+
+    let mut entries = String::new();
+    for issue in &issues {
+        let link = archive_url(&base_url.0, &issue.slug);
+        let published_at = issue.published_at.to_rfc3339();
+        writeln!(
+            entries,
+            r#"<entry>
+<title>{}</title>
+<link rel="alternate" type="text/html" href="{}"/>
+<id>urn:uuid:{}</id>
+<published>{published_at}</published>
+<updated>{published_at}</updated>
+<content type="html">{}</content>
+</entry>"#,
+            encode_minimal(&ARCHIVE_MERGE_TAGS.render_text(&issue.title)),
+            encode_minimal(&link),
+            issue.newsletter_issue_id,
+            encode_minimal(&ARCHIVE_MERGE_TAGS.render_html(&issue.html_content)),
+        ).unwrap();
+    }
+
+    // A feed must have an update time even before anything is published
+    let updated = issues
+        .first()
+        .map(|issue| issue.published_at)
+        .unwrap_or_else(Utc::now)
+        .to_rfc3339();
+    let archive_link = encode_minimal(&format!("{}/archive", base_url.0));
+    let feed_link = encode_minimal(&format!("{}/feed.atom", base_url.0));
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+<title>{FEED_TITLE}</title>
+<id>{feed_link}</id>
+<link rel="self" type="application/atom+xml" href="{feed_link}"/>
+<link rel="alternate" type="text/html" href="{archive_link}"/>
+<updated>{updated}</updated>
+<author><name>{FEED_TITLE}</name></author>
+{entries}</feed>"#
+    );
+    Ok((StatusCode::OK, feed_headers("application/atom+xml"), xml).into_response())
+}
+
+async fn get_feed_issues(pool: &PgPool) -> Result<Vec<ArchivedIssue>, anyhow::Error> {
+    get_archived_issues(pool, Some(FEED_LENGTH))
+        .await
+        .context("Failed to retrieve archived issues.")
+}
+
+fn feed_headers(content_type: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(&format!("{}; charset=utf-8", content_type)).unwrap(),
+    );
+    headers
+}
@@ -15,27 +15,39 @@ use axum_macros::debug_handler;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
-use crate::email_client::EmailClient;
+use crate::domain::{
+    EmailLayout, MergeTags, NewSubscriber, ProfileField, SubscriberEmail, SubscriberName,
+    SubscriberProfile,
+};
+use crate::email_client::EmailTransport;
+use crate::email_templates::get_default_email_layout;
+use crate::lists::get_list_by_name;
+use crate::profile_fields::{get_profile_fields, store_subscriber_profile};
 use crate::startup::ApplicationBaseUrl;
+use crate::suppression::is_email_suppressed;
 use crate::error::error_chain_fmt;
 
 #[derive(Deserialize)]
 pub struct FormData {
     email: String,
     name: String,
+    // Name of the list to join, empty for the default list
+    #[serde(default)]
+    list: String,
+    // Values of custom profile fields, keyed by field
+    #[serde(flatten)]
+    profile: HashMap<String, String>,
 }
 
-
-impl TryFrom<FormData> for NewSubscriber {
-    type Error = String;
-
-    fn try_from(value: FormData) -> Result<Self, Self::Error> {
-        let email = SubscriberEmail::parse(value.email)?;
-        let name = SubscriberName::parse(value.name)?;
-        Ok(NewSubscriber { email, name })
+impl FormData {
+    fn parse(self, profile_fields: &[ProfileField]) -> Result<NewSubscriber, String> {
+        let email = SubscriberEmail::parse(self.email)?;
+        let name = SubscriberName::parse(self.name)?;
+        let profile = SubscriberProfile::parse(profile_fields, &self.profile)?;
+        Ok(NewSubscriber { email, name, profile })
     }
 }
 
@@ -46,30 +58,75 @@ impl TryFrom<FormData> for NewSubscriber {
     fields(
         request_id = %Uuid::new_v4(),
         subscriber_email = %form.email,
-        subscriber_name = %form.name
+        subscriber_name = %form.name,
+        subscriber_list = %form.list
     )   
 )]
 pub async fn subscribe(
     Extension(pool): Extension<Arc<PgPool>>,
-    Extension(email_client): Extension<Arc<EmailClient>>,
+    Extension(email_client): Extension<Arc<dyn EmailTransport>>,
     Extension(base_url): Extension<ApplicationBaseUrl>,
     form: Form<FormData>, // Form must be last extractor, otherwise opaque error prevents compilation
 ) -> Result<StatusCode, SubscribeError> {
-    let new_subscriber = form.0.try_into().map_err(SubscribeError::ValidationError)?;
+    let list_name = form.list.clone();
+    let profile_fields = get_profile_fields(&pool).await?;
+    let new_subscriber = form.0.parse(&profile_fields).map_err(SubscribeError::ValidationError)?;
+    let list = get_list_by_name(&pool, &list_name)
+        .await
+        .context("Failed to look up the list to subscribe to")?
+        .ok_or_else(|| SubscribeError::ValidationError(format!("{} is not a known list.", list_name)))?;
     let mut transaction = pool.begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool.")?;
-    let subscriber_id = insert_subscriber(
-        &mut transaction,
-        &new_subscriber
-    )
-    .await
-    .context("Failed to insert a new subscriber in the database")?;
-    
+    let subscriber_id = match get_existing_subscriber(&mut transaction, &new_subscriber, list.list_id)
+        .await
+        .context("Failed to look up an existing subscriber")?
+    {
+        // Already confirmed for this list, nothing to do
+        Some((_, status, list_status))
+            if status == "confirmed" && list_status.as_deref() == Some("confirmed") =>
+        {
+            return Ok(StatusCode::OK)
+        }
+        // Pending, unsubscribed or new to this list, start the confirmation process over again
+        Some((subscriber_id, _, _)) => {
+            reset_pending_subscriber(&mut transaction, subscriber_id, list.list_id)
+                .await
+                .context("Failed to reset an existing subscriber")?;
+            subscriber_id
+        },
+        None => {
+            let subscriber_id = insert_subscriber(
+                &mut transaction,
+                &new_subscriber
+            )
+            .await
+            .context("Failed to insert a new subscriber in the database")?;
+
+            let unsubscribe_token = generate_subscription_token();
+            store_unsubscribe_token(
+                &mut transaction,
+                subscriber_id,
+                &unsubscribe_token
+            )
+            .await
+            .context("Failed to store the unsubscribe token for a new subscriber")?;
+            // Only for new addresses, the form must not overwrite someone else's profile
+            store_subscriber_profile(&mut transaction, subscriber_id, &new_subscriber.profile)
+                .await
+                .context("Failed to store the profile of a new subscriber")?;
+            subscriber_id
+        },
+    };
+    add_pending_list_subscription(&mut transaction, subscriber_id, list.list_id)
+        .await
+        .context("Failed to add a subscriber to a list")?;
+
     let subscription_token = generate_subscription_token();
     store_token(
         &mut transaction,
         subscriber_id,
+        list.list_id,
         &subscription_token
     )
     .await
@@ -80,8 +137,13 @@ pub async fn subscribe(
         .await
         .context("Failed to commit SQL transaction to store a new subscriber.")?;
 
+    let layout = get_default_email_layout(&pool)
+        .await
+        .context("Failed to retrieve the default email layout.")?;
     send_confirmation_email(
-        &email_client,
+        &pool,
+        email_client.as_ref(),
+        layout.as_ref(),
         new_subscriber,
         &base_url.0,
         &subscription_token,
@@ -116,16 +178,105 @@ pub async fn insert_subscriber(
     Ok(subscriber_id)
 }
 
+#[tracing::instrument(
+    name = "Look up an existing subscriber by email",
+    skip(new_subscriber, transaction)
+)]
+pub async fn get_existing_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+    list_id: Uuid,
+) -> Result<Option<(Uuid, String, Option<String>)>, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        SELECT id, subscriptions.status, list_subscriptions.status AS "list_status?"
+        FROM subscriptions
+        LEFT JOIN list_subscriptions ON
+        list_subscriptions.subscriber_id = subscriptions.id AND
+        list_subscriptions.list_id = $2
+        WHERE email = $1
+        FOR UPDATE OF subscriptions
+        "#,
+        new_subscriber.email.as_ref(),
+        list_id,
+    )
+    .fetch_optional(transaction)
+    .await?;
+    Ok(result.map(|r| (r.id, r.status, r.list_status)))
+}
+
+#[tracing::instrument(
+    name = "Reset an existing subscriber to pending confirmation",
+    skip(transaction)
+)]
+pub async fn reset_pending_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    list_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    // A confirmed address keeps receiving its other lists in the meantime,
+    // a bounced or complained one keeps the status reported by the provider
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions SET status = 'pending_confirmation'
+        WHERE id = $1 AND status IN ('pending_confirmation', 'unsubscribed')
+        "#,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // Only the most recently sent confirmation link for the list stays valid
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1 AND list_id = $2"#,
+        subscriber_id,
+        list_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Add a subscriber to a list pending confirmation",
+    skip(transaction)
+)]
+pub async fn add_pending_list_subscription(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    list_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO list_subscriptions (list_id, subscriber_id, status)
+        VALUES ($1, $2, 'pending_confirmation')
+        ON CONFLICT (list_id, subscriber_id) DO UPDATE
+        SET status = 'pending_confirmation', subscribed_at = now()
+        "#,
+        list_id,
+        subscriber_id
+    )
+    .execute(transaction)
+    .await?;
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "Send a confirmation email to a new subscriber",
-    skip(email_client, new_subscriber, base_url, subscription_token)
+    skip(pool, email_client, layout, new_subscriber, base_url, subscription_token)
 )]
 pub async fn send_confirmation_email(
-    email_client: &EmailClient,
+    pool: &PgPool,
+    email_client: &dyn EmailTransport,
+    layout: Option<&EmailLayout>,
     new_subscriber: NewSubscriber,
     base_url: &String,
     subscription_token: &str,
-) -> Result<(), reqwest::Error> {
+) -> Result<(), anyhow::Error> {
+    // The request still succeeds, the address is simply never confirmed
+    if is_email_suppressed(pool, new_subscriber.email.as_ref()).await? {
+        tracing::info!("Not sending a confirmation email to a suppressed address.");
+        return Ok(());
+    }
     let confirmation_link = format!(
         "{}/subscriptions/confirm?subscription_token={}",
         base_url,
@@ -140,17 +291,26 @@ pub async fn send_confirmation_email(
         Click <a href=\"{}\">here</a> to confirm your subscription.",
         confirmation_link
     );
+    // Not confirmed yet, so there is nothing to unsubscribe from
+    let merge_tags = MergeTags {
+        name: new_subscriber.name.as_ref(),
+        email: new_subscriber.email.as_ref(),
+        unsubscribe_url: "",
+        profile: new_subscriber.profile.values(),
+    };
+    let (html_body, plain_body) = EmailLayout::wrap(layout, &html_body, &plain_body);
     email_client
         .send_email(
             &new_subscriber.email,
             "Welcome!",
-            &html_body,
-            &plain_body,            
+            &merge_tags.render_html(&html_body),
+            &merge_tags.render_text(&plain_body),
+            &[],
         )
         .await
 }
 
-fn generate_subscription_token() -> String {
+pub fn generate_subscription_token() -> String {
     let mut rng = thread_rng();
     std::iter::repeat_with(|| rng.sample(Alphanumeric))
         .map(char::from)
@@ -165,16 +325,38 @@ fn generate_subscription_token() -> String {
 pub async fn store_token(
     transaction: &mut Transaction<'_, Postgres>,
     subscriber_id: Uuid,
+    list_id: Uuid,
     subscription_token: &str,
 ) -> Result<(), StoreTokenError> {
-    sqlx::query!(r#"INSERT INTO subscription_tokens (subscription_token, subscriber_id)
-        VALUES ($1, $2)"#,
+    sqlx::query!(r#"INSERT INTO subscription_tokens (subscription_token, subscriber_id, list_id)
+        VALUES ($1, $2, $3)"#,
         subscription_token,
-        subscriber_id    
+        subscriber_id,
+        list_id
+    )
+    .execute(transaction)
+    .await
+    .map_err(StoreTokenError)?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Store unsubscribe token in the database",
+    skip(unsubscribe_token, transaction)
+)]
+pub async fn store_unsubscribe_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    unsubscribe_token: &str,
+) -> Result<(), StoreTokenError> {
+    sqlx::query!(r#"INSERT INTO unsubscribe_tokens (unsubscribe_token, subscriber_id)
+        VALUES ($1, $2)"#,
+        unsubscribe_token,
+        subscriber_id
     )
     .execute(transaction)
     .await
-    .map_err(|e| StoreTokenError(e))?;
+    .map_err(StoreTokenError)?;
     Ok(())
 }
 
@@ -1,9 +1,15 @@
+use crate::startup::ConfirmationTokenTtl;
+
 use axum::{
     Extension,
     extract::Query,
-    http::StatusCode
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Response},
 };
-
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -17,50 +23,193 @@ pub struct Parameters {
 
 #[tracing::instrument(
     name = "Confirm a pending subscriber",
-    skip(parameters, pool)
+    skip(parameters, pool, token_ttl)
 )]
 pub async fn confirm(
     parameters: Query<Parameters>,
-    Extension(pool): Extension<Arc<PgPool>>
-) -> StatusCode {
-    let id = match get_subscriber_id_from_token(
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(token_ttl): Extension<ConfirmationTokenTtl>,
+) -> Response {
+    let token = match get_subscriber_id_from_token(
         &pool,
         &parameters.subscription_token,
     ).await {
-        Ok(id) => id,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+        Ok(token) => token,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
-    match id {
-        None => StatusCode::UNAUTHORIZED,
-        Some(subscriber_id) => {
-            if confirm_subscriber(&pool, subscriber_id).await.is_err() {
-                return StatusCode::INTERNAL_SERVER_ERROR;
+    match token {
+        None => StatusCode::UNAUTHORIZED.into_response(),
+        Some(token) if Utc::now() - token.created_at > token_ttl.0 => {
+            expired_token_page()
+        },
+        Some(SubscriptionToken { subscriber_id, new_email: Some(new_email), .. }) => {
+            match change_subscriber_email(&pool, subscriber_id, &new_email).await {
+                Ok(true) => StatusCode::OK.into_response(),
+                Ok(false) => email_taken_page(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
             }
-            StatusCode::OK
-        }
+        },
+        Some(SubscriptionToken { subscriber_id, list_id: Some(list_id), .. }) => {
+            if confirm_subscriber(&pool, subscriber_id, list_id).await.is_err() {
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            StatusCode::OK.into_response()
+        },
+        // Ruled out by a check constraint on subscription_tokens
+        Some(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
 }
 
+fn email_taken_page() -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    (
+        StatusCode::CONFLICT,
+        headers,
+        r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>Address already subscribed</title>
+        </head>
+        <body>
+        <p>This email address is already subscribed, so we kept your old one.</p>
+        </body>
+        </html>"#
+    ).into_response()
+}
+
+fn expired_token_page() -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    (
+        StatusCode::GONE,
+        headers,
+        r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>Confirmation link expired</title>
+        </head>
+        <body>
+        <p>This confirmation link has expired.</p>
+        <p>Subscribe again with the same email address and we will send you a new link.</p>
+        </body>
+        </html>"#
+    ).into_response()
+}
+
 #[tracing::instrument(
     name = "Mark subscriber as confirmed",
-    skip(subscriber_id, pool)
+    skip(subscriber_id, list_id, pool)
 )]
 pub async fn confirm_subscriber(
     pool: &PgPool,
     subscriber_id: Uuid,
+    list_id: Uuid,
 ) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    // Bounced, complained and unsubscribed addresses keep their status, an
+    // unsubscribed address is set back to pending when it signs up again
     sqlx::query!(
-        r#"UPDATE subscriptions SET status = 'confirmed' WHERE id = $1"#,
+        r#"
+        UPDATE subscriptions SET status = 'confirmed'
+        WHERE id = $1 AND status IN ('pending_confirmation', 'confirmed')
+        "#,
         subscriber_id
     )
-    .execute(pool)
+    .execute(&mut transaction)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
         e
     })?;
-    Ok(())
+    sqlx::query!(
+        r#"
+        UPDATE list_subscriptions SET status = 'confirmed'
+        WHERE subscriber_id = $1 AND list_id = $2 AND status = 'pending_confirmation'
+        "#,
+        subscriber_id,
+        list_id
+    )
+    .execute(&mut transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    // A used link cannot confirm the list again after the subscriber leaves it
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1 AND list_id = $2"#,
+        subscriber_id,
+        list_id
+    )
+    .execute(&mut transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    transaction.commit().await
+}
+
+// Returns false when the new address already belongs to another subscriber
+#[tracing::instrument(
+    name = "Change the email of a subscriber",
+    skip(subscriber_id, new_email, pool)
+)]
+pub async fn change_subscriber_email(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    new_email: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let updated = sqlx::query!(
+        r#"UPDATE subscriptions SET email = $2 WHERE id = $1"#,
+        subscriber_id,
+        new_email
+    )
+    .execute(&mut transaction)
+    .await;
+    match updated {
+        Err(sqlx::Error::Database(e)) if e.constraint() == Some("subscriptions_email_key") => {
+            return Ok(false);
+        }
+        updated => {
+            updated.map_err(|e| {
+                tracing::error!("Failed to execute query: {:?}", e);
+                e
+            })?;
+        }
+    }
+    // Links for any other address requested in the meantime are void
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1 AND new_email IS NOT NULL"#,
+        subscriber_id
+    )
+    .execute(&mut transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to execute query: {:?}", e);
+        e
+    })?;
+    transaction.commit().await?;
+    Ok(true)
+}
+
+// Confirms either a list subscription or a new email address
+pub struct SubscriptionToken {
+    subscriber_id: Uuid,
+    list_id: Option<Uuid>,
+    new_email: Option<String>,
+    created_at: DateTime<Utc>,
 }
 
 #[tracing::instrument(
@@ -70,9 +219,10 @@ pub async fn confirm_subscriber(
 pub async fn get_subscriber_id_from_token(
     pool: &PgPool,
     subscription_token: &str,
-) -> Result<Option<Uuid>, sqlx::Error> {
-    let result = sqlx::query!(
-        "SELECT subscriber_id FROM subscription_tokens \
+) -> Result<Option<SubscriptionToken>, sqlx::Error> {
+    sqlx::query_as!(
+        SubscriptionToken,
+        "SELECT subscriber_id, list_id, new_email, created_at FROM subscription_tokens \
         WHERE subscription_token = $1",
         subscription_token,
     )
@@ -81,6 +231,5 @@ pub async fn get_subscriber_id_from_token(
     .map_err(|e| {
         tracing::error!("Failed to execute query: {:?}", e);
         e
-    })?;
-    Ok(result.map(|r| r.subscriber_id))
-}
\ No newline at end of file
+    })
+}
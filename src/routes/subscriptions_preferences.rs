@@ -0,0 +1,480 @@
+use crate::domain::{EmailLayout, MergeTags, SubscriberEmail, SubscriberName};
+use crate::email_client::EmailTransport;
+use crate::email_templates::get_default_email_layout;
+use crate::error::ResponseError;
+use crate::profile_fields::get_subscriber_profile;
+use crate::routes::{generate_subscription_token, get_subscriber_id_from_unsubscribe_token};
+use crate::startup::ApplicationBaseUrl;
+use crate::suppression::is_email_suppressed;
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    Form,
+    extract::Query,
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_flash::{Flash, IncomingFlashes};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+// Lists are ticked with `list.<name>` checkboxes
+const LIST_CHECKBOX_PREFIX: &str = "list.";
+const MAX_PAUSE_WEEKS: i64 = 52;
+
+// The unsubscribe token doubles as the key to the preference center,
+// both are only ever sent to the subscriber's own address
+#[derive(serde::Deserialize)]
+pub struct PreferencesParameters {
+    unsubscribe_token: String,
+}
+
+#[tracing::instrument(
+    name = "Show subscriber preferences",
+    skip(flashes, parameters, pool)
+)]
+pub async fn preferences_form(
+    flashes: IncomingFlashes,
+    parameters: Query<PreferencesParameters>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let subscriber_id = match get_subscriber_id_from_unsubscribe_token(
+        &pool,
+        &parameters.unsubscribe_token,
+    )
+    .await?
+    {
+        Some(subscriber_id) => subscriber_id,
+        None => return Ok(StatusCode::UNAUTHORIZED.into_response()),
+    };
+    let subscriber = get_subscriber(&pool, subscriber_id)
+        .await
+        .context("Failed to retrieve the subscriber.")?;
+    let memberships = get_list_memberships(&pool, subscriber_id)
+        .await
+        .context("Failed to retrieve the lists of a subscriber.")?;
+
+    let mut msg_html = String::new();
+    for (_, msg) in flashes.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", msg).unwrap();
+    }
+    let mut lists_html = String::new();
+    for membership in memberships {
+        let is_member = membership.status.is_some() && membership.status.as_deref() != Some("unsubscribed");
+        writeln!(
+            lists_html,
+            r#"<label>
+                <input type="checkbox" name="{}{}"{}>
+                {}{}
+            </label>
+            <br>"#,
+            LIST_CHECKBOX_PREFIX,
+            membership.name,
+            if is_member { " checked" } else { "" },
+            htmlescape::encode_minimal(&membership.title),
+            if membership.status.as_deref() == Some("pending_confirmation") {
+                " (awaiting confirmation)"
+            } else {
+                ""
+            },
+        )
+        .unwrap();
+    }
+    let pause_html = match subscriber.paused_until {
+        Some(paused_until) if paused_until > Utc::now() => format!(
+            "<p>Delivery is paused until {}. Pause for 0 weeks to resume it now.</p>",
+            paused_until.format("%Y-%m-%d")
+        ),
+        _ => String::new(),
+    };
+    let action = htmlescape::encode_attribute(&preferences_path(&parameters.unsubscribe_token));
+    let unsubscribe_action = htmlescape::encode_attribute(&format!(
+        "/subscriptions/unsubscribe?unsubscribe_token={}",
+        urlencoding::encode(&parameters.unsubscribe_token)
+    ));
+    let name = htmlescape::encode_attribute(&subscriber.name);
+    let email = htmlescape::encode_attribute(&subscriber.email);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    Ok((
+        StatusCode::OK,
+        headers,
+        flashes, // Flashes must be in returned data in order to be removed from client
+        format!(
+            r#"<!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Your preferences</title>
+            </head>
+            <body>
+                {msg_html}
+                {pause_html}
+                <form action="{action}" method="post">
+                    <label>Name
+                        <input type="text" name="name" value="{name}">
+                    </label>
+                    <br>
+                    <label>Email
+                        <input type="email" name="email" value="{email}">
+                    </label>
+                    <br>
+                    <p>Lists</p>
+                    {lists_html}
+                    <label>Pause delivery for
+                        <input type="number" name="pause_weeks" min="0" max="{MAX_PAUSE_WEEKS}">
+                        weeks
+                    </label>
+                    <br>
+                    <button type="submit">Save preferences</button>
+                </form>
+                <form action="{unsubscribe_action}" method="post">
+                    <button type="submit">Unsubscribe from everything</button>
+                </form>
+            </body>
+            </html>"#,
+        ),
+    )
+        .into_response())
+}
+
+#[derive(serde::Deserialize)]
+pub struct PreferencesFormData {
+    name: String,
+    email: String,
+    // Empty to leave delivery as it is, 0 to resume it
+    #[serde(default)]
+    pause_weeks: String,
+    // Checkboxes are only submitted when ticked
+    #[serde(flatten)]
+    lists: HashMap<String, String>,
+}
+
+#[tracing::instrument(
+    name = "Update subscriber preferences",
+    skip(flash, parameters, pool, email_client, base_url, form)
+)]
+pub async fn update_preferences(
+    flash: Flash,
+    parameters: Query<PreferencesParameters>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(email_client): Extension<Arc<dyn EmailTransport>>,
+    Extension(base_url): Extension<ApplicationBaseUrl>,
+    Form(form): Form<PreferencesFormData>,
+) -> Result<Response, ResponseError> {
+    let subscriber_id = match get_subscriber_id_from_unsubscribe_token(
+        &pool,
+        &parameters.unsubscribe_token,
+    )
+    .await?
+    {
+        Some(subscriber_id) => subscriber_id,
+        None => return Ok(StatusCode::UNAUTHORIZED.into_response()),
+    };
+    let redirect = Redirect::to(&preferences_path(&parameters.unsubscribe_token));
+    let parsed = (|| {
+        Ok::<_, String>((
+            SubscriberName::parse(form.name)?,
+            SubscriberEmail::parse(form.email.trim().to_owned())?,
+            parse_pause_weeks(&form.pause_weeks)?,
+        ))
+    })();
+    let (name, email, pause_weeks) = match parsed {
+        Ok(parsed) => parsed,
+        // Flash messages are rendered as is
+        Err(e) => return Ok((flash.error(htmlescape::encode_minimal(&e)), redirect).into_response()),
+    };
+    let ticked_lists: Vec<&str> = form
+        .lists
+        .keys()
+        .filter_map(|key| key.strip_prefix(LIST_CHECKBOX_PREFIX))
+        .collect();
+
+    let subscriber = get_subscriber(&pool, subscriber_id)
+        .await
+        .context("Failed to retrieve the subscriber.")?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    let paused_until = match pause_weeks {
+        None => subscriber.paused_until,
+        Some(0) => None,
+        Some(weeks) => Some(Utc::now() + Duration::weeks(weeks)),
+    };
+    sqlx::query!(
+        r#"UPDATE subscriptions SET name = $2, paused_until = $3 WHERE id = $1"#,
+        subscriber_id,
+        name.as_ref(),
+        paused_until
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to update the subscriber.")?;
+    update_list_memberships(&mut transaction, subscriber_id, &ticked_lists)
+        .await
+        .context("Failed to update the lists of a subscriber.")?;
+    // The address only changes once the link sent to the new one is opened
+    let email_change_token = if email.as_ref() != subscriber.email {
+        let token = generate_subscription_token();
+        store_email_change_token(&mut transaction, subscriber_id, email.as_ref(), &token)
+            .await
+            .context("Failed to store the token for an email change.")?;
+        Some(token)
+    } else {
+        None
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to update preferences.")?;
+
+    let flash = match email_change_token {
+        Some(token) => {
+            let layout = get_default_email_layout(&pool)
+                .await
+                .context("Failed to retrieve the default email layout.")?;
+            send_email_change_confirmation(
+                &pool,
+                email_client.as_ref(),
+                layout.as_ref(),
+                &subscriber.email,
+                &name,
+                &email,
+                &base_url.0,
+                &token,
+            )
+            .await
+            .context("Failed to send an email change confirmation.")?;
+            flash.info(format!(
+                "Your preferences have been saved. Open the link we sent to {} to confirm your new address.",
+                htmlescape::encode_minimal(email.as_ref())
+            ))
+        }
+        None => flash.info("Your preferences have been saved."),
+    };
+    Ok((flash, redirect).into_response())
+}
+
+fn preferences_path(unsubscribe_token: &str) -> String {
+    format!(
+        "/subscriptions/preferences?unsubscribe_token={}",
+        urlencoding::encode(unsubscribe_token)
+    )
+}
+
+fn parse_pause_weeks(value: &str) -> Result<Option<i64>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse::<i64>() {
+        Ok(weeks) if (0..=MAX_PAUSE_WEEKS).contains(&weeks) => Ok(Some(weeks)),
+        _ => Err(format!(
+            "Delivery can be paused for 0 to {} weeks.",
+            MAX_PAUSE_WEEKS
+        )),
+    }
+}
+
+struct Subscriber {
+    email: String,
+    name: String,
+    paused_until: Option<DateTime<Utc>>,
+}
+
+#[tracing::instrument(skip(pool))]
+async fn get_subscriber(pool: &PgPool, subscriber_id: Uuid) -> Result<Subscriber, sqlx::Error> {
+    sqlx::query_as!(
+        Subscriber,
+        r#"SELECT email, name, paused_until FROM subscriptions WHERE id = $1"#,
+        subscriber_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+struct ListMembership {
+    name: String,
+    title: String,
+    status: Option<String>,
+}
+
+// Every list, with the subscriber's status on the ones they ever joined
+#[tracing::instrument(skip(pool))]
+async fn get_list_memberships(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+) -> Result<Vec<ListMembership>, sqlx::Error> {
+    sqlx::query_as!(
+        ListMembership,
+        r#"
+        SELECT lists.name, lists.title, list_subscriptions.status AS "status?"
+        FROM lists
+        LEFT JOIN list_subscriptions ON
+        list_subscriptions.list_id = lists.list_id AND
+        list_subscriptions.subscriber_id = $1
+        ORDER BY lists.name
+        "#,
+        subscriber_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// The token proves the subscriber owns the address, so ticked lists are
+// joined without another confirmation
+#[tracing::instrument(skip(transaction))]
+async fn update_list_memberships(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    ticked_lists: &[&str],
+) -> Result<(), sqlx::Error> {
+    let ticked_lists: Vec<String> = ticked_lists.iter().map(|l| l.to_string()).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO list_subscriptions (list_id, subscriber_id, status)
+        SELECT list_id, $1, 'confirmed'
+        FROM lists
+        WHERE name = ANY($2)
+        ON CONFLICT (list_id, subscriber_id) DO UPDATE
+        SET
+        status = 'confirmed',
+        subscribed_at = CASE
+            WHEN list_subscriptions.status = 'unsubscribed' THEN now()
+            ELSE list_subscriptions.subscribed_at
+        END
+        "#,
+        subscriber_id,
+        &ticked_lists
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE list_subscriptions SET status = 'unsubscribed'
+        WHERE
+        subscriber_id = $1 AND
+        list_id NOT IN (SELECT list_id FROM lists WHERE name = ANY($2))
+        "#,
+        subscriber_id,
+        &ticked_lists
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // Same rules as unsubscribing: the address follows its lists, while
+    // bounced and complained addresses stay that way
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET status = CASE
+            WHEN EXISTS (
+                SELECT 1 FROM list_subscriptions
+                WHERE subscriber_id = $1 AND status = 'confirmed'
+            ) THEN 'confirmed'
+            ELSE 'unsubscribed'
+        END
+        WHERE id = $1 AND status IN ('pending_confirmation', 'confirmed', 'unsubscribed')
+        "#,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Store email change token in the database",
+    skip(email_change_token, transaction)
+)]
+async fn store_email_change_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    new_email: &str,
+    email_change_token: &str,
+) -> Result<(), sqlx::Error> {
+    // Only the most recently requested address can be confirmed
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1 AND new_email IS NOT NULL"#,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO subscription_tokens (subscription_token, subscriber_id, new_email)
+        VALUES ($1, $2, $3)
+        "#,
+        email_change_token,
+        subscriber_id,
+        new_email
+    )
+    .execute(&mut *transaction)
+    .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(
+    name = "Send an email change confirmation",
+    skip(pool, email_client, layout, current_email, name, new_email, base_url, email_change_token)
+)]
+async fn send_email_change_confirmation(
+    pool: &PgPool,
+    email_client: &dyn EmailTransport,
+    layout: Option<&EmailLayout>,
+    current_email: &str,
+    name: &SubscriberName,
+    new_email: &SubscriberEmail,
+    base_url: &str,
+    email_change_token: &str,
+) -> Result<(), anyhow::Error> {
+    if is_email_suppressed(pool, new_email.as_ref()).await? {
+        tracing::info!("Not sending an email change confirmation to a suppressed address.");
+        return Ok(());
+    }
+    let confirmation_link = format!(
+        "{}/subscriptions/confirm?subscription_token={}",
+        base_url,
+        email_change_token
+    );
+    let plain_body = format!(
+        "Please confirm your new email address.\nVisit {} to receive our newsletter here from now on.",
+        confirmation_link
+    );
+    let html_body = format!(
+        "Please confirm your new email address.<br />\
+        Click <a href=\"{}\">here</a> to receive our newsletter here from now on.",
+        confirmation_link
+    );
+    let profile = get_subscriber_profile(pool, current_email).await?;
+    let merge_tags = MergeTags {
+        name: name.as_ref(),
+        email: new_email.as_ref(),
+        unsubscribe_url: "",
+        profile: &profile,
+    };
+    let (html_body, plain_body) = EmailLayout::wrap(layout, &html_body, &plain_body);
+    email_client
+        .send_email(
+            new_email,
+            "Confirm your new email address",
+            &merge_tags.render_html(&html_body),
+            &merge_tags.render_text(&plain_body),
+            &[],
+        )
+        .await
+}
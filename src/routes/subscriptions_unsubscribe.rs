@@ -0,0 +1,217 @@
+use crate::error::ResponseError;
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    extract::Query,
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub struct UnsubscribeParameters {
+    unsubscribe_token: String,
+    // Name of the list to leave, every list when missing
+    list: Option<String>,
+}
+
+// GET only renders a confirmation form so that link scanners prefetching
+// the URL cannot unsubscribe anyone; the change itself happens on POST.
+#[tracing::instrument(
+    name = "Show unsubscribe confirmation",
+    skip(parameters, pool)
+)]
+pub async fn unsubscribe_form(
+    parameters: Query<UnsubscribeParameters>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let subscriber_id = get_subscriber_id_from_unsubscribe_token(
+        &pool,
+        &parameters.unsubscribe_token,
+    )
+    .await?;
+    if subscriber_id.is_none() {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+    if let Some(list) = &parameters.list {
+        if get_list_id(&pool, list).await?.is_none() {
+            return Ok(unknown_list_page());
+        }
+    }
+
+    let mut action = format!(
+        "/subscriptions/unsubscribe?unsubscribe_token={}",
+        urlencoding::encode(&parameters.unsubscribe_token)
+    );
+    if let Some(list) = &parameters.list {
+        action.push_str(&format!("&list={}", urlencoding::encode(list)));
+    }
+    let action = htmlescape::encode_attribute(&action);
+    let html = format!(
+        r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>Unsubscribe</title>
+        </head>
+        <body>
+        <p>Are you sure you want to stop receiving our newsletter?</p>
+        <form action="{action}" method="post">
+            <input hidden type="text" name="List-Unsubscribe" value="One-Click">
+            <button type="submit">Unsubscribe</button>
+        </form>
+        </body>
+        </html>"#
+    );
+    Ok((StatusCode::OK, html_headers(), html).into_response())
+}
+
+// Also the target of RFC 8058 one-click requests, which POST a
+// `List-Unsubscribe=One-Click` body that carries no extra information.
+#[tracing::instrument(
+    name = "Unsubscribe a subscriber",
+    skip(parameters, pool)
+)]
+pub async fn unsubscribe(
+    parameters: Query<UnsubscribeParameters>,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<Response, ResponseError> {
+    let subscriber_id = match get_subscriber_id_from_unsubscribe_token(
+        &pool,
+        &parameters.unsubscribe_token,
+    )
+    .await?
+    {
+        Some(subscriber_id) => subscriber_id,
+        None => return Ok(StatusCode::UNAUTHORIZED.into_response()),
+    };
+    let list_id = match &parameters.list {
+        Some(list) => match get_list_id(&pool, list).await? {
+            Some(list_id) => Some(list_id),
+            None => return Ok(unknown_list_page()),
+        },
+        None => None,
+    };
+    mark_subscriber_as_unsubscribed(&pool, subscriber_id, list_id).await?;
+
+    let html = r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>Unsubscribed</title>
+        </head>
+        <body>
+        <p>You have been unsubscribed and will not receive any further issues.</p>
+        </body>
+        </html>"#;
+    Ok((StatusCode::OK, html_headers(), html).into_response())
+}
+
+fn unknown_list_page() -> Response {
+    let html = r#"<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta http-equiv="content-type" content="text/html; charset=utf-8">
+            <title>List not found</title>
+        </head>
+        <body>
+        <p>This list does not exist, so there is nothing to unsubscribe from.</p>
+        </body>
+        </html>"#;
+    (StatusCode::BAD_REQUEST, html_headers(), html).into_response()
+}
+
+fn html_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str("text/html; charset=utf-8").unwrap(),
+    );
+    headers
+}
+
+#[tracing::instrument(
+    name = "Mark subscriber as unsubscribed",
+    skip(subscriber_id, pool)
+)]
+pub async fn mark_subscriber_as_unsubscribed(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+    list_id: Option<Uuid>,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    sqlx::query!(
+        r#"
+        UPDATE list_subscriptions SET status = 'unsubscribed'
+        WHERE
+        subscriber_id = $1 AND
+        ($2::UUID IS NULL OR list_id = $2)
+        "#,
+        subscriber_id,
+        list_id
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to remove subscriber from their lists.")?;
+    // The address itself is unsubscribed once it has left every list,
+    // bounced and complained addresses keep their status
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions SET status = 'unsubscribed'
+        WHERE
+        id = $1 AND
+        status IN ('pending_confirmation', 'confirmed', 'unsubscribed') AND
+        NOT EXISTS (
+            SELECT 1 FROM list_subscriptions
+            WHERE subscriber_id = $1 AND status <> 'unsubscribed'
+        )
+        "#,
+        subscriber_id
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to mark subscriber as unsubscribed.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to unsubscribe a subscriber.")?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get list_id from list name", skip(pool))]
+async fn get_list_id(pool: &PgPool, name: &str) -> Result<Option<Uuid>, anyhow::Error> {
+    let result = sqlx::query!("SELECT list_id FROM lists WHERE name = $1", name)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to retrieve list_id for list name.")?;
+    Ok(result.map(|r| r.list_id))
+}
+
+#[tracing::instrument(
+    name = "Get subscriber_id from unsubscribe token",
+    skip(unsubscribe_token, pool)
+)]
+pub async fn get_subscriber_id_from_unsubscribe_token(
+    pool: &PgPool,
+    unsubscribe_token: &str,
+) -> Result<Option<Uuid>, anyhow::Error> {
+    let result = sqlx::query!(
+        "SELECT subscriber_id FROM unsubscribe_tokens \
+        WHERE unsubscribe_token = $1",
+        unsubscribe_token,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve subscriber_id for unsubscribe token.")?;
+    Ok(result.map(|r| r.subscriber_id))
+}
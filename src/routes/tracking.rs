@@ -0,0 +1,64 @@
+use crate::error::ResponseError;
+use crate::startup::TrackingEnabled;
+use crate::tracking::{get_tracked_url, record_tracking_event};
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    extract::Path,
+    http::{
+        header::{self, HeaderValue, HeaderMap},
+        StatusCode
+    },
+    response::{IntoResponse, Redirect, Response},
+};
+use sqlx::PgPool;
+
+use std::sync::Arc;
+
+// 1x1 transparent GIF
+const TRACKING_PIXEL: [u8; 43] = [
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00,
+    0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+// With tracking switched off, emails sent earlier still get their pixel
+// and working links, nothing is recorded
+#[tracing::instrument(name = "Track an open", skip(pool, tracking_enabled))]
+pub async fn track_open(
+    Path(tracking_token): Path<String>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(tracking_enabled): Extension<TrackingEnabled>,
+) -> Result<Response, ResponseError> {
+    if tracking_enabled.0 {
+        record_tracking_event(&pool, &tracking_token)
+            .await
+            .context("Failed to record an open.")?;
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/gif"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok((StatusCode::OK, headers, TRACKING_PIXEL.as_slice()).into_response())
+}
+
+#[tracing::instrument(name = "Track a click", skip(pool, tracking_enabled))]
+pub async fn track_click(
+    Path(tracking_token): Path<String>,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Extension(tracking_enabled): Extension<TrackingEnabled>,
+) -> Result<Response, ResponseError> {
+    let url = match get_tracked_url(&pool, &tracking_token)
+        .await
+        .context("Failed to retrieve a tracked link.")?
+    {
+        Some(url) if HeaderValue::from_str(&url).is_ok() => url,
+        _ => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    if tracking_enabled.0 {
+        record_tracking_event(&pool, &tracking_token)
+            .await
+            .context("Failed to record a click.")?;
+    }
+    Ok(Redirect::to(&url).into_response())
+}
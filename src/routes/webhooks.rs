@@ -0,0 +1,139 @@
+use crate::error::ResponseError;
+use crate::suppression::suppress_email;
+
+use anyhow::Context;
+use axum::{
+    Extension,
+    Json,
+    http::StatusCode,
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+// Postmark webhook payloads, only the fields we act on.
+// Callers are authenticated by reject_unauthenticated_webhooks.
+#[derive(serde::Deserialize)]
+#[serde(tag = "RecordType")]
+pub enum EmailEvent {
+    Bounce {
+        #[serde(rename = "Email")]
+        email: String,
+        #[serde(rename = "Type")]
+        bounce_type: String,
+        #[serde(rename = "Description", default)]
+        description: String,
+    },
+    SpamComplaint {
+        #[serde(rename = "Email")]
+        email: String,
+    },
+    Delivery {
+        #[serde(rename = "Recipient")]
+        recipient: String,
+        #[serde(rename = "Details", default)]
+        details: String,
+    },
+    // Opens, clicks and anything added later are acknowledged and ignored
+    #[serde(other)]
+    Other,
+}
+
+// Soft bounces are transient, only these stop future sends
+const PERMANENT_BOUNCE_TYPES: [&str; 3] = ["HardBounce", "BadEmailAddress", "ManuallyDeactivated"];
+
+#[tracing::instrument(name = "Handle an email event", skip_all)]
+pub async fn email_events(
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(event): Json<EmailEvent>,
+) -> Result<StatusCode, ResponseError> {
+    let (record_type, email, details, new_status) = match &event {
+        EmailEvent::Bounce { email, bounce_type, description } => {
+            let new_status = PERMANENT_BOUNCE_TYPES
+                .contains(&bounce_type.as_str())
+                .then_some("bounced");
+            (
+                "Bounce",
+                email,
+                format!("{}: {}", bounce_type, description),
+                new_status,
+            )
+        }
+        EmailEvent::SpamComplaint { email } => {
+            ("SpamComplaint", email, String::new(), Some("complained"))
+        }
+        EmailEvent::Delivery { recipient, details } => {
+            ("Delivery", recipient, details.clone(), None)
+        }
+        EmailEvent::Other => return Ok(StatusCode::OK),
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    store_email_event(&mut transaction, record_type, email, &details)
+        .await
+        .context("Failed to store an email event.")?;
+    if let Some(new_status) = new_status {
+        update_subscription_status(&mut transaction, email, new_status)
+            .await
+            .context("Failed to update the status of a subscriber.")?;
+        suppress_email(&mut *transaction, email, new_status, "webhook")
+            .await
+            .context("Failed to add an email address to the suppression list.")?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to store an email event.")?;
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(skip(transaction, details))]
+async fn store_email_event(
+    transaction: &mut Transaction<'_, Postgres>,
+    record_type: &str,
+    email: &str,
+    details: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_events (email_event_id, record_type, email, details, received_at)
+        VALUES ($1, $2, $3, $4, now())
+        "#,
+        Uuid::new_v4(),
+        record_type,
+        email,
+        details
+    )
+    .execute(transaction)
+    .await?;
+    Ok(())
+}
+
+// Only confirmed subscribers are enqueued, so any other status stops future sends.
+// A complaint is never downgraded to a bounce. Providers may report the address
+// in a different case than it was signed up with.
+#[tracing::instrument(skip(transaction))]
+async fn update_subscription_status(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &str,
+    new_status: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET status = $2
+        WHERE
+        lower(email) = lower($1) AND
+        status <> 'complained'
+        "#,
+        email,
+        new_status
+    )
+    .execute(transaction)
+    .await?;
+    Ok(())
+}
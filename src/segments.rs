@@ -0,0 +1,190 @@
+use crate::error::error_chain_fmt;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct Segment {
+    pub segment_id: Uuid,
+    pub name: String,
+    pub required_tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub subscribed_after: Option<DateTime<Utc>>,
+    pub subscribed_before: Option<DateTime<Utc>>,
+    pub profile_field_key: Option<String>,
+    pub profile_field_value: Option<String>,
+    pub subscriber_status: String,
+}
+
+impl Segment {
+    pub fn describe(&self) -> String {
+        let mut rules = Vec::new();
+        if !self.required_tags.is_empty() {
+            rules.push(format!("tagged {}", self.required_tags.join(" and ")));
+        }
+        if !self.excluded_tags.is_empty() {
+            rules.push(format!("not tagged {}", self.excluded_tags.join(" or ")));
+        }
+        if let Some(after) = self.subscribed_after {
+            rules.push(format!("joined on or after {}", after.format("%Y-%m-%d")));
+        }
+        if let Some(before) = self.subscribed_before {
+            rules.push(format!("joined before {}", before.format("%Y-%m-%d")));
+        }
+        if let (Some(key), Some(value)) = (&self.profile_field_key, &self.profile_field_value) {
+            rules.push(format!("whose {} is {}", key, value));
+        }
+        let (everyone, members) = if self.subscriber_status == "pending_confirmation" {
+            ("Every member pending confirmation", "Members pending confirmation")
+        } else {
+            ("Every confirmed member", "Confirmed members")
+        };
+        if rules.is_empty() {
+            everyone.into()
+        } else {
+            format!("{} {}", members, rules.join(", "))
+        }
+    }
+}
+
+#[tracing::instrument(name = "Get segments", skip(pool))]
+pub async fn get_segments(pool: &PgPool) -> Result<Vec<Segment>, sqlx::Error> {
+    sqlx::query_as!(
+        Segment,
+        r#"
+        SELECT
+        segment_id,
+        name,
+        required_tags,
+        excluded_tags,
+        subscribed_after,
+        subscribed_before,
+        profile_field_key,
+        profile_field_value,
+        subscriber_status
+        FROM segments
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Midnight UTC at the start of the given day
+pub fn parse_date(value: &str) -> Result<Option<DateTime<Utc>>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("{} is not a valid date.", value))?;
+    Ok(Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())))
+}
+
+// Issues are only ever sent to confirmed members or to those who have not confirmed yet
+pub fn parse_subscriber_status(value: &str) -> Result<&'static str, String> {
+    match value {
+        "" | "confirmed" => Ok("confirmed"),
+        "pending_confirmation" => Ok("pending_confirmation"),
+        _ => Err(format!("{} is not a status a segment can pick.", value)),
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum SelectSegmentError {
+    #[error("The selected segment no longer exists.")]
+    UnknownSegment,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for SelectSegmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+// Looks up the segment picked in a form, an empty value means the whole list
+#[tracing::instrument(name = "Get selected segment", skip(pool))]
+pub async fn get_selected_segment(
+    pool: &PgPool,
+    segment_id: &str,
+) -> Result<Option<Uuid>, SelectSegmentError> {
+    if segment_id.is_empty() {
+        return Ok(None);
+    }
+    let segment_id = Uuid::parse_str(segment_id)
+        .map_err(|_| SelectSegmentError::UnknownSegment)?;
+    let segment = sqlx::query!(
+        "SELECT segment_id FROM segments WHERE segment_id = $1",
+        segment_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(anyhow::Error::from)?
+    .ok_or(SelectSegmentError::UnknownSegment)?;
+    Ok(Some(segment.segment_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_subscriber_status, Segment};
+    use chrono::{TimeZone, Utc};
+    use claims::assert_err;
+    use uuid::Uuid;
+
+    fn segment() -> Segment {
+        Segment {
+            segment_id: Uuid::new_v4(),
+            name: "Everyone".into(),
+            required_tags: vec![],
+            excluded_tags: vec![],
+            subscribed_after: None,
+            subscribed_before: None,
+            profile_field_key: None,
+            profile_field_value: None,
+            subscriber_status: "confirmed".into(),
+        }
+    }
+
+    #[test]
+    fn a_segment_without_rules_is_every_confirmed_member() {
+        assert_eq!(segment().describe(), "Every confirmed member");
+    }
+
+    #[test]
+    fn every_rule_is_described() {
+        let segment = Segment {
+            required_tags: vec!["beta".into(), "vip".into()],
+            excluded_tags: vec!["churned".into()],
+            subscribed_after: Some(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()),
+            subscribed_before: Some(Utc.with_ymd_and_hms(2023, 7, 1, 0, 0, 0).unwrap()),
+            profile_field_key: Some("plan".into()),
+            profile_field_value: Some("pro".into()),
+            ..segment()
+        };
+
+        assert_eq!(
+            segment.describe(),
+            "Confirmed members tagged beta and vip, not tagged churned, \
+            joined on or after 2023-01-01, joined before 2023-07-01, whose plan is pro"
+        );
+    }
+
+    #[test]
+    fn segments_of_pending_members_are_described_as_such() {
+        let segment = Segment {
+            subscriber_status: "pending_confirmation".into(),
+            required_tags: vec!["beta".into()],
+            ..segment()
+        };
+
+        assert_eq!(segment.describe(), "Members pending confirmation tagged beta");
+    }
+
+    #[test]
+    fn segments_cannot_pick_members_who_left() {
+        assert_err!(parse_subscriber_status("unsubscribed"));
+        assert_err!(parse_subscriber_status("bounced"));
+    }
+}
@@ -0,0 +1,106 @@
+use tokio::sync::watch;
+
+use std::time::Duration;
+
+pub struct ShutdownTrigger(watch::Sender<bool>);
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+#[derive(Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    // Resolves once shutdown is triggered, or the trigger has been dropped
+    pub async fn recv(&mut self) {
+        while !self.is_triggered() {
+            if self.0.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // Returns early if shutdown is triggered while sleeping
+    pub async fn sleep(&mut self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {},
+            _ = self.recv() => {},
+        }
+    }
+}
+
+pub fn shutdown_channel() -> (ShutdownTrigger, ShutdownSignal) {
+    let (sender, receiver) = watch::channel(false);
+    (ShutdownTrigger(sender), ShutdownSignal(receiver))
+}
+
+pub async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shutdown_channel;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn signal_resolves_once_triggered() {
+        let (trigger, mut signal) = shutdown_channel();
+        assert!(!signal.is_triggered());
+
+        trigger.trigger();
+
+        tokio::time::timeout(Duration::from_secs(1), signal.recv())
+            .await
+            .expect("Shutdown signal was not received");
+        assert!(signal.is_triggered());
+    }
+
+    #[tokio::test]
+    async fn signal_resolves_if_trigger_is_dropped() {
+        let (trigger, mut signal) = shutdown_channel();
+
+        drop(trigger);
+
+        tokio::time::timeout(Duration::from_secs(1), signal.recv())
+            .await
+            .expect("Shutdown signal was not received");
+    }
+
+    #[tokio::test]
+    async fn sleep_is_interrupted_by_shutdown() {
+        let (trigger, mut signal) = shutdown_channel();
+        trigger.trigger();
+
+        tokio::time::timeout(Duration::from_secs(1), signal.sleep(Duration::from_secs(60)))
+            .await
+            .expect("Sleep was not interrupted");
+    }
+}
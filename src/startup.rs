@@ -1,12 +1,29 @@
-use crate::authentication::reject_anonymous_users;
-use crate::configuration::{DatabaseSettings, Settings};
-use crate::email_client::EmailClient;
+use crate::authentication::{reject_anonymous_users, reject_unauthenticated_webhooks};
+use crate::configuration::{DatabaseSettings, Settings, WebhookSettings};
+use crate::email_client::EmailTransport;
+use crate::shutdown::ShutdownSignal;
 use crate::routes::{
     health_check,
     home,
-    subscribe, confirm,
+    archive, archived_issue, rss_feed, atom_feed,
+    track_open, track_click,
+    email_events,
+    subscribe, confirm, unsubscribe_form, unsubscribe, preferences_form, update_preferences,
     publish_newsletter,
-    login_form, login, admin_dashboard, change_password_form, change_password, logout, publish_newsletter_form
+    login_form, login, admin_dashboard, change_password_form, change_password, logout, publish_newsletter_form,
+    delivery_failures, issues, issue_delivery_status,
+    scheduled_newsletters, reschedule_newsletter, cancel_newsletter,
+    drafts, new_draft_form, edit_draft_form, preview_draft,
+    create_draft, update_draft, delete_draft, publish_draft, send_test_draft,
+    send_test_newsletter,
+    list_email_templates, new_email_template_form, edit_email_template_form,
+    create_email_template, update_email_template, delete_email_template,
+    suppressed_emails, add_suppressed_email, remove_suppressed_email,
+    lists, create_list,
+    segments, create_segment, subscriber_tags, add_subscriber_tag, remove_subscriber_tag,
+    profile_fields, create_profile_field,
+    import_subscribers_form, import_subscribers, import_report, MAX_IMPORT_SIZE,
+    export_subscribers,
 };
 
 use axum::middleware;
@@ -15,7 +32,7 @@ use sqlx::postgres::PgPoolOptions;
 
 use axum::{
     Extension,
-    extract::FromRef,
+    extract::{DefaultBodyLimit, FromRef},
     Router,
     routing::{get, post, IntoMakeService},
     Server, // Re-export of Server from hyper crate
@@ -37,20 +54,30 @@ pub struct Application {
 #[derive(Clone  )]
 pub struct ApplicationBaseUrl(pub String);
 
+#[derive(Clone)]
+pub struct ConfirmationTokenTtl(pub chrono::Duration);
+
+#[derive(Clone)]
+pub struct TrackingEnabled(pub bool);
+
 impl Application {
     pub async fn build(
-        configuration: Settings
+        configuration: Settings,
+        email_client: Arc<dyn EmailTransport>,
     ) -> Result<Self, anyhow::Error> {
         let connection_pool = get_connection_pool(&configuration.database); 
-        let email_client = configuration.email_client.client();
         let address = format!("{}:{}", configuration.application.host, configuration.application.port);
         let listener = TcpListener::bind(address).expect("Unable to bind to address");
         let port = listener.local_addr().unwrap().port();
+        let confirmation_token_ttl = configuration.application.confirmation_token_ttl();
         let server = run(
             listener,
             connection_pool,
             email_client,
             configuration.application.base_url,
+            confirmation_token_ttl,
+            configuration.application.tracking_enabled,
+            configuration.webhooks,
             configuration.application.hmac_secret,
             configuration.redis_uri,
         ).await?;
@@ -62,8 +89,11 @@ impl Application {
         self.port
     }
 
-    pub async fn run_until_stopped(self) -> Result<(), hyper::Error> {
-        self.server.await
+    pub async fn run_until_stopped(self, mut shutdown: ShutdownSignal) -> Result<(), hyper::Error> {
+        // Stops accepting connections on shutdown, in-flight requests are allowed to finish
+        self.server
+            .with_graceful_shutdown(async move { shutdown.recv().await })
+            .await
     }
 }
 
@@ -86,18 +116,23 @@ impl FromRef<AppState> for axum_flash::Config {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     listener: TcpListener,
     db_pool: PgPool,
-    email_client: EmailClient,
+    email_client: Arc<dyn EmailTransport>,
     base_url: String,
+    confirmation_token_ttl: chrono::Duration,
+    tracking_enabled: bool,
+    webhooks: WebhookSettings,
     hmac_secret: Secret<String>,
     redis_uri: Secret<String>,
 ) -> Result<Server<hyper::server::conn::AddrIncoming, IntoMakeService<Router>>, anyhow::Error> {
     // State must be cloneable for the into_make_service call, hence Arc
     let db_pool = Arc::new(db_pool);
-    let email_client = Arc::new(email_client);
     let base_url = ApplicationBaseUrl(base_url);
+    let confirmation_token_ttl = ConfirmationTokenTtl(confirmation_token_ttl);
+    let tracking_enabled = TrackingEnabled(tracking_enabled);
 
     let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
 
@@ -116,16 +151,71 @@ pub async fn run(
         .route("/admin/logout", post(logout::<SessionRedisPool>))
         .route("/admin/newsletters", get(publish_newsletter_form::<SessionRedisPool>))
         .route("/admin/newsletters", post(publish_newsletter::<SessionRedisPool>))
+        .route("/admin/newsletters/test", post(send_test_newsletter))
+        .route("/admin/newsletters/scheduled", get(scheduled_newsletters))
+        .route("/admin/newsletters/:issue_id/reschedule", post(reschedule_newsletter))
+        .route("/admin/newsletters/:issue_id/cancel", post(cancel_newsletter))
+        .route("/admin/drafts", get(drafts))
+        .route("/admin/drafts", post(create_draft))
+        .route("/admin/drafts/new", get(new_draft_form))
+        .route("/admin/drafts/:issue_id", post(update_draft))
+        .route("/admin/drafts/:issue_id/edit", get(edit_draft_form))
+        .route("/admin/drafts/:issue_id/preview", get(preview_draft))
+        .route("/admin/drafts/:issue_id/delete", post(delete_draft))
+        .route("/admin/drafts/:issue_id/publish", post(publish_draft))
+        .route("/admin/drafts/:issue_id/test", post(send_test_draft))
+        .route("/admin/templates", get(list_email_templates))
+        .route("/admin/templates", post(create_email_template))
+        .route("/admin/templates/new", get(new_email_template_form))
+        .route("/admin/templates/:email_template_id", post(update_email_template))
+        .route("/admin/templates/:email_template_id/edit", get(edit_email_template_form))
+        .route("/admin/templates/:email_template_id/delete", post(delete_email_template))
+        .route("/admin/issues", get(issues))
+        .route("/admin/issues/:issue_id", get(issue_delivery_status))
+        .route("/admin/delivery_failures", get(delivery_failures))
+        .route("/admin/lists", get(lists))
+        .route("/admin/lists", post(create_list))
+        .route("/admin/segments", get(segments))
+        .route("/admin/segments", post(create_segment))
+        .route("/admin/profile_fields", get(profile_fields))
+        .route("/admin/profile_fields", post(create_profile_field))
+        .route("/admin/tags", get(subscriber_tags))
+        .route("/admin/tags", post(add_subscriber_tag))
+        .route("/admin/tags/delete", post(remove_subscriber_tag))
+        .route("/admin/subscribers/import", get(import_subscribers_form))
+        .route(
+            "/admin/subscribers/import",
+            post(import_subscribers).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route("/admin/subscribers/import/:import_id/report", get(import_report))
+        .route("/admin/subscribers/export", get(export_subscribers))
+        .route("/admin/suppressions", get(suppressed_emails))
+        .route("/admin/suppressions", post(add_suppressed_email))
+        .route("/admin/suppressions/delete", post(remove_suppressed_email))
         .layer(middleware::from_fn_with_state(app_state.clone(), reject_anonymous_users));
+    let webhook_routes = Router::new()
+        .route("/webhooks/email-events", post(email_events))
+        .layer(middleware::from_fn(reject_unauthenticated_webhooks));
 
     let app = Router::new()
         .route("/", get(home))
         .route("/health_check", get(health_check))
+        .route("/archive", get(archive))
+        .route("/archive/:slug", get(archived_issue))
+        .route("/feed.rss", get(rss_feed))
+        .route("/feed.atom", get(atom_feed))
+        .route("/t/o/:tracking_token", get(track_open))
+        .route("/t/c/:tracking_token", get(track_click))
         .route("/login", get(login_form))
         .route("/login", post(login))
         .route("/subscriptions", post(subscribe))
         .route("/subscriptions/confirm", get(confirm))
+        .route("/subscriptions/unsubscribe", get(unsubscribe_form))
+        .route("/subscriptions/unsubscribe", post(unsubscribe))
+        .route("/subscriptions/preferences", get(preferences_form))
+        .route("/subscriptions/preferences", post(update_preferences))
         .merge(admin_routes)
+        .merge(webhook_routes)
         .layer(SessionLayer::new(redis_store))
         .layer(
             ServiceBuilder::new()
@@ -144,6 +234,9 @@ pub async fn run(
         .layer(Extension(db_pool))
         .layer(Extension(email_client))
         .layer(Extension(base_url))
+        .layer(Extension(confirmation_token_ttl))
+        .layer(Extension(tracking_enabled))
+        .layer(Extension(webhooks))
         .with_state(app_state);
 
     let server = Server::from_tcp(listener)?
@@ -0,0 +1,247 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::{stream, Stream, TryStreamExt};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use std::sync::Arc;
+
+pub const SUBSCRIBER_STATUSES: [&str; 5] = [
+    "pending_confirmation",
+    "confirmed",
+    "unsubscribed",
+    "bounced",
+    "complained",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    // One JSON object per line
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<ExportFormat, String> {
+        match format {
+            "" | "csv" => Ok(ExportFormat::Csv),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(format!("{} is not an export format, use csv or ndjson.", format)),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    fn header(&self) -> Option<&'static str> {
+        match self {
+            ExportFormat::Csv => Some("id,email,name,status,subscribed_at,lists,tags\r\n"),
+            ExportFormat::Ndjson => None,
+        }
+    }
+
+    fn line(&self, subscriber: &ExportedSubscriber) -> String {
+        let subscribed_at = subscriber
+            .subscribed_at
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        match self {
+            ExportFormat::Csv => format!(
+                "{},{},{},{},{},{},{}\r\n",
+                subscriber.id,
+                quote_field(&subscriber.email),
+                quote_field(&subscriber.name),
+                subscriber.status,
+                subscribed_at,
+                quote_field(&subscriber.lists.join(";")),
+                quote_field(&subscriber.tags.join(";")),
+            ),
+            ExportFormat::Ndjson => format!(
+                "{}\n",
+                serde_json::json!({
+                    "id": subscriber.id,
+                    "email": subscriber.email,
+                    "name": subscriber.name,
+                    "status": subscriber.status,
+                    "subscribed_at": subscribed_at,
+                    "lists": subscriber.lists,
+                    "tags": subscriber.tags,
+                })
+            ),
+        }
+    }
+}
+
+// Plain RFC 4180 quoting. Unlike the import report, values are exported
+// unchanged so that the file can be imported back as is.
+fn quote_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+// Empty fields do not filter
+#[derive(Debug)]
+pub struct ExportFilter {
+    pub status: Option<String>,
+    pub subscribed_after: Option<DateTime<Utc>>,
+    pub subscribed_before: Option<DateTime<Utc>>,
+}
+
+struct ExportedSubscriber {
+    id: Uuid,
+    email: String,
+    name: String,
+    status: String,
+    subscribed_at: DateTime<Utc>,
+    // Lists the subscriber has not left
+    lists: Vec<String>,
+    tags: Vec<String>,
+}
+
+// Rows are written out as they are read, so that the export never holds
+// every subscriber in memory. The channel holds back the query when the
+// client reads slower than Postgres answers.
+#[tracing::instrument(name = "Export subscribers", skip(pool, format))]
+pub fn stream_subscribers(
+    pool: Arc<PgPool>,
+    filter: ExportFilter,
+    format: ExportFormat,
+) -> impl Stream<Item = Result<String, anyhow::Error>> {
+    let (sender, receiver) = mpsc::channel(64);
+    tokio::spawn(
+        async move {
+            if let Some(header) = format.header() {
+                if sender.send(Ok(header.to_owned())).await.is_err() {
+                    return;
+                }
+            }
+            let mut subscribers = sqlx::query_as!(
+                ExportedSubscriber,
+                r#"
+                SELECT
+                subscriptions.id,
+                subscriptions.email,
+                subscriptions.name,
+                subscriptions.status,
+                subscriptions.subscribed_at,
+                ARRAY(
+                    SELECT lists.name
+                    FROM list_subscriptions
+                    JOIN lists ON lists.list_id = list_subscriptions.list_id
+                    WHERE list_subscriptions.subscriber_id = subscriptions.id AND
+                    list_subscriptions.status <> 'unsubscribed'
+                    ORDER BY lists.name
+                ) AS "lists!",
+                ARRAY(
+                    SELECT tag
+                    FROM subscriber_tags
+                    WHERE subscriber_tags.subscriber_id = subscriptions.id
+                    ORDER BY tag
+                ) AS "tags!"
+                FROM subscriptions
+                WHERE
+                ($1::TEXT IS NULL OR subscriptions.status = $1) AND
+                subscriptions.subscribed_at >= COALESCE($2::TIMESTAMPTZ, '-infinity') AND
+                subscriptions.subscribed_at < COALESCE($3::TIMESTAMPTZ, 'infinity')
+                ORDER BY subscriptions.subscribed_at, subscriptions.id
+                "#,
+                filter.status,
+                filter.subscribed_after,
+                filter.subscribed_before
+            )
+            .fetch(&*pool);
+            loop {
+                let line = match subscribers.try_next().await {
+                    Ok(Some(subscriber)) => Ok(format.line(&subscriber)),
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!(
+                            error.cause_chain = ?e,
+                            error.message = %e,
+                            "Failed to read subscribers to export."
+                        );
+                        Err(anyhow::Error::new(e).context("Failed to read subscribers to export."))
+                    }
+                };
+                let failed = line.is_err();
+                // Stops early when the client went away
+                if sender.send(line).await.is_err() || failed {
+                    break;
+                }
+            }
+        }
+        .in_current_span(),
+    );
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (line, receiver))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportFormat, ExportedSubscriber};
+    use chrono::{TimeZone, Utc};
+    use claims::assert_err;
+    use uuid::Uuid;
+
+    fn subscriber() -> ExportedSubscriber {
+        ExportedSubscriber {
+            id: Uuid::nil(),
+            email: "ursula@example.com".into(),
+            name: "Le Guin, \"Ursula\"".into(),
+            status: "confirmed".into(),
+            subscribed_at: Utc.with_ymd_and_hms(2023, 12, 7, 4, 43, 50).unwrap(),
+            lists: vec!["newsletter".into(), "weekly".into()],
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn csv_lines_quote_text_and_join_lists() {
+        assert_eq!(
+            ExportFormat::Csv.line(&subscriber()),
+            "00000000-0000-0000-0000-000000000000,\"ursula@example.com\",\"Le Guin, \"\"Ursula\"\"\",\
+            confirmed,2023-12-07T04:43:50Z,\"newsletter;weekly\",\"\"\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_lines_keep_values_that_start_like_a_formula() {
+        let subscriber = ExportedSubscriber {
+            name: "-Ursula".into(),
+            tags: vec!["=vip".into()],
+            ..subscriber()
+        };
+
+        let line = ExportFormat::Csv.line(&subscriber);
+
+        assert!(line.contains(",\"-Ursula\","));
+        assert!(line.ends_with(",\"=vip\"\r\n"));
+    }
+
+    #[test]
+    fn ndjson_lines_are_single_json_objects() {
+        let line = ExportFormat::Ndjson.line(&subscriber());
+        assert_eq!(line.matches('\n').count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["name"], "Le Guin, \"Ursula\"");
+        assert_eq!(value["subscribed_at"], "2023-12-07T04:43:50Z");
+        assert_eq!(value["lists"], serde_json::json!(["newsletter", "weekly"]));
+        assert_eq!(value["tags"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        assert_err!(ExportFormat::parse("xlsx"));
+    }
+}
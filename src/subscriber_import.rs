@@ -0,0 +1,396 @@
+use crate::confirmation_email_queue::enqueue_confirmation_email;
+use crate::domain::{SubscriberEmail, SubscriberName};
+use crate::error::error_chain_fmt;
+use crate::routes::{generate_subscription_token, store_token, store_unsubscribe_token};
+use crate::suppression::is_email_suppressed;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use csv_core::{ReadRecordResult, Reader};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Splits CSV into records as the chunks of an upload arrive,
+// a record may span any number of chunks
+pub struct CsvRecords {
+    reader: Reader,
+    output: Vec<u8>,
+    ends: Vec<usize>,
+    n_output: usize,
+    n_ends: usize,
+}
+
+impl Default for CsvRecords {
+    fn default() -> Self {
+        Self {
+            reader: Reader::new(),
+            output: vec![0; 1024],
+            ends: vec![0; 16],
+            n_output: 0,
+            n_ends: 0,
+        }
+    }
+}
+
+impl CsvRecords {
+    // Returns the records completed by the chunk, an empty chunk marks the end of the input
+    pub fn feed(&mut self, mut input: &[u8]) -> Vec<Vec<String>> {
+        let is_last = input.is_empty();
+        let mut records = Vec::new();
+        // csv_core also reads an empty slice as the end of the input
+        while is_last || !input.is_empty() {
+            let (result, n_input, n_output, n_ends) = self.reader.read_record(
+                input,
+                &mut self.output[self.n_output..],
+                &mut self.ends[self.n_ends..],
+            );
+            input = &input[n_input..];
+            self.n_output += n_output;
+            self.n_ends += n_ends;
+            match result {
+                ReadRecordResult::OutputFull => self.output.resize(self.output.len() * 2, 0),
+                ReadRecordResult::OutputEndsFull => self.ends.resize(self.ends.len() * 2, 0),
+                ReadRecordResult::Record => records.push(self.take_record()),
+                ReadRecordResult::InputEmpty | ReadRecordResult::End => break,
+            }
+        }
+        records
+    }
+
+    fn take_record(&mut self) -> Vec<String> {
+        let mut start = 0;
+        let record = self.ends[..self.n_ends]
+            .iter()
+            .map(|&end| {
+                let field = String::from_utf8_lossy(&self.output[start..end]).into_owned();
+                start = end;
+                field
+            })
+            .collect();
+        self.n_output = 0;
+        self.n_ends = 0;
+        record
+    }
+}
+
+// Positions of the columns read from each row, other columns are ignored
+#[derive(Debug)]
+pub struct ImportColumns {
+    email: usize,
+    name: usize,
+}
+
+impl ImportColumns {
+    pub fn parse(header: &[String]) -> Result<ImportColumns, String> {
+        let position = |column: &str| {
+            header.iter().position(|h| {
+                // Spreadsheets often start their exports with a byte order mark
+                h.trim_start_matches('\u{feff}').trim().eq_ignore_ascii_case(column)
+            })
+        };
+        match (position("email"), position("name")) {
+            (Some(email), Some(name)) => Ok(ImportColumns { email, name }),
+            _ => Err("The first row of the file must name an email and a name column.".into()),
+        }
+    }
+
+    // Missing cells are read as empty
+    pub fn row<'a>(&self, record: &'a [String]) -> (&'a str, &'a str) {
+        let cell = |i: usize| record.get(i).map(|c| c.trim()).unwrap_or("");
+        (cell(self.email), cell(self.name))
+    }
+}
+
+pub fn parse_row(email: &str, name: &str) -> Result<(SubscriberEmail, SubscriberName), String> {
+    Ok((
+        SubscriberEmail::parse(email.to_owned())?,
+        SubscriberName::parse(name.to_owned())?,
+    ))
+}
+
+// Returns why the row was skipped, if it was
+#[tracing::instrument(name = "Import a subscriber", skip(transaction, email, name))]
+pub async fn import_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &SubscriberEmail,
+    name: &SubscriberName,
+    list_id: Uuid,
+    pre_confirmed: bool,
+) -> Result<Option<String>, anyhow::Error> {
+    if is_email_suppressed(&mut *transaction, email.as_ref()).await? {
+        return Ok(Some(format!("{} is on the suppression list.", email.as_ref())));
+    }
+    let status = if pre_confirmed { "confirmed" } else { "pending_confirmation" };
+    // Addresses are matched whatever their case, like suppressions and webhook events
+    let existing = sqlx::query!(
+        "SELECT id, status FROM subscriptions WHERE lower(email) = lower($1)",
+        email.as_ref()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to look up an imported subscriber.")?;
+    let subscriber_id = match existing {
+        None => {
+            let subscriber_id = Uuid::new_v4();
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+                VALUES ($1, $2, $3, now(), $4)
+                ON CONFLICT (email) DO NOTHING
+                "#,
+                subscriber_id,
+                email.as_ref(),
+                name.as_ref(),
+                status
+            )
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to insert an imported subscriber.")?
+            .rows_affected();
+            // Signed up while the import was running
+            if inserted == 0 {
+                return Ok(Some(format!("{} is already a subscriber.", email.as_ref())));
+            }
+            store_unsubscribe_token(transaction, subscriber_id, &generate_subscription_token())
+                .await?;
+            subscriber_id
+        }
+        // An import never signs an address up again once it left, bounced or complained
+        Some(existing) if !["pending_confirmation", "confirmed"].contains(&existing.status.as_str()) => {
+            return Ok(Some(format!(
+                "{} is {} and was not added to the list.",
+                email.as_ref(),
+                existing.status.replace('_', " ")
+            )));
+        }
+        Some(existing) => existing.id,
+    };
+    let added = sqlx::query!(
+        r#"
+        INSERT INTO list_subscriptions (list_id, subscriber_id, status)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (list_id, subscriber_id) DO NOTHING
+        "#,
+        list_id,
+        subscriber_id,
+        status
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to add an imported subscriber to a list.")?
+    .rows_affected();
+    // Including members who left the list, they are not signed up again either
+    if added == 0 {
+        return Ok(Some(format!("{} is already on this list.", email.as_ref())));
+    }
+    if pre_confirmed {
+        sqlx::query!(
+            "UPDATE subscriptions SET status = 'confirmed' WHERE id = $1 AND status = 'pending_confirmation'",
+            subscriber_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to confirm an imported subscriber.")?;
+    }
+    if !pre_confirmed {
+        let subscription_token = generate_subscription_token();
+        store_token(transaction, subscriber_id, list_id, &subscription_token).await?;
+        enqueue_confirmation_email(transaction, &subscription_token)
+            .await
+            .context("Failed to enqueue a confirmation email.")?;
+    }
+    Ok(None)
+}
+
+pub struct SkippedRow {
+    pub row_number: i32,
+    pub email: String,
+    pub error: String,
+}
+
+#[tracing::instrument(name = "Store a subscriber import", skip(transaction, errors))]
+pub async fn store_import(
+    transaction: &mut Transaction<'_, Postgres>,
+    import_id: Uuid,
+    file_name: &str,
+    n_rows: i32,
+    n_imported: i32,
+    errors: &[SkippedRow],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriber_imports (import_id, file_name, n_rows, n_imported)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        import_id,
+        file_name,
+        n_rows,
+        n_imported
+    )
+    .execute(&mut *transaction)
+    .await?;
+    for error in errors {
+        sqlx::query!(
+            r#"
+            INSERT INTO subscriber_import_errors (import_id, row_number, email, error)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            import_id,
+            error.row_number,
+            error.email,
+            error.error
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    Ok(())
+}
+
+pub struct SubscriberImport {
+    pub import_id: Uuid,
+    pub file_name: String,
+    pub n_rows: i32,
+    pub n_imported: i32,
+    pub imported_at: DateTime<Utc>,
+}
+
+#[tracing::instrument(name = "Get recent subscriber imports", skip(pool))]
+pub async fn get_recent_imports(pool: &PgPool) -> Result<Vec<SubscriberImport>, sqlx::Error> {
+    sqlx::query_as!(
+        SubscriberImport,
+        r#"
+        SELECT import_id, file_name, n_rows, n_imported, imported_at
+        FROM subscriber_imports
+        ORDER BY imported_at DESC
+        LIMIT 20
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// None when there is no such import
+#[tracing::instrument(name = "Get the errors of a subscriber import", skip(pool))]
+pub async fn get_import_errors(
+    pool: &PgPool,
+    import_id: Uuid,
+) -> Result<Option<Vec<SkippedRow>>, sqlx::Error> {
+    let exists = sqlx::query!(
+        "SELECT import_id FROM subscriber_imports WHERE import_id = $1",
+        import_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    if exists.is_none() {
+        return Ok(None);
+    }
+    let errors = sqlx::query_as!(
+        SkippedRow,
+        r#"
+        SELECT row_number, email, error
+        FROM subscriber_import_errors
+        WHERE import_id = $1
+        ORDER BY row_number
+        "#,
+        import_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(Some(errors))
+}
+
+pub fn error_report_csv(errors: &[SkippedRow]) -> String {
+    let mut report = String::from("row,email,error\r\n");
+    for error in errors {
+        report.push_str(&format!(
+            "{},{},{}\r\n",
+            error.row_number,
+            csv_field(&error.email),
+            csv_field(&error.error)
+        ));
+    }
+    report
+}
+
+// Cells starting like a formula are prefixed with a quote, so that
+// spreadsheets opening the report do not evaluate uploaded values
+pub fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_owned()
+    };
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[derive(thiserror::Error)]
+pub enum ImportError {
+    #[error("{0}")]
+    InvalidUpload(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, CsvRecords, ImportColumns};
+    use claims::{assert_err, assert_ok};
+
+    fn read_in_chunks(input: &str, chunk_size: usize) -> Vec<Vec<String>> {
+        let mut records = CsvRecords::default();
+        let mut output = Vec::new();
+        for chunk in input.as_bytes().chunks(chunk_size) {
+            output.extend(records.feed(chunk));
+        }
+        output.extend(records.feed(&[]));
+        output
+    }
+
+    #[test]
+    fn records_can_span_chunks() {
+        let input = "email,name\r\nursula@example.com,\"Le Guin, Ursula\"\nbob@example.com,Bob";
+        let expected = vec![
+            vec!["email", "name"],
+            vec!["ursula@example.com", "Le Guin, Ursula"],
+            vec!["bob@example.com", "Bob"],
+        ];
+
+        for chunk_size in [1, 3, 7, input.len()] {
+            assert_eq!(read_in_chunks(input, chunk_size), expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn quoted_fields_keep_quotes_and_line_breaks() {
+        let records = read_in_chunks("\"a \"\"b\"\"\",\"line\nbreak\"\n", 4);
+        assert_eq!(records, vec![vec!["a \"b\"", "line\nbreak"]]);
+    }
+
+    #[test]
+    fn columns_are_found_by_name() {
+        let header: Vec<String> = vec!["\u{feff}Name".into(), "Company".into(), " EMAIL ".into()];
+        let columns = assert_ok!(ImportColumns::parse(&header));
+
+        let record: Vec<String> = vec!["Ursula".into(), "Acme".into(), " ursula@example.com".into()];
+        assert_eq!(columns.row(&record), ("ursula@example.com", "Ursula"));
+        assert_eq!(columns.row(&["Ursula".to_string()]), ("", "Ursula"));
+    }
+
+    #[test]
+    fn a_header_without_an_email_column_is_rejected() {
+        let header: Vec<String> = vec!["name".into(), "address".into()];
+        assert_err!(ImportColumns::parse(&header));
+    }
+
+    #[test]
+    fn report_fields_are_quoted_and_defused() {
+        assert_eq!(csv_field("a \"b\", c"), "\"a \"\"b\"\", c\"");
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+pub struct SuppressedEmail {
+    pub email: String,
+    pub reason: String,
+    pub source: String,
+    pub suppressed_at: DateTime<Utc>,
+}
+
+// Consulted before every email goes out, whatever the address' subscription status
+#[tracing::instrument(name = "Check the suppression list", skip(executor))]
+pub async fn is_email_suppressed(
+    executor: impl PgExecutor<'_>,
+    email: &str,
+) -> Result<bool, sqlx::Error> {
+    let suppressed = sqlx::query!(
+        r#"SELECT EXISTS(SELECT 1 FROM suppressed_emails WHERE email = lower($1)) as "suppressed!""#,
+        email
+    )
+    .fetch_one(executor)
+    .await?
+    .suppressed;
+    Ok(suppressed)
+}
+
+// An address already on the list keeps its original reason.
+// Returns false in that case.
+#[tracing::instrument(name = "Suppress an email address", skip(executor))]
+pub async fn suppress_email(
+    executor: impl PgExecutor<'_>,
+    email: &str,
+    reason: &str,
+    source: &str,
+) -> Result<bool, sqlx::Error> {
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO suppressed_emails (email, reason, source, suppressed_at)
+        VALUES (lower($1), $2, $3, now())
+        ON CONFLICT DO NOTHING
+        "#,
+        email,
+        reason,
+        source
+    )
+    .execute(executor)
+    .await?
+    .rows_affected()
+        > 0;
+    Ok(inserted)
+}
+
+#[tracing::instrument(name = "Remove an email address from the suppression list", skip(pool))]
+pub async fn unsuppress_email(pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query!(
+        "DELETE FROM suppressed_emails WHERE email = lower($1)",
+        email
+    )
+    .execute(pool)
+    .await?
+    .rows_affected()
+        > 0;
+    Ok(deleted)
+}
+
+#[tracing::instrument(name = "Get suppressed emails", skip(pool))]
+pub async fn get_suppressed_emails(pool: &PgPool) -> Result<Vec<SuppressedEmail>, sqlx::Error> {
+    sqlx::query_as!(
+        SuppressedEmail,
+        r#"
+        SELECT email, reason, source, suppressed_at
+        FROM suppressed_emails
+        ORDER BY suppressed_at DESC, email
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,225 @@
+use axum::http::HeaderValue;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+pub struct TrackingToken {
+    pub token: String,
+    // None for the open pixel
+    pub url: Option<String>,
+}
+
+// Rewrites http(s) links through the click endpoint and adds an open pixel.
+// Links listed in `untracked`, such as the unsubscribe link, are left alone.
+pub fn track_html(html: &str, base_url: &str, untracked: &[&str]) -> (String, Vec<TrackingToken>) {
+    let mut tokens: Vec<TrackingToken> = Vec::new();
+    let mut output = String::with_capacity(html.len());
+    // ASCII lowercasing keeps byte offsets, so positions found in it apply to html
+    let lowercase = html.to_ascii_lowercase();
+    let mut copied_up_to = 0;
+    let mut search_from = 0;
+    while let Some(offset) = lowercase[search_from..].find("href=") {
+        let attribute_start = search_from + offset;
+        search_from = attribute_start + "href=".len();
+        if !lowercase[..attribute_start].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        let quote = match html[search_from..].chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => continue,
+        };
+        let value_start = search_from + 1;
+        let value_end = match html[value_start..].find(quote) {
+            Some(length) => value_start + length,
+            None => break,
+        };
+        search_from = value_end;
+
+        let raw = &html[value_start..value_end];
+        let url = htmlescape::decode_html(raw).unwrap_or_else(|_| raw.to_string());
+        if !is_trackable(&url) || untracked.contains(&url.as_str()) {
+            continue;
+        }
+        let token = match tokens.iter().find(|t| t.url.as_ref() == Some(&url)) {
+            Some(existing) => existing.token.clone(),
+            None => {
+                let token = generate_tracking_token();
+                tokens.push(TrackingToken { token: token.clone(), url: Some(url) });
+                token
+            }
+        };
+        output.push_str(&html[copied_up_to..value_start]);
+        output.push_str(&format!("{}/t/c/{}", base_url, token));
+        copied_up_to = value_end;
+    }
+    output.push_str(&html[copied_up_to..]);
+
+    let open_token = generate_tracking_token();
+    let pixel = format!(
+        r#"<img src="{}/t/o/{}" width="1" height="1" alt="">"#,
+        base_url, open_token
+    );
+    tokens.push(TrackingToken { token: open_token, url: None });
+    // Layouts are full documents, the pixel has to stay inside the body
+    match output.to_ascii_lowercase().rfind("</body>") {
+        Some(position) => output.insert_str(position, &pixel),
+        None => output.push_str(&pixel),
+    }
+    (output, tokens)
+}
+
+// The click endpoint redirects to the link, which must fit in a Location header
+fn is_trackable(url: &str) -> bool {
+    let lowercase = url.to_ascii_lowercase();
+    (lowercase.starts_with("http://") || lowercase.starts_with("https://"))
+        && HeaderValue::from_str(url).is_ok()
+}
+
+fn generate_tracking_token() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(25)
+        .collect()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn store_tracking_tokens(
+    transaction: &mut Transaction<'_, Postgres>,
+    newsletter_issue_id: Uuid,
+    subscriber_email: &str,
+    tokens: &[TrackingToken],
+) -> Result<(), sqlx::Error> {
+    for token in tokens {
+        sqlx::query!(
+            r#"
+            INSERT INTO tracking_tokens (
+            tracking_token,
+            newsletter_issue_id,
+            subscriber_email,
+            url
+            )
+            VALUES ($1, $2, $3, $4)
+            "#,
+            token.token,
+            newsletter_issue_id,
+            subscriber_email,
+            token.url
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    Ok(())
+}
+
+#[tracing::instrument(name = "Get tracked url", skip(pool))]
+pub async fn get_tracked_url(
+    pool: &PgPool,
+    tracking_token: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let url = sqlx::query!(
+        "SELECT url FROM tracking_tokens WHERE tracking_token = $1",
+        tracking_token
+    )
+    .fetch_optional(pool)
+    .await?
+    .and_then(|r| r.url);
+    Ok(url)
+}
+
+// Unknown tokens are ignored, there is nothing to attribute the event to
+#[tracing::instrument(name = "Record tracking event", skip(pool))]
+pub async fn record_tracking_event(
+    pool: &PgPool,
+    tracking_token: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO tracking_events (tracking_token, occurred_at)
+        SELECT tracking_token, now()
+        FROM tracking_tokens
+        WHERE tracking_token = $1
+        "#,
+        tracking_token
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::track_html;
+
+    const BASE_URL: &str = "https://example.com";
+
+    #[test]
+    fn links_are_rewritten_through_the_click_endpoint() {
+        let (html, tokens) = track_html(
+            r#"<p><a href="https://rust-lang.org/?a=1&amp;b=2">Rust</a></p>"#,
+            BASE_URL,
+            &[],
+        );
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].url.as_deref(), Some("https://rust-lang.org/?a=1&b=2"));
+        assert!(html.starts_with(&format!(
+            r#"<p><a href="https://example.com/t/c/{}">Rust</a></p>"#,
+            tokens[0].token
+        )));
+    }
+
+    #[test]
+    fn an_open_pixel_is_added_inside_the_body() {
+        let (html, tokens) = track_html("<html><body><p>Hi</p></BODY></html>", BASE_URL, &[]);
+
+        let open = tokens.iter().find(|t| t.url.is_none()).unwrap();
+        assert_eq!(
+            html,
+            format!(
+                r#"<html><body><p>Hi</p><img src="https://example.com/t/o/{}" width="1" height="1" alt=""></BODY></html>"#,
+                open.token
+            )
+        );
+    }
+
+    #[test]
+    fn repeated_links_share_a_token() {
+        let (_, tokens) = track_html(
+            r#"<a href="https://a.com">A</a><a href='https://a.com'>A again</a>"#,
+            BASE_URL,
+            &[],
+        );
+
+        assert_eq!(tokens.iter().filter(|t| t.url.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn untracked_and_non_web_links_are_left_alone() {
+        let input = concat!(
+            r#"<a href="https://example.com/unsubscribe">Unsubscribe</a>"#,
+            r#"<a href="mailto:hi@example.com">Mail</a>"#,
+            r#"<a data-href="https://a.com">Data</a>"#,
+            r##"<a href="#top">Top</a>"##,
+        );
+
+        let (html, tokens) = track_html(input, BASE_URL, &["https://example.com/unsubscribe"]);
+
+        assert_eq!(tokens.len(), 1);
+        assert!(html.starts_with(input));
+    }
+
+    #[test]
+    fn links_that_cannot_be_redirected_to_are_left_alone() {
+        let input = concat!(
+            r#"<a href="https://a.com/&#10;x">Encoded newline</a>"#,
+            "<a href=\"https://a.com/\ny\">Raw newline</a>",
+        );
+
+        let (html, tokens) = track_html(input, BASE_URL, &[]);
+
+        assert_eq!(tokens.len(), 1);
+        assert!(html.starts_with(input));
+    }
+}
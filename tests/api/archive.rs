@@ -0,0 +1,208 @@
+use crate::helpers::{spawn_app, create_confirmed_subscriber, when_sending_an_email, TestApp};
+
+use chrono::{Duration, Utc};
+use wiremock::ResponseTemplate;
+
+fn newsletter_body(title: &str, is_public: bool) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "title": title,
+        "text": "Hi {{ name }}, here is the plain text body",
+        "html": "<p>Hi {{ name }}, here is the HTML body</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    if is_public {
+        body["is_public"] = "on".into();
+    }
+    body
+}
+
+async fn publish(app: &TestApp, body: &serde_json::Value) {
+    let response = app.post_publish_newsletter(body).await;
+    assert_eq!(response.status().as_u16(), 303);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn public_issues_are_listed_and_rendered_in_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    publish(&app, &newsletter_body("November News", true)).await;
+
+    // Assert
+    let slug = app.get_issue_slug("November News").await.unwrap();
+    assert_eq!(slug, "november-news");
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let html_page = app.get_archive_html().await;
+    assert!(html_page.contains(&format!(
+        r#"<a href="/archive/november-news">November News</a> - {}"#,
+        today
+    )));
+
+    let response = app.get_archived_issue(&slug).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<h1>November News</h1>"));
+    assert!(html_page.contains(&format!("Published on {}", today)));
+    assert!(html_page.contains("<p>Hi reader, here is the HTML body</p>"));
+}
+
+#[tokio::test]
+async fn private_issues_are_not_in_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    publish(&app, &newsletter_body("Members only", false)).await;
+
+    // Assert
+    let html_page = app.get_archive_html().await;
+    assert!(!html_page.contains("Members only"));
+    assert!(html_page.contains("No issues have been published yet."));
+    let slug = app.get_issue_slug("Members only").await.unwrap();
+    assert_eq!(app.get_archived_issue(&slug).await.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn unpublished_issues_are_not_in_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut scheduled = newsletter_body("Coming soon", true);
+    scheduled["scheduled_for"] = (Utc::now() + Duration::days(1))
+        .format("%Y-%m-%dT%H:%M")
+        .to_string()
+        .into();
+
+    // Act
+    publish(&app, &scheduled).await;
+    app.create_draft(&serde_json::json!({
+        "title": "Work in progress",
+        "text": "Draft body",
+        "html": "<p>Draft body</p>",
+    }))
+    .await;
+
+    // Assert
+    let html_page = app.get_archive_html().await;
+    assert!(!html_page.contains("Coming soon"));
+    assert!(!html_page.contains("Work in progress"));
+    assert_eq!(app.get_issue_slug("Coming soon").await, None);
+}
+
+#[tokio::test]
+async fn unknown_slugs_are_not_found() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_archived_issue("does-not-exist").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn issues_with_the_same_title_get_distinct_slugs() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    publish(&app, &newsletter_body("Weekly digest", true)).await;
+    publish(&app, &newsletter_body("Weekly digest", true)).await;
+
+    // Assert
+    let slugs: Vec<String> = sqlx::query!("SELECT slug FROM newsletter_issues")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.slug.unwrap())
+        .collect();
+    assert_eq!(slugs.len(), 2);
+    assert!(slugs.contains(&"weekly-digest".to_string()));
+    assert!(slugs.iter().any(|s| s.starts_with("weekly-digest-")));
+    for slug in &slugs {
+        assert_eq!(app.get_archived_issue(slug).await.status().as_u16(), 200);
+    }
+}
+
+#[tokio::test]
+async fn public_issues_link_to_their_archive_page() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    publish(&app, &newsletter_body("November News", true)).await;
+
+    // Assert
+    // The confirmation email accounts for the first request
+    let request = &app.email_server.received_requests().await.unwrap()[1];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let archive_link = format!("{}/archive/november-news", app.address);
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!(r#"<p><a href="{}">View this issue in your browser</a></p>"#, archive_link)));
+    assert!(body["TextBody"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("View this issue in your browser: {}", archive_link)));
+}
+
+#[tokio::test]
+async fn private_issues_do_not_link_to_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    publish(&app, &newsletter_body("Members only", false)).await;
+
+    // Assert
+    let request = &app.email_server.received_requests().await.unwrap()[1];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert!(!body["HtmlBody"].as_str().unwrap().contains("/archive/"));
+    assert!(!body["TextBody"].as_str().unwrap().contains("/archive/"));
+}
+
+#[tokio::test]
+async fn drafts_can_be_published_to_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app
+        .create_draft(&serde_json::json!({
+            "title": "From a draft",
+            "text": "Draft body",
+            "html": "<p>Draft body</p>",
+        }))
+        .await;
+
+    // Act
+    app.post_publish_draft(issue_id, &serde_json::json!({ "is_public": "on" }))
+        .await;
+
+    // Assert
+    let html_page = app.get_archive_html().await;
+    assert!(html_page.contains(r#"<a href="/archive/from-a-draft">From a draft</a>"#));
+}
@@ -0,0 +1,272 @@
+use crate::helpers::{
+    spawn_app, assert_is_redirect_to, create_confirmed_subscriber, when_sending_an_email
+};
+
+use chrono::{Duration, Utc};
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+fn draft_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Draft title",
+        "text": "Draft body as plain text",
+        "html": "<p>Draft body as HTML</p>",
+    })
+}
+
+#[tokio::test]
+async fn saving_a_draft_does_not_send_any_email() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_create_draft(&draft_body()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 303);
+    app.dispatch_all_pending_emails().await;
+
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("Draft title"));
+}
+
+#[tokio::test]
+async fn drafts_can_be_edited() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+
+    // Act
+    let response = app
+        .post_update_draft(
+            issue_id,
+            &serde_json::json!({
+                "title": "Updated title",
+                "text": "Updated body",
+                "html": "<p>Updated body</p>",
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/edit", issue_id));
+    let html_page = app.get_edit_draft(issue_id).await.text().await.unwrap();
+    assert!(html_page.contains("<p><i>The draft has been saved.</i></p>"));
+    assert!(html_page.contains(r#"value="Updated&#x20;title""#));
+    assert!(html_page.contains("&lt;p&gt;Updated body&lt;/p&gt;"));
+}
+
+#[tokio::test]
+async fn the_preview_shows_both_the_html_and_text_content() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+
+    // Act
+    let response = app.get_draft_preview(issue_id).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<h1>Draft title</h1>"));
+    assert!(html_page.contains(r#"srcdoc="&lt;p&gt;Draft&#x20;body&#x20;as&#x20;HTML&lt;&#x2F;p&gt;""#));
+    assert!(html_page.contains("<pre>Draft body as plain text</pre>"));
+}
+
+#[tokio::test]
+async fn deleted_drafts_are_no_longer_listed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+
+    // Act
+    let response = app.post_delete_draft(issue_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/drafts");
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("<p><i>The draft has been deleted.</i></p>"));
+    assert!(!html_page.contains("Draft title"));
+    assert_eq!(app.get_draft_preview(issue_id).await.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn publishing_a_draft_delivers_it_to_confirmed_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+    assert_eq!(app.get_issue_status(issue_id).await, "draft");
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_publish_draft(issue_id, &serde_json::json!({ "scheduled_for": "" }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/drafts");
+    assert_eq!(app.get_issue_status(issue_id).await, "sending");
+    app.dispatch_all_pending_emails().await;
+    assert_eq!(app.get_issue_status(issue_id).await, "sent");
+
+    let html_page = app.get_drafts_html().await;
+    assert!(!html_page.contains("Draft title"));
+}
+
+#[tokio::test]
+async fn a_draft_is_only_published_once() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_publish_draft(issue_id, &serde_json::json!({})).await;
+    let response = app.post_publish_draft(issue_id, &serde_json::json!({})).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/drafts");
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue is no longer a draft.</i></p>"));
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn drafts_can_be_scheduled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+    let send_time = (Utc::now() + Duration::days(1)).format("%Y-%m-%dT%H:%M").to_string();
+
+    // Act
+    app.post_publish_draft(issue_id, &serde_json::json!({ "scheduled_for": send_time }))
+        .await;
+
+    // Assert
+    assert_eq!(app.get_issue_status(issue_id).await, "scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("Draft title"));
+}
+
+#[tokio::test]
+async fn cancelled_newsletters_go_back_to_drafts() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app.create_draft(&draft_body()).await;
+    let send_time = (Utc::now() + Duration::days(1)).format("%Y-%m-%dT%H:%M").to_string();
+    app.post_publish_draft(issue_id, &serde_json::json!({ "scheduled_for": send_time }))
+        .await;
+
+    // Act
+    app.post_cancel_newsletter(issue_id).await;
+
+    // Assert
+    assert_eq!(app.get_issue_status(issue_id).await, "draft");
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("Draft title"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_drafts() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_create_draft(&draft_body()).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_drafts().await, "/login");
+}
+
+#[tokio::test]
+async fn markdown_drafts_can_be_re_edited_from_their_source() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let issue_id = app
+        .create_draft(&serde_json::json!({
+            "title": "Draft title",
+            "markdown": "Hello **world**",
+        }))
+        .await;
+
+    // Assert
+    let html_page = app.get_edit_draft(issue_id).await.text().await.unwrap();
+    assert!(html_page.contains(">Hello **world**</textarea>"));
+
+    let html_page = app.get_draft_preview(issue_id).await.text().await.unwrap();
+    assert!(html_page.contains("&lt;strong&gt;world&lt;&#x2F;strong&gt;"));
+    assert!(html_page.contains("<pre>Hello world</pre>"));
+}
+
+#[tokio::test]
+async fn drafts_with_unknown_merge_tags_cannot_be_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app
+        .create_draft(&serde_json::json!({
+            "title": "Hello {{ nickname }}",
+            "markdown": "Some content",
+        }))
+        .await;
+
+    // Act
+    let response = app.post_publish_draft(issue_id, &serde_json::json!({})).await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/preview", issue_id));
+    let html_page = app.get_draft_preview(issue_id).await.text().await.unwrap();
+    assert!(html_page.contains("Unknown merge tags: {{ nickname }}."));
+    assert_eq!(app.get_issue_status(issue_id).await, "draft");
+}
+
+#[tokio::test]
+async fn drafts_without_a_body_cannot_be_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    // Drafts are saved as they are, content can be written later
+    let issue_id = app
+        .create_draft(&serde_json::json!({ "title": "Draft title" }))
+        .await;
+
+    // Act
+    let response = app.post_publish_draft(issue_id, &serde_json::json!({})).await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/preview", issue_id));
+    let html_page = app.get_draft_preview(issue_id).await.text().await.unwrap();
+    assert!(html_page.contains("The issue has no content, write it in Markdown, HTML or plain text."));
+    assert_eq!(app.get_issue_status(issue_id).await, "draft");
+}
@@ -0,0 +1,198 @@
+use crate::helpers::{
+    spawn_app, assert_is_redirect_to, create_confirmed_subscriber, when_sending_an_email
+};
+
+use wiremock::ResponseTemplate;
+
+fn layout_body(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "html_layout": "<header>Our newsletter</header>{{ content }}<footer>Sent to {{ email }}</footer>",
+        "text_layout": "OUR NEWSLETTER\n{{ content }}\n--\nSent to {{ email }}",
+    })
+}
+
+#[tokio::test]
+async fn layouts_can_be_created_and_listed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.post_create_email_template(&layout_body("Branded")).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/templates");
+    let html_page = app.get_email_templates_html().await;
+    assert!(html_page.contains("<p><i>The layout has been saved.</i></p>"));
+    assert!(html_page.contains("Branded"));
+}
+
+#[tokio::test]
+async fn layouts_without_a_content_slot_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_create_email_template(&serde_json::json!({
+            "name": "Broken",
+            "html_layout": "<header>Our newsletter</header>",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/templates/new");
+    let html_page = app.get_email_templates_html().await;
+    assert!(!html_page.contains("Broken"));
+}
+
+#[tokio::test]
+async fn layout_names_must_be_unique() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_create_email_template(&layout_body("Branded")).await;
+
+    // Act
+    let response = app.post_create_email_template(&layout_body("Branded")).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/templates/new");
+    let html_page = app.get_email_templates_html().await;
+    assert!(html_page.contains("<p><i>A layout with this name already exists.</i></p>"));
+}
+
+#[tokio::test]
+async fn layouts_can_be_updated_and_deleted() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_create_email_template(&layout_body("Branded")).await;
+    let email_template_id = app.get_email_template_id("Branded").await;
+
+    // Act - Part 1 - Rename
+    app.post_update_email_template(email_template_id, &layout_body("Rebranded"))
+        .await;
+
+    // Assert - Part 1
+    assert_eq!(app.get_email_template_id("Rebranded").await, email_template_id);
+
+    // Act - Part 2 - Delete
+    let response = app.post_delete_email_template(email_template_id).await;
+
+    // Assert - Part 2
+    assert_is_redirect_to(&response, "/admin/templates");
+    let html_page = app.get_email_templates_html().await;
+    assert!(!html_page.contains("Rebranded"));
+}
+
+#[tokio::test]
+async fn issues_are_wrapped_in_the_selected_layout() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    app.post_create_email_template(&layout_body("Branded")).await;
+    let email_template_id = app.get_email_template_id("Branded").await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "email_template_id": email_template_id.to_string(),
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    // The confirmation email accounts for the first request
+    let request = &app.email_server.received_requests().await.unwrap()[1];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let email = body["To"].as_str().unwrap();
+    let html_body = body["HtmlBody"].as_str().unwrap();
+    let text_body = body["TextBody"].as_str().unwrap();
+    assert!(html_body.starts_with("<header>Our newsletter</header><p>Newsletter body as HTML</p>"));
+    assert!(html_body.ends_with(&format!("<footer>Sent to {}</footer>", email)));
+    assert!(text_body.starts_with("OUR NEWSLETTER\nNewsletter body as plain text"));
+    assert!(text_body.ends_with(&format!("--\nSent to {}", email)));
+}
+
+#[tokio::test]
+async fn the_default_layout_is_used_for_confirmation_emails() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut body = layout_body("Branded");
+    body["is_default"] = "on".into();
+    app.post_create_email_template(&body).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+
+    // Assert
+    let request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let html_body = body["HtmlBody"].as_str().unwrap();
+    assert!(html_body.starts_with("<header>Our newsletter</header>Welcome to our newsletter!"));
+    assert!(html_body.ends_with("<footer>Sent to ursula_le_guin@gmail.com</footer>"));
+    // The confirmation link is still there
+    let confirmation_links = app.get_confirmation_links(request);
+    assert_eq!(confirmation_links.html, confirmation_links.plain_text);
+}
+
+#[tokio::test]
+async fn there_is_at_most_one_default_layout() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut first = layout_body("First");
+    first["is_default"] = "on".into();
+    let mut second = layout_body("Second");
+    second["is_default"] = "on".into();
+
+    // Act
+    app.post_create_email_template(&first).await;
+    app.post_create_email_template(&second).await;
+
+    // Assert
+    let defaults = sqlx::query!("SELECT name FROM email_templates WHERE is_default")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(defaults.len(), 1);
+    assert_eq!(defaults[0].name, "Second");
+
+    let html_page = app.get_publish_newsletter_html().await;
+    let email_template_id = app.get_email_template_id("Second").await;
+    assert!(html_page.contains(&format!(r#"<option value="{}" selected>Second</option>"#, email_template_id)));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_layouts() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_create_email_template(&layout_body("Branded")).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_email_templates().await, "/login");
+}
@@ -0,0 +1,147 @@
+use crate::helpers::{spawn_app, TestApp};
+
+fn newsletter_body(title: &str, is_public: bool) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "title": title,
+        "text": "Newsletter body as plain text",
+        "html": "<p>Fish &amp; chips</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    if is_public {
+        body["is_public"] = "on".into();
+    }
+    body
+}
+
+async fn publish(app: &TestApp, body: &serde_json::Value) {
+    let response = app.post_publish_newsletter(body).await;
+    assert_eq!(response.status().as_u16(), 303);
+}
+
+async fn get_issue_id(app: &TestApp, title: &str) -> uuid::Uuid {
+    sqlx::query!(
+        "SELECT newsletter_issue_id FROM newsletter_issues WHERE title = $1",
+        title
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+    .newsletter_issue_id
+}
+
+#[tokio::test]
+async fn the_rss_feed_lists_public_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    publish(&app, &newsletter_body("Tips & tricks", true)).await;
+    let issue_id = get_issue_id(&app, "Tips & tricks").await;
+
+    // Act
+    let response = app.get_feed("feed.rss").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/rss+xml; charset=utf-8"
+    );
+    let xml = response.text().await.unwrap();
+    assert!(xml.contains("<title>Tips &amp; tricks</title>"));
+    assert!(xml.contains(&format!(
+        "<link>{}/archive/tips-tricks</link>",
+        app.configuration.application.base_url
+    )));
+    assert!(xml.contains(&format!(r#"<guid isPermaLink="false">urn:uuid:{}</guid>"#, issue_id)));
+    assert!(xml.contains("<pubDate>"));
+    assert!(xml.contains("<description>&lt;p&gt;Fish &amp;amp; chips&lt;/p&gt;</description>"));
+}
+
+#[tokio::test]
+async fn the_atom_feed_lists_public_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    publish(&app, &newsletter_body("Tips & tricks", true)).await;
+    let issue_id = get_issue_id(&app, "Tips & tricks").await;
+
+    // Act
+    let response = app.get_feed("feed.atom").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/atom+xml; charset=utf-8"
+    );
+    let xml = response.text().await.unwrap();
+    assert!(xml.contains("<title>Tips &amp; tricks</title>"));
+    assert!(xml.contains(&format!(
+        r#"<link rel="alternate" type="text/html" href="{}/archive/tips-tricks"/>"#,
+        app.configuration.application.base_url
+    )));
+    assert!(xml.contains(&format!("<id>urn:uuid:{}</id>", issue_id)));
+    assert!(xml.contains("<published>"));
+    assert!(xml.contains(r#"<content type="html">&lt;p&gt;Fish &amp;amp; chips&lt;/p&gt;</content>"#));
+}
+
+#[tokio::test]
+async fn private_issues_are_not_in_the_feeds() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    publish(&app, &newsletter_body("Members only", false)).await;
+
+    for path in ["feed.rss", "feed.atom"] {
+        // Act
+        let xml = app.get_feed(path).await.text().await.unwrap();
+
+        // Assert
+        assert!(!xml.contains("Members only"));
+    }
+}
+
+#[tokio::test]
+async fn feeds_only_include_the_most_recent_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    for i in 0..21 {
+        publish(&app, &newsletter_body(&format!("Issue number {}", i), true)).await;
+    }
+    // Publish dates are only as precise as the clock, make the order explicit
+    sqlx::query!(
+        r#"
+        UPDATE newsletter_issues
+        SET published_at = (now() - interval '1 day')::text
+        WHERE title = 'Issue number 0'
+        "#
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let rss = app.get_feed("feed.rss").await.text().await.unwrap();
+    let atom = app.get_feed("feed.atom").await.text().await.unwrap();
+
+    // Assert
+    assert_eq!(rss.matches("<item>").count(), 20);
+    assert_eq!(atom.matches("<entry>").count(), 20);
+    assert!(!rss.contains("Issue number 0<"));
+    assert!(!atom.contains("Issue number 0<"));
+}
+
+#[tokio::test]
+async fn feeds_are_served_before_anything_is_published() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for path in ["feed.rss", "feed.atom"] {
+        // Act
+        let response = app.get_feed(path).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+    }
+}
@@ -3,12 +3,22 @@ use uuid::Uuid;
 
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use fake::{
+    faker::{internet::en::SafeEmail, name::en::Name},
+    Fake,
+};
 use once_cell::sync::Lazy;
-use wiremock::MockServer;
-
-use zero2prod::configuration::{get_configuration, DatabaseSettings};
-use zero2prod::email_client::EmailClient;
-use zero2prod::issue_delivery_worker::{try_execute_task, ExecutionOutcome};
+use secrecy::ExposeSecret;
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockBuilder, MockServer, ResponseTemplate};
+
+use zero2prod::configuration::{get_configuration, DatabaseSettings, Settings};
+use zero2prod::confirmation_email_queue::try_send_confirmation_email;
+use zero2prod::email_client::EmailTransport;
+use zero2prod::issue_delivery_worker::{run_worker_until_stopped, try_execute_task, ExecutionOutcome};
+use zero2prod::issue_scheduler::promote_due_issues;
+use zero2prod::shutdown::{shutdown_channel, ShutdownTrigger};
 use zero2prod::startup::{Application, get_connection_pool};
 use zero2prod::telemetry::{get_subscriber, init_subscriber};
 
@@ -35,7 +45,9 @@ pub struct TestApp {
     pub email_server: MockServer,
     pub test_user: TestUser,
     pub api_client: reqwest::Client,
-    pub email_client: EmailClient,
+    pub email_client: Arc<dyn EmailTransport>,
+    pub shutdown: ShutdownTrigger,
+    pub configuration: Settings,
 }
 
 pub struct ConfirmationLinks {
@@ -47,18 +59,41 @@ impl TestApp {
     pub async fn dispatch_all_pending_emails(&self) {
         loop {
             if let ExecutionOutcome::EmptyQueue =
-                try_execute_task(&self.db_pool, &self.email_client)
+                try_execute_task(
+                    &self.db_pool,
+                    self.email_client.as_ref(),
+                    &self.address,
+                    self.configuration.application.tracking_enabled,
+                )
                     .await
                     .unwrap()
             {
                 break;
             }
         }
+        while let ExecutionOutcome::TaskCompleted =
+            try_send_confirmation_email(&self.db_pool, self.email_client.as_ref(), &self.address)
+                .await
+                .unwrap()
+        {}
+    }
+
+    pub async fn promote_due_issues(&self) -> usize {
+        promote_due_issues(&self.db_pool).await.unwrap()
+    }
+
+    pub fn spawn_worker(&self) -> ShutdownTrigger {
+        let (shutdown_trigger, shutdown_signal) = shutdown_channel();
+        let mut configuration = self.configuration.clone();
+        configuration.application.base_url = self.address.clone();
+        let email_client = configuration.email_client.clone().client();
+        tokio::spawn(run_worker_until_stopped(configuration, email_client, shutdown_signal));
+        shutdown_trigger
     }
 
     pub async fn post_subscriptions(&self, body: String) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/subscriptions", &self.address))
+            .post(format!("{}/subscriptions", &self.address))
             .header("Content-Type", "application/x-www-form-urlencoded")
             .body(body)
             .send()
@@ -88,20 +123,71 @@ impl TestApp {
             confirmation_link
         };
 
-        let html = get_link(&body["HtmlBody"].as_str().unwrap());
-        let plain_text = get_link(&body["TextBody"].as_str().unwrap());
+        let html = get_link(body["HtmlBody"].as_str().unwrap());
+        let plain_text = get_link(body["TextBody"].as_str().unwrap());
         ConfirmationLinks {
             html,
             plain_text,
         }
     }
 
+    pub fn get_unsubscribe_link(&self, email_request: &wiremock::Request) -> reqwest::Url {
+        let body: serde_json::Value = serde_json::from_slice(&email_request.body)
+            .unwrap();
+
+        let header = body["Headers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|h| h["Name"] == "List-Unsubscribe")
+            .unwrap();
+        let raw_link = header["Value"]
+            .as_str()
+            .unwrap()
+            .trim_start_matches('<')
+            .trim_end_matches('>');
+        let unsubscribe_link = reqwest::Url::parse(raw_link).unwrap();
+        // Ensure call is local
+        assert_eq!(unsubscribe_link.host_str().unwrap(), "127.0.0.1");
+        unsubscribe_link
+    }
+
+    pub async fn get_preferences(&self, unsubscribe_token: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/subscriptions/preferences", &self.address))
+            .query(&[("unsubscribe_token", unsubscribe_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_preferences_html(&self, unsubscribe_token: &str) -> String {
+        self.get_preferences(unsubscribe_token).await.text().await.unwrap()
+    }
+
+    pub async fn post_preferences<Body>(
+        &self,
+        unsubscribe_token: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/subscriptions/preferences", &self.address))
+            .query(&[("unsubscribe_token", unsubscribe_token)])
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .form(body)
             .send()
             .await
@@ -110,7 +196,7 @@ impl TestApp {
 
     pub async fn get_login_html(&self) -> String {
         self.api_client
-            .get(&format!("{}/login", &self.address))
+            .get(format!("{}/login", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -121,7 +207,7 @@ impl TestApp {
 
     pub async fn get_admin_dashboard(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/dashboard", &self.address))
+            .get(format!("{}/admin/dashboard", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -133,7 +219,7 @@ impl TestApp {
 
     pub async fn get_change_password(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/password", &self.address))
+            .get(format!("{}/admin/password", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -148,7 +234,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/password", &self.address))
+            .post(format!("{}/admin/password", &self.address))
             .form(body)
             .send()
             .await
@@ -157,7 +243,7 @@ impl TestApp {
 
     pub async fn post_logout(&self) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/admin/logout", &self.address))
+            .post(format!("{}/admin/logout", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -165,7 +251,7 @@ impl TestApp {
 
     pub async fn get_publish_newsletter(&self) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/admin/newsletters", &self.address))
+            .get(format!("{}/admin/newsletters", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -175,17 +261,533 @@ impl TestApp {
         self.get_publish_newsletter().await.text().await.unwrap()
     }
 
+    pub async fn get_delivery_failures_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/delivery_failures", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
     pub async fn post_publish_newsletter<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
-            .post(&format!("{}/admin/newsletters", &self.address))
+            .post(format!("{}/admin/newsletters", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_send_test_newsletter<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/newsletters/test", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_scheduled_newsletters(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/newsletters/scheduled", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_scheduled_newsletters_html(&self) -> String {
+        self.get_scheduled_newsletters().await.text().await.unwrap()
+    }
+
+    pub async fn post_reschedule_newsletter<Body>(
+        &self,
+        issue_id: Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/newsletters/{}/reschedule", &self.address, issue_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_cancel_newsletter(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/newsletters/{}/cancel", &self.address, issue_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_drafts(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/drafts", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_drafts_html(&self) -> String {
+        self.get_drafts().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_draft<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Returns the id of the new draft, taken from the redirect to its edit page
+    pub async fn create_draft<Body>(&self, body: &Body) -> Uuid
+    where
+        Body: serde::Serialize,
+    {
+        let response = self.post_create_draft(body).await;
+        let location = response.headers().get("Location").unwrap().to_str().unwrap();
+        let issue_id = location
+            .strip_prefix("/admin/drafts/")
+            .and_then(|l| l.strip_suffix("/edit"))
+            .unwrap();
+        Uuid::parse_str(issue_id).unwrap()
+    }
+
+    pub async fn post_update_draft<Body>(&self, issue_id: Uuid, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts/{}", &self.address, issue_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_edit_draft(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/drafts/{}/edit", &self.address, issue_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_draft_preview(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/drafts/{}/preview", &self.address, issue_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_delete_draft(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/drafts/{}/delete", &self.address, issue_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_publish_draft<Body>(&self, issue_id: Uuid, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts/{}/publish", &self.address, issue_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_send_test_draft<Body>(&self, issue_id: Uuid, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/drafts/{}/test", &self.address, issue_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_email_templates(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/templates", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_email_templates_html(&self) -> String {
+        self.get_email_templates().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_email_template<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/templates", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_update_email_template<Body>(
+        &self,
+        email_template_id: Uuid,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/templates/{}", &self.address, email_template_id))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_delete_email_template(&self, email_template_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/templates/{}/delete", &self.address, email_template_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_email_template_id(&self, name: &str) -> Uuid {
+        sqlx::query!(
+            "SELECT email_template_id FROM email_templates WHERE name = $1",
+            name
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .expect("Failed to fetch email template.")
+        .email_template_id
+    }
+
+    pub async fn get_archive_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/archive", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get_archived_issue(&self, slug: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/archive/{}", &self.address, slug))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_feed(&self, path: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_issue_slug(&self, title: &str) -> Option<String> {
+        sqlx::query!(
+            "SELECT slug FROM newsletter_issues WHERE title = $1",
+            title
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .expect("Failed to fetch newsletter issue.")
+        .slug
+    }
+
+    pub async fn get_issues_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/issues", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get_issue_delivery_status(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/issues/{}", &self.address, issue_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_tracking_link(&self, path: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_issue_delivery_status_html(&self, issue_id: Uuid) -> String {
+        self.get_issue_delivery_status(issue_id).await.text().await.unwrap()
+    }
+
+    pub async fn post_email_event(
+        &self,
+        body: &serde_json::Value,
+        username: &str,
+        password: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/webhooks/email-events", &self.address))
+            .basic_auth(username, Some(password))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_authenticated_email_event(&self, body: &serde_json::Value) -> reqwest::Response {
+        let webhooks = &self.configuration.webhooks;
+        self.post_email_event(body, &webhooks.username, webhooks.password.expose_secret())
+            .await
+    }
+
+    pub async fn get_lists(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/lists", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lists_html(&self) -> String {
+        self.get_lists().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_list<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/lists", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_segments(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/segments", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_segments_html(&self) -> String {
+        self.get_segments().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_segment<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/segments", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_profile_fields(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/profile_fields", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_profile_fields_html(&self) -> String {
+        self.get_profile_fields().await.text().await.unwrap()
+    }
+
+    pub async fn post_create_profile_field<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/profile_fields", &self.address))
             .form(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
+
+    pub async fn get_import_subscribers(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers/import", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_import_subscribers_html(&self) -> String {
+        self.get_import_subscribers().await.text().await.unwrap()
+    }
+
+    // Fields are sent in the order the import form uses, options before the file
+    pub async fn post_import_subscribers(&self, csv: &str, pre_confirmed: bool) -> reqwest::Response {
+        self.post_import_subscribers_to_list(csv, "", pre_confirmed).await
+    }
+
+    pub async fn post_import_subscribers_to_list(
+        &self,
+        csv: &str,
+        list_id: &str,
+        pre_confirmed: bool,
+    ) -> reqwest::Response {
+        let mut form = reqwest::multipart::Form::new().text("list_id", list_id.to_owned());
+        if pre_confirmed {
+            form = form.text("pre_confirmed", "on");
+        }
+        let file = reqwest::multipart::Part::text(csv.to_owned())
+            .file_name("subscribers.csv")
+            .mime_str("text/csv")
+            .unwrap();
+        self.api_client
+            .post(format!("{}/admin/subscribers/import", &self.address))
+            .multipart(form.part("file", file))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_export_subscribers(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers/export?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_import_report(&self, import_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers/import/{}/report", &self.address, import_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_tags(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/tags", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_tags_html(&self) -> String {
+        self.get_tags().await.text().await.unwrap()
+    }
+
+    pub async fn post_add_tag<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/tags", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_remove_tag<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/tags/delete", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_suppressions(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/suppressions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_suppressions_html(&self) -> String {
+        self.get_suppressions().await.text().await.unwrap()
+    }
+
+    pub async fn post_add_suppression<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/suppressions", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_remove_suppression<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/suppressions/delete", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_issue_status(&self, issue_id: Uuid) -> String {
+        sqlx::query!(
+            "SELECT status FROM newsletter_issues WHERE newsletter_issue_id = $1",
+            issue_id
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .expect("Failed to fetch newsletter issue status.")
+        .status
+    }
 }
 
 pub struct TestUser {
@@ -235,6 +837,14 @@ impl TestUser {
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+pub async fn spawn_app_with_tracking() -> TestApp {
+    spawn_app_with(|c| c.application.tracking_enabled = true).await
+}
+
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
     Lazy::force(&TRACING);
 
     let email_server = MockServer::start().await;
@@ -244,16 +854,19 @@ pub async fn spawn_app() -> TestApp {
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
         c.email_client.base_url = email_server.uri();
+        configure(&mut c);
         c
     };
 
     configure_database(&configuration.database).await;
 
-    let application = Application::build(configuration.clone())
+    let email_client = configuration.email_client.clone().client();
+    let application = Application::build(configuration.clone(), email_client.clone())
         .await
         .expect("Failed to  build application.");
     let application_port = application.port();
-    let _ = tokio::spawn(application.run_until_stopped());
+    let (shutdown_trigger, shutdown_signal) = shutdown_channel();
+    tokio::spawn(application.run_until_stopped(shutdown_signal));
     
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -268,7 +881,9 @@ pub async fn spawn_app() -> TestApp {
         email_server,
         test_user: TestUser::generate(),
         api_client: client,
-        email_client: configuration.email_client.client(),
+        email_client,
+        shutdown: shutdown_trigger,
+        configuration: configuration.clone(),
     };
     test_app.test_user.store(&test_app.db_pool).await;
     test_app
@@ -300,6 +915,50 @@ async fn configure_database(config: &DatabaseSettings) -> PgPool {
     connection_pool
 }
 
+pub fn when_sending_an_email() -> MockBuilder {
+    Mock::given(path("/email")).and(method("POST"))
+}
+
+pub async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
+    let name: String = Name().fake();
+    let email: String = SafeEmail().fake();
+    let body = serde_urlencoded::to_string(serde_json::json!({
+        "name": name,
+        "email": email,
+    }))
+    .unwrap();
+
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate:: new(200))
+        .named("Create unconfirmed subscriber")
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    app.post_subscriptions(body)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    let email_request = &app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    app.get_confirmation_links(email_request)
+}
+
+pub async fn create_confirmed_subscriber(app: &TestApp) {
+    let confirmation_link = create_unconfirmed_subscriber(app).await;
+    reqwest::get(confirmation_link.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
 pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
     assert_eq!(response.status().as_u16(), 303);
     assert_eq!(response.headers().get("Location").unwrap(), location);
@@ -0,0 +1,184 @@
+use crate::helpers::{
+    spawn_app, assert_is_redirect_to, create_confirmed_subscriber, when_sending_an_email,
+    TestApp,
+};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn publish_newsletter(app: &TestApp) -> Uuid {
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 303);
+    sqlx::query!("SELECT newsletter_issue_id FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .newsletter_issue_id
+}
+
+#[tokio::test]
+async fn the_dashboard_tracks_pending_and_delivered_emails() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+    let issue_id = publish_newsletter(&app).await;
+
+    // Act - Part 1 - Before the worker runs
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+
+    // Assert - Part 1
+    assert!(html_page.contains("<h1>Newsletter title</h1>"));
+    assert!(html_page.contains("<tr><th>Total recipients</th><td>2</td></tr>"));
+    assert!(html_page.contains("<tr><th>Pending</th><td>2</td></tr>"));
+    assert!(html_page.contains("<tr><th>Delivered</th><td>0</td></tr>"));
+
+    // Act - Part 2 - After the worker runs
+    app.dispatch_all_pending_emails().await;
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+
+    // Assert - Part 2
+    assert!(html_page.contains("<tr><th>Total recipients</th><td>2</td></tr>"));
+    assert!(html_page.contains("<tr><th>Pending</th><td>0</td></tr>"));
+    assert!(html_page.contains("<tr><th>Delivered</th><td>2</td></tr>"));
+    assert!(html_page.contains("<p>Status: sent</p>"));
+}
+
+#[tokio::test]
+async fn completed_deliveries_are_logged() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let issue_id = publish_newsletter(&app).await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let delivery = sqlx::query!(
+        "SELECT outcome, n_retries FROM issue_deliveries WHERE newsletter_issue_id = $1",
+        issue_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(delivery.outcome, "delivered");
+    assert_eq!(delivery.n_retries, 0);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_shown_on_the_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+    let issue_id = publish_newsletter(&app).await;
+    // Jump straight to the last attempt
+    sqlx::query!("UPDATE issue_delivery_queue SET n_retries = 4")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+    assert!(html_page.contains("<tr><th>Failed</th><td>1</td></tr>"));
+    assert!(html_page.contains("<tr><th>Pending</th><td>0</td></tr>"));
+    let subscriber_email = sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .email;
+    assert!(html_page.contains(&format!("<tr><td>{}</td><td>5</td>", subscriber_email)));
+}
+
+#[tokio::test]
+async fn subscribers_who_left_before_delivery_are_shown_as_skipped() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue_id = publish_newsletter(&app).await;
+    sqlx::query!("UPDATE subscriptions SET status = 'unsubscribed'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+    assert!(html_page.contains("<tr><th>Skipped</th><td>1</td></tr>"));
+    assert!(html_page.contains("<tr><th>Delivered</th><td>0</td></tr>"));
+}
+
+#[tokio::test]
+async fn published_issues_link_to_their_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let issue_id = publish_newsletter(&app).await;
+
+    // Assert
+    let html_page = app.get_issues_html().await;
+    assert!(html_page.contains(&format!(
+        r#"<a href="/admin/issues/{}">Newsletter title</a>"#,
+        issue_id
+    )));
+}
+
+#[tokio::test]
+async fn unknown_issues_are_not_found() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_issue_delivery_status(Uuid::new_v4()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_delivery_status() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_issue_delivery_status(Uuid::new_v4()).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
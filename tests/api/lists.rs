@@ -0,0 +1,343 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, when_sending_an_email, TestApp};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn create_list(app: &TestApp, name: &str) {
+    let response = app
+        .post_create_list(&serde_json::json!({
+            "name": name,
+            "title": format!("The {} list", name),
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/lists");
+}
+
+async fn get_list_id(app: &TestApp, name: &str) -> Uuid {
+    sqlx::query!("SELECT list_id FROM lists WHERE name = $1", name)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .list_id
+}
+
+async fn get_list_status(app: &TestApp, email: &str, list: &str) -> String {
+    sqlx::query!(
+        r#"
+        SELECT list_subscriptions.status
+        FROM list_subscriptions
+        JOIN subscriptions ON subscriptions.id = list_subscriptions.subscriber_id
+        JOIN lists ON lists.list_id = list_subscriptions.list_id
+        WHERE subscriptions.email = $1 AND lists.name = $2
+        "#,
+        email,
+        list
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+    .status
+}
+
+async fn get_subscriber_status(app: &TestApp, email: &str) -> String {
+    sqlx::query!("SELECT status FROM subscriptions WHERE email = $1", email)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .status
+}
+
+async fn subscribe_to_list(app: &TestApp, email: &str, list: &str) -> reqwest::Url {
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let body = serde_urlencoded::to_string(serde_json::json!({
+        "name": "le guin",
+        "email": email,
+        "list": list,
+    }))
+    .unwrap();
+    app.post_subscriptions(body).await.error_for_status().unwrap();
+
+    let email_request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    app.get_confirmation_links(&email_request).html
+}
+
+async fn subscribe_to_list_and_confirm(app: &TestApp, email: &str, list: &str) {
+    let confirmation_link = subscribe_to_list(app, email, list).await;
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
+async fn deliver_issue_to_list(app: &TestApp, list: &str) -> Vec<wiremock::Request> {
+    let n_received = app.email_server.received_requests().await.unwrap().len();
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "list_id": get_list_id(app, list).await.to_string(),
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    app.dispatch_all_pending_emails().await;
+    app.email_server.received_requests().await.unwrap()[n_received..].to_vec()
+}
+
+fn recipient(request: &wiremock::Request) -> String {
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    body["To"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn admins_can_create_lists() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_create_list(&serde_json::json!({
+            "name": "weekly-digest",
+            "title": "Weekly digest",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/lists");
+    let html_page = app.get_lists_html().await;
+    assert!(html_page.contains("<p><i>The list has been created.</i></p>"));
+    assert!(html_page.contains("<td>weekly-digest</td>"));
+    assert!(html_page.contains("<td>Weekly digest</td>"));
+    // The list created by the migrations stays the default
+    assert!(html_page.contains("<td>newsletter</td>"));
+    let publish_page = app.get_publish_newsletter_html().await;
+    assert!(publish_page.contains("Weekly digest</option>"));
+}
+
+#[tokio::test]
+async fn invalid_lists_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"name": "Weekly Digest", "title": "Weekly digest"}),
+            "List names may only contain lowercase letters, digits and hyphens.",
+        ),
+        (
+            serde_json::json!({"name": "weekly", "title": " "}),
+            "The list needs a title.",
+        ),
+        (
+            serde_json::json!({"name": "newsletter", "title": "Another newsletter"}),
+            "A list with this name already exists.",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_create_list(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/lists");
+        let html_page = app.get_lists_html().await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {}",
+            error_message,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribing_to_an_unknown_list_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com&list=unknown".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn subscribers_confirm_each_list_separately() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_list(&app, "weekly").await;
+    let email = "ursula_le_guin@gmail.com";
+    subscribe_to_list_and_confirm(&app, email, "newsletter").await;
+
+    // Act - Part 1 - Join a second list
+    let confirmation_link = subscribe_to_list(&app, email, "weekly").await;
+
+    // Assert - Part 1
+    assert_eq!(get_list_status(&app, email, "weekly").await, "pending_confirmation");
+    assert_eq!(get_list_status(&app, email, "newsletter").await, "confirmed");
+    assert_eq!(get_subscriber_status(&app, email).await, "confirmed");
+
+    // Act - Part 2 - Confirm it
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Assert - Part 2
+    assert_eq!(get_list_status(&app, email, "weekly").await, "confirmed");
+}
+
+#[tokio::test]
+async fn issues_are_only_sent_to_confirmed_members_of_their_list() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_list(&app, "weekly").await;
+    subscribe_to_list_and_confirm(&app, "ursula@example.com", "newsletter").await;
+    subscribe_to_list_and_confirm(&app, "octavia@example.com", "weekly").await;
+    subscribe_to_list(&app, "pending@example.com", "weekly").await;
+
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    // Act
+    let requests = deliver_issue_to_list(&app, "weekly").await;
+
+    // Assert
+    assert_eq!(requests.len(), 1);
+    assert_eq!(recipient(&requests[0]), "octavia@example.com");
+}
+
+#[tokio::test]
+async fn issues_are_sent_to_the_default_list_when_none_is_selected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_list(&app, "weekly").await;
+    subscribe_to_list_and_confirm(&app, "ursula@example.com", "").await;
+    subscribe_to_list_and_confirm(&app, "octavia@example.com", "weekly").await;
+
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    // Act
+    app.post_publish_newsletter(&serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string()
+    }))
+    .await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    assert_eq!(recipient(&request), "ursula@example.com");
+}
+
+#[tokio::test]
+async fn unsubscribing_from_a_list_keeps_the_other_lists() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_list(&app, "weekly").await;
+    let email = "ursula@example.com";
+    subscribe_to_list_and_confirm(&app, email, "newsletter").await;
+    subscribe_to_list_and_confirm(&app, email, "weekly").await;
+
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let requests = deliver_issue_to_list(&app, "weekly").await;
+    let unsubscribe_link = app.get_unsubscribe_link(&requests[0]);
+
+    // Act - Part 1 - Leave the list the issue was sent to
+    app.api_client
+        .post(unsubscribe_link.clone())
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Assert - Part 1
+    assert_eq!(get_list_status(&app, email, "weekly").await, "unsubscribed");
+    assert_eq!(get_list_status(&app, email, "newsletter").await, "confirmed");
+    assert_eq!(get_subscriber_status(&app, email).await, "confirmed");
+    assert!(deliver_issue_to_list(&app, "weekly").await.is_empty());
+
+    // Act - Part 2 - Leave the last one
+    let requests = deliver_issue_to_list(&app, "newsletter").await;
+    let unsubscribe_link = app.get_unsubscribe_link(&requests[0]);
+    app.api_client
+        .post(unsubscribe_link)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Assert - Part 2
+    assert_eq!(get_list_status(&app, email, "newsletter").await, "unsubscribed");
+    assert_eq!(get_subscriber_status(&app, email).await, "unsubscribed");
+}
+
+#[tokio::test]
+async fn publishing_to_an_unknown_list_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "list_id": Uuid::new_v4().to_string(),
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The selected list no longer exists.</i></p>"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_lists() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_create_list(&serde_json::json!({
+            "name": "weekly",
+            "title": "Weekly",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_lists().await, "/login");
+}
@@ -1,9 +1,27 @@
 mod helpers;
 
 mod admin_dashboard;
+mod archive;
 mod change_password;
+mod drafts;
+mod email_templates;
+mod feeds;
 mod health_check;
+mod issues;
+mod lists;
 mod login;
 mod newsletters;
+mod newsletters_scheduled;
+mod newsletters_test_copy;
+mod profile_fields;
+mod segments;
+mod shutdown;
+mod subscribers_export;
+mod subscribers_import;
 mod subscriptions;
-mod subscriptions_confirm;
\ No newline at end of file
+mod subscriptions_confirm;
+mod subscriptions_preferences;
+mod subscriptions_unsubscribe;
+mod suppressions;
+mod tracking;
+mod webhooks;
\ No newline at end of file
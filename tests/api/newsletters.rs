@@ -1,57 +1,12 @@
-use crate::helpers::{spawn_app, TestApp, ConfirmationLinks, assert_is_redirect_to};
-
-use fake::{
-    faker::{internet::en::SafeEmail, name::en::Name},
-    Fake,
+use crate::helpers::{
+    spawn_app, assert_is_redirect_to,
+    create_confirmed_subscriber, create_unconfirmed_subscriber, when_sending_an_email
 };
-use wiremock::matchers::{any, method, path};
-use wiremock::{Mock, ResponseTemplate, MockBuilder};
-
-use std::time::Duration;
-
-fn when_sending_an_email() -> MockBuilder {
-    Mock::given(path("/email")).and(method("POST"))
-}
 
-async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
-    let name: String = Name().fake();
-    let email: String = SafeEmail().fake();
-    let body = serde_urlencoded::to_string(&serde_json::json!({
-        "name": name,
-        "email": email,
-    }))
-    .unwrap();
-
-    let _mock_guard = when_sending_an_email()
-        .respond_with(ResponseTemplate:: new(200))
-        .named("Create unconfirmed subscriber")
-        .expect(1)
-        .mount_as_scoped(&app.email_server)
-        .await;
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
 
-    app.post_subscriptions(body.into())
-        .await
-        .error_for_status()
-        .unwrap();
-
-    let email_request = &app
-        .email_server
-        .received_requests()
-        .await
-        .unwrap()
-        .pop()
-        .unwrap();
-    app.get_confirmation_links(&email_request)
-}
-
-async fn create_confirmed_subscriber(app: &TestApp) {
-    let confirmation_link = create_unconfirmed_subscriber(app).await;
-    reqwest::get(confirmation_link.html)
-        .await
-        .unwrap()
-        .error_for_status()
-        .unwrap();
-}
+use std::time::Duration;
 
 #[tokio::test]
 async fn newsletters_are_not_delivered_to_unconfirmed_subscribers() {
@@ -216,4 +171,502 @@ async fn concurrent_form_submission_is_handled_gracefully() {
     app.dispatch_all_pending_emails().await;
 
     // Mock asserts on drop
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn failed_deliveries_are_retried_later() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Act - Part 1 - The email provider is temporarily unavailable
+    let mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+    drop(mock_guard);
+
+    // Assert - Part 1 - The task is rescheduled rather than dropped
+    let task = sqlx::query!("SELECT n_retries, execute_after > now() AS \"is_delayed!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch queued task.");
+    assert_eq!(task.n_retries, 1);
+    assert!(task.is_delayed);
+
+    // Act - Part 2 - The retry is due and the provider has recovered
+    sqlx::query!("UPDATE issue_delivery_queue SET execute_after = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert - Part 2
+    let n_queued = sqlx::query!("SELECT count(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_queued, 0);
+}
+
+#[tokio::test]
+async fn deliveries_that_exhaust_their_retries_are_recorded_as_failures() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Act - Keep making retries due until the task leaves the queue
+    for _ in 0..10 {
+        sqlx::query!("UPDATE issue_delivery_queue SET execute_after = now()")
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+        app.dispatch_all_pending_emails().await;
+    }
+
+    // Assert
+    let n_queued = sqlx::query!("SELECT count(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_queued, 0);
+
+    let failure = sqlx::query!("SELECT n_retries FROM issue_delivery_failures")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch delivery failure.");
+    let n_requests = app.email_server.received_requests().await.unwrap().len();
+    // The confirmation email accounts for one of the requests
+    assert_eq!(failure.n_retries as usize, n_requests - 1);
+
+    let html_page = app.get_delivery_failures_html().await;
+    assert!(html_page.contains("Newsletter title"));
+}
+
+#[tokio::test]
+async fn issues_that_cannot_be_rendered_are_recorded_as_failures() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    app.post_create_email_template(&serde_json::json!({
+        "name": "Branded",
+        "html_layout": "<header>Our newsletter</header>{{ content }}",
+        "text_layout": "OUR NEWSLETTER\n{{ content }}",
+    }))
+    .await;
+    let email_template_id = app.get_email_template_id("Branded").await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    app.post_publish_newsletter(&serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "email_template_id": email_template_id.to_string(),
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    }))
+    .await;
+    // A layout that no longer parses fails every attempt the same way
+    sqlx::query!("UPDATE email_templates SET html_layout = '<header>Our newsletter</header>'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act - Keep making retries due until the task leaves the queue
+    for _ in 0..10 {
+        sqlx::query!("UPDATE issue_delivery_queue SET execute_after = now()")
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+        app.dispatch_all_pending_emails().await;
+    }
+
+    // Assert
+    let n_queued = sqlx::query!("SELECT count(*) AS \"count!\" FROM issue_delivery_queue")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_queued, 0);
+    sqlx::query!("SELECT n_retries FROM issue_delivery_failures")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch delivery failure.");
+}
+
+#[tokio::test]
+async fn the_background_worker_delivers_new_issues_without_waiting_to_poll() {
+    // Arrange
+    let mut app = spawn_app().await;
+    app.configuration.worker.poll_interval_seconds = 60;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let _worker = app.spawn_worker();
+    // Give the worker time to find the queue empty and start listening
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Assert - Well before the poll interval elapses
+    let mut n_issue_requests = 0;
+    for _ in 0..50 {
+        // The confirmation email accounts for one of the requests
+        n_issue_requests = app.email_server.received_requests().await.unwrap().len() - 1;
+        if n_issue_requests > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(n_issue_requests, 1);
+}
+
+#[tokio::test]
+async fn concurrent_workers_deliver_an_issue_in_parallel() {
+    // Arrange
+    let mut app = spawn_app().await;
+    app.configuration.worker.concurrency = 4;
+    app.configuration.email_client.max_emails_per_second = 100;
+    for _ in 0..4 {
+        create_confirmed_subscriber(&app).await;
+    }
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+        .expect(4)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Act
+    let _worker = app.spawn_worker();
+
+    // Assert - A single consumer would need at least four seconds
+    let mut n_issue_requests = 0;
+    for _ in 0..25 {
+        // The confirmation emails account for four of the requests
+        n_issue_requests = app.email_server.received_requests().await.unwrap().len() - 4;
+        if n_issue_requests == 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(n_issue_requests, 4);
+}
+
+#[tokio::test]
+async fn an_issue_is_marked_as_sent_once_every_email_is_delivered() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    let issue_id = sqlx::query!("SELECT newsletter_issue_id FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .newsletter_issue_id;
+    assert_eq!(app.get_issue_status(issue_id).await, "sending");
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    assert_eq!(app.get_issue_status(issue_id).await, "sent");
+}
+
+#[tokio::test]
+async fn markdown_issues_are_delivered_with_rendered_html_and_text_bodies() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "markdown": "Hello **world**, read [more](https://example.com).",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    // The confirmation email accounts for the first request
+    let request = &app.email_server.received_requests().await.unwrap()[1];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let html_body = body["HtmlBody"].as_str().unwrap();
+    let text_body = body["TextBody"].as_str().unwrap();
+    assert!(html_body.starts_with("<p>Hello <strong>world</strong>, read <a href=\"https://example.com\""));
+    assert!(text_body.starts_with("Hello world, read more (https://example.com)."));
+
+    let saved = sqlx::query!("SELECT markdown_content FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        saved.markdown_content.as_deref(),
+        Some("Hello **world**, read [more](https://example.com).")
+    );
+}
+
+#[tokio::test]
+async fn merge_tags_are_rendered_for_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "News for {{ name }}",
+        "text": "Sent to {{ email }}, opt out at {{ unsubscribe_url }}",
+        "html": "<p>Hi {{name}}</p><a href=\"{{ unsubscribe_url }}\">Leave</a>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let subscribers = sqlx::query!("SELECT email, name FROM subscriptions")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    // The confirmation emails account for the first two requests
+    let requests = app.email_server.received_requests().await.unwrap();
+    for request in &requests[2..] {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let subscriber = subscribers
+            .iter()
+            .find(|s| s.email == body["To"])
+            .unwrap();
+        let unsubscribe_link = app.get_unsubscribe_link(request);
+        assert_eq!(body["Subject"], format!("News for {}", subscriber.name));
+        assert!(body["TextBody"].as_str().unwrap().starts_with(&format!(
+            "Sent to {}, opt out at {}",
+            subscriber.email,
+            unsubscribe_link
+        )));
+        assert!(body["HtmlBody"].as_str().unwrap().starts_with(&format!(
+            "<p>Hi {}</p><a href=\"{}\">Leave</a>",
+            htmlescape::encode_minimal(&subscriber.name),
+            htmlescape::encode_minimal(unsubscribe_link.as_str())
+        )));
+    }
+}
+
+#[tokio::test]
+async fn issues_without_a_body_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    let test_cases = vec![
+        ("", "sent right away"),
+        ("2099-01-01T09:00", "scheduled"),
+    ];
+
+    for (scheduled_for, description) in test_cases {
+        // Act
+        let newsletter_request_body = serde_json::json!({
+            "title": "Newsletter title",
+            "text": " ",
+            "html": "",
+            "markdown": "",
+            "scheduled_for": scheduled_for,
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        });
+        let response = app.post_publish_newsletter(&newsletter_request_body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/newsletters");
+        let html_page = app.get_publish_newsletter_html().await;
+        assert!(
+            html_page.contains("The issue has no content, write it in Markdown, HTML or plain text."),
+            "An empty issue was not rejected when {}",
+            description
+        );
+    }
+
+    let n_issues = sqlx::query!("SELECT count(*) as \"count!\" FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_issues, 0);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn issues_with_unknown_merge_tags_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Hi {{ first_name }}",
+        "html": "<p>Hi {{ first_name }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("Unknown merge tags: {{ first_name }}."));
+
+    let n_issues = sqlx::query!("SELECT count(*) as \"count!\" FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_issues, 0);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn issues_with_merge_tags_of_unknown_profile_fields_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let response = app
+        .post_create_profile_field(&serde_json::json!({
+            "key": "company",
+            "label": "Company",
+            "field_type": "string"
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/profile_fields");
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Hi {{ profile.compnay }}",
+        "html": "<p>Hi {{ profile.company }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains(
+        "Unknown merge tags: {{ profile.compnay }}. Available tags are {{ name }}, {{ email }}, \
+        {{ unsubscribe_url }}, {{ profile.company }}."
+    ));
+
+    let n_issues = sqlx::query!("SELECT count(*) as \"count!\" FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_issues, 0);
+    app.dispatch_all_pending_emails().await;
+}
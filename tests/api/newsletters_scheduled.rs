@@ -0,0 +1,203 @@
+use crate::helpers::{
+    spawn_app, assert_is_redirect_to, create_confirmed_subscriber, when_sending_an_email, TestApp
+};
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+fn send_time(offset: Duration) -> String {
+    (Utc::now() + offset).format("%Y-%m-%dT%H:%M").to_string()
+}
+
+async fn schedule_newsletter(app: &TestApp, scheduled_for: &str) -> reqwest::Response {
+    let newsletter_request_body = serde_json::json!({
+        "title": "Scheduled title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string(),
+        "scheduled_for": scheduled_for,
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await
+}
+
+async fn get_only_issue_id(app: &TestApp) -> Uuid {
+    sqlx::query!("SELECT newsletter_issue_id FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch newsletter issue.")
+        .newsletter_issue_id
+}
+
+async fn make_all_issues_due(app: &TestApp) {
+    sqlx::query!("UPDATE newsletter_issues SET scheduled_for = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn scheduled_newsletters_are_not_delivered_before_their_send_time() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = schedule_newsletter(&app, &send_time(Duration::days(1))).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The newsletter issue has been scheduled for"));
+
+    assert_eq!(app.promote_due_issues().await, 0);
+    app.dispatch_all_pending_emails().await;
+
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("Scheduled title"));
+}
+
+#[tokio::test]
+async fn scheduled_newsletters_are_delivered_once_due() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    schedule_newsletter(&app, &send_time(Duration::days(1))).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    make_all_issues_due(&app).await;
+    let n_promoted = app.promote_due_issues().await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    assert_eq!(n_promoted, 1);
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(!html_page.contains("Scheduled title"));
+    // Promoting again must not enqueue the issue a second time
+    assert_eq!(app.promote_due_issues().await, 0);
+}
+
+#[tokio::test]
+async fn a_send_time_in_the_past_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = schedule_newsletter(&app, &send_time(-Duration::days(1))).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The send time must be in the future.</i></p>"));
+
+    let n_issues = sqlx::query!("SELECT count(*) as \"count!\" FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_issues, 0);
+}
+
+#[tokio::test]
+async fn cancelled_newsletters_are_never_delivered() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    schedule_newsletter(&app, &send_time(Duration::days(1))).await;
+    let issue_id = get_only_issue_id(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_cancel_newsletter(issue_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been cancelled and moved back to drafts.</i></p>"));
+    assert!(!html_page.contains("Scheduled title"));
+
+    make_all_issues_due(&app).await;
+    assert_eq!(app.promote_due_issues().await, 0);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn newsletters_can_be_rescheduled_before_they_are_sent() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    schedule_newsletter(&app, &send_time(Duration::days(1))).await;
+    let issue_id = get_only_issue_id(&app).await;
+    let new_send_time = send_time(Duration::days(2));
+
+    // Act
+    let response = app
+        .post_reschedule_newsletter(
+            issue_id,
+            &serde_json::json!({ "scheduled_for": new_send_time }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("The newsletter issue has been rescheduled for"));
+    assert!(html_page.contains(&new_send_time));
+}
+
+#[tokio::test]
+async fn newsletters_that_have_been_sent_cannot_be_cancelled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string(),
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    let issue_id = get_only_issue_id(&app).await;
+
+    // Act
+    let response = app.post_cancel_newsletter(issue_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue is no longer scheduled.</i></p>"));
+    get_only_issue_id(&app).await;
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_scheduled_newsletters() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_scheduled_newsletters().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
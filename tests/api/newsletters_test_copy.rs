@@ -0,0 +1,260 @@
+use crate::helpers::{
+    spawn_app, spawn_app_with, assert_is_redirect_to, create_confirmed_subscriber,
+    when_sending_an_email, TestApp
+};
+
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+use std::time::{Duration, Instant};
+
+fn test_copy_body(test_recipients: &str) -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "test_recipients": test_recipients,
+    })
+}
+
+async fn count_rows(app: &TestApp, table: &str) -> i64 {
+    sqlx::query_scalar::<_, i64>(&format!("SELECT count(*) FROM {}", table))
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn a_test_copy_is_sent_to_every_test_recipient_only() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_newsletter(&test_copy_body("editor@example.com, reviewer@example.com"))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains(
+        "<p><i>A test copy has been sent to editor@example.com, reviewer@example.com.</i></p>"
+    ));
+
+    // The confirmation email accounts for the first request
+    let requests = app.email_server.received_requests().await.unwrap();
+    let mut recipients = Vec::new();
+    for request in &requests[1..] {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["Subject"], "[TEST] Newsletter title");
+        recipients.push(body["To"].as_str().unwrap().to_owned());
+    }
+    assert_eq!(recipients, ["editor@example.com", "reviewer@example.com"]);
+}
+
+#[tokio::test]
+async fn a_test_copy_does_not_touch_the_delivery_queue_or_idempotency_records() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+    let body = test_copy_body("editor@example.com");
+
+    // Act - Sending twice with the same idempotency key sends twice
+    app.post_send_test_newsletter(&body).await;
+    app.post_send_test_newsletter(&body).await;
+
+    // Assert
+    assert_eq!(count_rows(&app, "newsletter_issues").await, 0);
+    assert_eq!(count_rows(&app, "issue_delivery_queue").await, 0);
+    assert_eq!(count_rows(&app, "idempotency").await, 0);
+}
+
+#[tokio::test]
+async fn invalid_test_recipients_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_newsletter(&test_copy_body("editor@example.com, <b>nope</b>"))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("&lt;b&gt;nope&lt;/b&gt; is not a valid subscriber email."));
+}
+
+#[tokio::test]
+async fn a_failed_test_copy_is_reported() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_send_test_newsletter(&test_copy_body("editor@example.com")).await;
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>Failed to send a test copy to editor@example.com.</i></p>"));
+}
+
+#[tokio::test]
+async fn a_test_copy_of_a_draft_uses_its_saved_content() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue_id = app
+        .create_draft(&serde_json::json!({
+            "title": "Draft title",
+            "text": "Draft body as plain text",
+            "html": "<p>Draft body as HTML</p>",
+        }))
+        .await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_draft(
+            issue_id,
+            &serde_json::json!({ "test_recipients": "editor@example.com" }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, &format!("/admin/drafts/{}/preview", issue_id));
+    let request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(body["Subject"], "[TEST] Draft title");
+    assert_eq!(body["HtmlBody"], "<p>Draft body as HTML</p>");
+    assert_eq!(app.get_issue_status(issue_id).await, "draft");
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_send_a_test_copy() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_send_test_newsletter(&test_copy_body("editor@example.com"))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn test_copies_with_unknown_merge_tags_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Hi {{ first_name }}",
+            "html": "<p>Newsletter body as HTML</p>",
+            "test_recipients": "editor@example.com",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("Unknown merge tags: {{ first_name }}."));
+}
+
+#[tokio::test]
+async fn merge_tags_of_the_layout_are_not_checked_again_for_test_copies() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_create_email_template(&serde_json::json!({
+        "name": "Personal",
+        "html_layout": "<header>Hi {{ profile.nickname }}</header>{{ content }}",
+        "text_layout": "Hi {{ profile.nickname }}\n{{ content }}",
+    }))
+    .await;
+    let email_template_id = app.get_email_template_id("Personal").await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let mut body = test_copy_body("editor@example.com");
+    body["email_template_id"] = email_template_id.to_string().into();
+    let response = app.post_send_test_newsletter(&body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("A test copy has been sent to editor@example.com."));
+}
+
+#[tokio::test]
+async fn test_copies_stay_within_the_send_rate_limit() {
+    // Arrange
+    let app = spawn_app_with(|c| c.email_client.max_emails_per_second = 1).await;
+    app.test_user.login(&app).await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let started_at = Instant::now();
+    let response = app
+        .post_send_test_newsletter(&test_copy_body("editor@example.com, reviewer@example.com"))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    assert!(started_at.elapsed() >= Duration::from_millis(900));
+}
@@ -0,0 +1,340 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, when_sending_an_email, TestApp};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn create_profile_field(app: &TestApp, body: serde_json::Value) {
+    let response = app.post_create_profile_field(&body).await;
+    assert_is_redirect_to(&response, "/admin/profile_fields");
+}
+
+async fn create_company_and_plan_fields(app: &TestApp) {
+    create_profile_field(
+        app,
+        serde_json::json!({ "key": "company", "label": "Company", "field_type": "string" }),
+    )
+    .await;
+    create_profile_field(
+        app,
+        serde_json::json!({
+            "key": "plan",
+            "label": "Plan",
+            "field_type": "enum",
+            "options": "free, pro",
+            "is_required": "on",
+        }),
+    )
+    .await;
+}
+
+async fn subscribe_and_confirm(app: &TestApp, body: serde_json::Value) {
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.post_subscriptions(serde_urlencoded::to_string(body).unwrap())
+        .await
+        .error_for_status()
+        .unwrap();
+    let email_request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    let confirmation_link = app.get_confirmation_links(&email_request).html;
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
+async fn get_profile_values(app: &TestApp, email: &str) -> Vec<(String, String)> {
+    sqlx::query!(
+        r#"
+        SELECT profile_fields.key, subscriber_profile_values.value
+        FROM subscriber_profile_values
+        JOIN profile_fields ON
+        profile_fields.profile_field_id = subscriber_profile_values.profile_field_id
+        JOIN subscriptions ON subscriptions.id = subscriber_profile_values.subscriber_id
+        WHERE subscriptions.email = $1
+        ORDER BY profile_fields.key
+        "#,
+        email
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|r| (r.key, r.value))
+    .collect()
+}
+
+async fn publish_issue(app: &TestApp, body: serde_json::Value) -> Vec<serde_json::Value> {
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let n_received = app.email_server.received_requests().await.unwrap().len();
+    let response = app.post_publish_newsletter(&body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    app.dispatch_all_pending_emails().await;
+    app.email_server.received_requests().await.unwrap()[n_received..]
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn admins_can_create_profile_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    create_company_and_plan_fields(&app).await;
+
+    // Assert
+    let html_page = app.get_profile_fields_html().await;
+    assert!(html_page.contains("<p><i>The profile field has been created.</i></p>"));
+    assert!(html_page.contains("<td>company</td>"));
+    assert!(html_page.contains("<td>plan</td>"));
+    assert!(html_page.contains("<td>free, pro</td>"));
+    assert!(html_page.contains("<td>Required</td>"));
+}
+
+#[tokio::test]
+async fn invalid_profile_fields_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"key": "Job Title", "label": "Job title", "field_type": "string"}),
+            "Field keys may only contain up to 64 lowercase letters, digits and underscores.",
+        ),
+        (
+            serde_json::json!({"key": "email", "label": "Email", "field_type": "string"}),
+            "email is already a field of the subscribe form.",
+        ),
+        (
+            serde_json::json!({"key": "job_title", "label": " ", "field_type": "string"}),
+            "The field needs a label.",
+        ),
+        (
+            serde_json::json!({"key": "job_title", "label": "Job title", "field_type": "colour"}),
+            "colour is not a known field type.",
+        ),
+        (
+            serde_json::json!({"key": "frequency", "label": "Frequency", "field_type": "enum"}),
+            "A choice field needs at least one option.",
+        ),
+        (
+            serde_json::json!({"key": "company", "label": "Employer", "field_type": "string"}),
+            "A field with this key already exists.",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_create_profile_field(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/profile_fields");
+        let html_page = app.get_profile_fields_html().await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {}",
+            error_message,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribe_stores_the_values_of_profile_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+
+    // Act
+    subscribe_and_confirm(
+        &app,
+        serde_json::json!({
+            "name": "le guin",
+            "email": "ursula@example.com",
+            "company": " Acme ",
+            "plan": "pro",
+            "unknown": "ignored",
+        }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(
+        get_profile_values(&app, "ursula@example.com").await,
+        vec![
+            ("company".to_string(), "Acme".to_string()),
+            ("plan".to_string(), "pro".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_400_when_profile_fields_are_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"name": "le guin", "email": "ursula@example.com"}),
+            "a missing required field",
+        ),
+        (
+            serde_json::json!({"name": "le guin", "email": "ursula@example.com", "plan": "gold"}),
+            "an unknown option",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app
+            .post_subscriptions(serde_urlencoded::to_string(&body).unwrap())
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not return a 400 Bad Request when the payload was {}.",
+            description
+        );
+    }
+    let saved = sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_optional(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn profile_merge_tags_are_rendered_for_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+    subscribe_and_confirm(
+        &app,
+        serde_json::json!({
+            "name": "le guin",
+            "email": "ursula@example.com",
+            "company": "Acme & Sons",
+            "plan": "pro",
+        }),
+    )
+    .await;
+
+    // Act
+    let emails = publish_issue(
+        &app,
+        serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Hello {{ profile.company }}",
+            "html": "<p>Hello {{ profile.company }}</p>",
+            "idempotency_key": Uuid::new_v4().to_string()
+        }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(emails.len(), 1);
+    assert!(emails[0]["TextBody"].as_str().unwrap().starts_with("Hello Acme & Sons"));
+    assert!(emails[0]["HtmlBody"].as_str().unwrap().starts_with("<p>Hello Acme &amp; Sons</p>"));
+}
+
+#[tokio::test]
+async fn issues_sent_to_a_segment_only_reach_subscribers_with_a_matching_profile() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+    for (email, plan) in [("ursula@example.com", "pro"), ("octavia@example.com", "free")] {
+        subscribe_and_confirm(
+            &app,
+            serde_json::json!({ "name": "reader", "email": email, "plan": plan }),
+        )
+        .await;
+    }
+    let response = app
+        .post_create_segment(&serde_json::json!({
+            "name": "Pro readers",
+            "profile_field_key": "plan",
+            "profile_field_value": "pro",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/segments");
+    assert!(app.get_segments_html().await.contains("<td>Confirmed members whose plan is pro</td>"));
+    let segment_id = sqlx::query!("SELECT segment_id FROM segments WHERE name = 'Pro readers'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .segment_id;
+
+    // Act
+    let emails = publish_issue(
+        &app,
+        serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "segment_id": segment_id.to_string(),
+            "idempotency_key": Uuid::new_v4().to_string()
+        }),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["To"], "ursula@example.com");
+}
+
+#[tokio::test]
+async fn segments_reject_values_that_do_not_fit_the_profile_field() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_company_and_plan_fields(&app).await;
+
+    // Act
+    let response = app
+        .post_create_segment(&serde_json::json!({
+            "name": "Gold readers",
+            "profile_field_key": "plan",
+            "profile_field_value": "gold",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/segments");
+    let html_page = app.get_segments_html().await;
+    assert!(html_page.contains("<p><i>gold is not a valid value for Plan.</i></p>"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_profile_fields() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_create_profile_field(&serde_json::json!({
+            "key": "company",
+            "label": "Company",
+            "field_type": "string",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_profile_fields().await, "/login");
+}
@@ -0,0 +1,362 @@
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber, create_unconfirmed_subscriber, spawn_app,
+    when_sending_an_email, TestApp,
+};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn get_subscriber_emails(app: &TestApp) -> Vec<String> {
+    sqlx::query!("SELECT email FROM subscriptions ORDER BY email")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.email)
+        .collect()
+}
+
+async fn tag(app: &TestApp, email: &str, tag: &str) {
+    let response = app
+        .post_add_tag(&serde_json::json!({ "email": email, "tag": tag }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/tags");
+}
+
+async fn create_segment(app: &TestApp, body: serde_json::Value) -> Uuid {
+    let name = body["name"].as_str().unwrap().to_owned();
+    let response = app.post_create_segment(&body).await;
+    assert_is_redirect_to(&response, "/admin/segments");
+    sqlx::query!("SELECT segment_id FROM segments WHERE name = $1", name)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .segment_id
+}
+
+async fn deliver_issue_to_segment(app: &TestApp, segment_id: Uuid) -> Vec<String> {
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let n_received = app.email_server.received_requests().await.unwrap().len();
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "segment_id": segment_id.to_string(),
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    app.dispatch_all_pending_emails().await;
+
+    let mut recipients: Vec<String> = app.email_server.received_requests().await.unwrap()
+        [n_received..]
+        .iter()
+        .map(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            body["To"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    recipients.sort();
+    recipients
+}
+
+#[tokio::test]
+async fn admins_can_tag_and_untag_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let email = get_subscriber_emails(&app).await.remove(0);
+
+    // Act - Part 1 - Tag
+    let response = app
+        .post_add_tag(&serde_json::json!({ "email": email, "tag": " Beta " }))
+        .await;
+
+    // Assert - Part 1
+    assert_is_redirect_to(&response, "/admin/tags");
+    let html_page = app.get_tags_html().await;
+    assert!(html_page.contains(&format!("<p><i>{} has been tagged beta.</i></p>", email)));
+    assert!(html_page.contains("<td>beta</td>"));
+
+    // Act - Part 2 - Untag
+    let response = app
+        .post_remove_tag(&serde_json::json!({ "email": email, "tag": "beta" }))
+        .await;
+
+    // Assert - Part 2
+    assert_is_redirect_to(&response, "/admin/tags");
+    let html_page = app.get_tags_html().await;
+    assert!(html_page.contains(&format!("<p><i>{} is no longer tagged beta.</i></p>", email)));
+    assert!(!html_page.contains("<td>beta</td>"));
+}
+
+#[tokio::test]
+async fn invalid_tags_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let email = get_subscriber_emails(&app).await.remove(0);
+    tag(&app, &email, "beta").await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"email": email, "tag": "early adopters"}),
+            "early adopters is not a valid tag.".to_string(),
+        ),
+        (
+            serde_json::json!({"email": email, "tag": "beta"}),
+            format!("{} is already tagged beta.", email),
+        ),
+        (
+            serde_json::json!({"email": "nobody@example.com", "tag": "beta"}),
+            "nobody@example.com is not a subscriber.".to_string(),
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_add_tag(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/tags");
+        let html_page = app.get_tags_html().await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {}",
+            error_message,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn admins_can_create_segments() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    create_segment(
+        &app,
+        serde_json::json!({
+            "name": "Recent beta testers",
+            "required_tags": "beta",
+            "excluded_tags": "churned, staff",
+            "subscribed_after": "2023-06-01",
+        }),
+    )
+    .await;
+
+    // Assert
+    let html_page = app.get_segments_html().await;
+    assert!(html_page.contains("<p><i>The segment has been created.</i></p>"));
+    assert!(html_page.contains("<td>Recent beta testers</td>"));
+    assert!(html_page.contains(
+        "<td>Confirmed members tagged beta, not tagged churned or staff, \
+        joined on or after 2023-06-01</td>"
+    ));
+    let publish_page = app.get_publish_newsletter_html().await;
+    assert!(publish_page.contains("Recent beta testers</option>"));
+}
+
+#[tokio::test]
+async fn invalid_segments_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    create_segment(&app, serde_json::json!({ "name": "Beta", "required_tags": "beta" })).await;
+    let test_cases = vec![
+        (
+            serde_json::json!({"name": " ", "required_tags": "beta"}),
+            "The segment needs a name.",
+        ),
+        (
+            serde_json::json!({"name": "VIP", "required_tags": "very important"}),
+            "very important is not a valid tag.",
+        ),
+        (
+            serde_json::json!({"name": "VIP", "subscribed_after": "last year"}),
+            "last year is not a valid date.",
+        ),
+        (
+            serde_json::json!({
+                "name": "VIP",
+                "subscribed_after": "2023-06-01",
+                "subscribed_before": "2023-01-01"
+            }),
+            "The date range of the segment is empty.",
+        ),
+        (
+            serde_json::json!({"name": "VIP", "subscriber_status": "bounced"}),
+            "bounced is not a status a segment can pick.",
+        ),
+        (
+            serde_json::json!({"name": "Beta", "required_tags": "beta"}),
+            "A segment with this name already exists.",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_create_segment(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/segments");
+        let html_page = app.get_segments_html().await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {}",
+            error_message,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn issues_sent_to_a_segment_only_reach_subscribers_with_matching_tags() {
+    // Arrange
+    let app = spawn_app().await;
+    for _ in 0..3 {
+        create_confirmed_subscriber(&app).await;
+    }
+    app.test_user.login(&app).await;
+    let emails = get_subscriber_emails(&app).await;
+    tag(&app, &emails[0], "beta").await;
+    tag(&app, &emails[1], "beta").await;
+    tag(&app, &emails[1], "churned").await;
+    let segment_id = create_segment(
+        &app,
+        serde_json::json!({
+            "name": "Active beta testers",
+            "required_tags": "beta",
+            "excluded_tags": "churned",
+        }),
+    )
+    .await;
+
+    // Act
+    let recipients = deliver_issue_to_segment(&app, segment_id).await;
+
+    // Assert
+    assert_eq!(recipients, vec![emails[0].clone()]);
+}
+
+#[tokio::test]
+async fn issues_sent_to_a_segment_only_reach_subscribers_who_joined_in_its_date_range() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let emails = get_subscriber_emails(&app).await;
+    sqlx::query!(
+        r#"
+        UPDATE list_subscriptions SET subscribed_at = '2020-05-01T00:00:00Z'
+        WHERE subscriber_id = (SELECT id FROM subscriptions WHERE email = $1)
+        "#,
+        emails[0]
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let early = create_segment(
+        &app,
+        serde_json::json!({ "name": "Early readers", "subscribed_before": "2021-01-01" }),
+    )
+    .await;
+    let recent = create_segment(
+        &app,
+        serde_json::json!({ "name": "Recent readers", "subscribed_after": "2021-01-01" }),
+    )
+    .await;
+
+    // Act
+    let early_recipients = deliver_issue_to_segment(&app, early).await;
+    let recent_recipients = deliver_issue_to_segment(&app, recent).await;
+
+    // Assert
+    assert_eq!(early_recipients, vec![emails[0].clone()]);
+    assert_eq!(recent_recipients, vec![emails[1].clone()]);
+}
+
+#[tokio::test]
+async fn issues_sent_to_a_segment_of_pending_members_only_reach_unconfirmed_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_unconfirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let pending_email = sqlx::query!(
+        "SELECT email FROM subscriptions WHERE status = 'pending_confirmation'"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+    .email;
+    let segment_id = create_segment(
+        &app,
+        serde_json::json!({
+            "name": "Not confirmed yet",
+            "subscriber_status": "pending_confirmation",
+        }),
+    )
+    .await;
+
+    // Act
+    let recipients = deliver_issue_to_segment(&app, segment_id).await;
+
+    // Assert
+    assert_eq!(recipients, vec![pending_email]);
+    assert!(app
+        .get_segments_html()
+        .await
+        .contains("<td>Every member pending confirmation</td>"));
+}
+
+#[tokio::test]
+async fn publishing_to_an_unknown_segment_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "segment_id": Uuid::new_v4().to_string(),
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The selected segment no longer exists.</i></p>"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_segments_and_tags() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let segment_response = app
+        .post_create_segment(&serde_json::json!({ "name": "Beta", "required_tags": "beta" }))
+        .await;
+    let tag_response = app
+        .post_add_tag(&serde_json::json!({ "email": "ursula@example.com", "tag": "beta" }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&segment_response, "/login");
+    assert_is_redirect_to(&tag_response, "/login");
+    assert_is_redirect_to(&app.get_segments().await, "/login");
+    assert_is_redirect_to(&app.get_tags().await, "/login");
+}
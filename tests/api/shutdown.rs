@@ -0,0 +1,47 @@
+use crate::helpers::{spawn_app, when_sending_an_email};
+
+use wiremock::ResponseTemplate;
+
+use std::time::Duration;
+
+#[tokio::test]
+async fn in_flight_requests_complete_after_shutdown_is_triggered() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    when_sending_an_email()
+        // Delay keeps the request in flight while shutdown is triggered
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let in_flight_request = app.post_subscriptions(body.into());
+    let trigger_shutdown = async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        app.shutdown.trigger();
+    };
+    let (response, _) = tokio::join!(in_flight_request, trigger_shutdown);
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn new_connections_are_refused_after_shutdown() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.shutdown.trigger();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Assert
+    let outcome = reqwest::Client::new()
+        .get(format!("{}/health_check", &app.address))
+        .send()
+        .await;
+    assert!(outcome.is_err());
+}
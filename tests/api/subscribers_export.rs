@@ -0,0 +1,148 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+
+async fn import_subscribers(app: &TestApp, csv: &str, pre_confirmed: bool) {
+    let response = app.post_import_subscribers(csv, pre_confirmed).await;
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+}
+
+#[tokio::test]
+async fn subscribers_are_exported_as_csv() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    import_subscribers(&app, "email,name\nursula@example.com,\"Le Guin, Ursula\"\n", true).await;
+    let response = app
+        .post_add_tag(&serde_json::json!({ "email": "ursula@example.com", "tag": "beta" }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/tags");
+    let id = sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .id;
+
+    // Act
+    let response = app.get_export_subscribers("").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "text/csv; charset=utf-8");
+    assert!(response.headers()["Content-Disposition"]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment; filename=\"subscribers-"));
+    let body = response.text().await.unwrap();
+    let lines: Vec<&str> = body.split("\r\n").collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "id,email,name,status,subscribed_at,lists,tags");
+    assert!(lines[1].starts_with(&format!(
+        "{},\"ursula@example.com\",\"Le Guin, Ursula\",confirmed,",
+        id
+    )));
+    assert!(lines[1].ends_with(",\"newsletter\",\"beta\""));
+    assert_eq!(lines[2], "");
+}
+
+#[tokio::test]
+async fn subscribers_are_exported_as_newline_delimited_json() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\nursula@example.com,Ursula\noctavia@example.com,Octavia\n";
+    import_subscribers(&app, csv, true).await;
+
+    // Act
+    let response = app.get_export_subscribers("format=ndjson").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "application/x-ndjson");
+    let body = response.text().await.unwrap();
+    let subscribers: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(subscribers.len(), 2);
+    let mut emails: Vec<&str> = subscribers.iter().map(|s| s["email"].as_str().unwrap()).collect();
+    emails.sort();
+    assert_eq!(emails, vec!["octavia@example.com", "ursula@example.com"]);
+    assert_eq!(subscribers[0]["status"], "confirmed");
+    assert_eq!(subscribers[0]["lists"], serde_json::json!(["newsletter"]));
+    assert_eq!(subscribers[0]["tags"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn exports_can_be_filtered_by_status_and_subscription_date() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    import_subscribers(&app, "email,name\nursula@example.com,Ursula\n", true).await;
+    import_subscribers(&app, "email,name\noctavia@example.com,Octavia\n", false).await;
+    import_subscribers(&app, "email,name\nnk@example.com,N. K. Jemisin\n", true).await;
+    sqlx::query!(
+        "UPDATE subscriptions SET subscribed_at = '2023-06-15T12:00:00Z' WHERE email = 'nk@example.com'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let test_cases = vec![
+        ("status=pending_confirmation", vec!["octavia@example.com"]),
+        ("status=confirmed", vec!["nk@example.com", "ursula@example.com"]),
+        ("subscribed_before=2023-07-01", vec!["nk@example.com"]),
+        ("subscribed_after=2023-06-15&subscribed_before=2023-06-16", vec!["nk@example.com"]),
+        ("status=confirmed&subscribed_after=2023-07-01", vec!["ursula@example.com"]),
+    ];
+
+    for (query, expected_emails) in test_cases {
+        // Act
+        let response = app.get_export_subscribers(&format!("format=ndjson&{}", query)).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+        let mut emails: Vec<String> = response
+            .text()
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let subscriber: serde_json::Value = serde_json::from_str(line).unwrap();
+                subscriber["email"].as_str().unwrap().to_owned()
+            })
+            .collect();
+        emails.sort();
+        assert_eq!(emails, expected_emails, "Unexpected export for {}", query);
+    }
+}
+
+#[tokio::test]
+async fn invalid_export_parameters_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = vec![
+        ("format=xlsx", "xlsx is not an export format, use csv or ndjson."),
+        ("status=active", "active is not a subscriber status."),
+        ("subscribed_after=yesterday", "yesterday is not a valid date."),
+    ];
+
+    for (query, error_message) in test_cases {
+        // Act
+        let response = app.get_export_subscribers(query).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400, "Expected a 400 for {}", query);
+        assert_eq!(response.text().await.unwrap(), error_message);
+    }
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_export_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_export_subscribers("").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
@@ -0,0 +1,273 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, when_sending_an_email, TestApp};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn get_statuses(app: &TestApp) -> Vec<(String, String, String)> {
+    sqlx::query!(
+        r#"
+        SELECT subscriptions.email, subscriptions.status, list_subscriptions.status AS list_status
+        FROM subscriptions
+        JOIN list_subscriptions ON list_subscriptions.subscriber_id = subscriptions.id
+        ORDER BY subscriptions.email
+        "#
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|r| (r.email, r.status, r.list_status))
+    .collect()
+}
+
+async fn get_last_import_id(app: &TestApp) -> String {
+    sqlx::query!("SELECT import_id FROM subscriber_imports ORDER BY imported_at DESC LIMIT 1")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .import_id
+        .to_string()
+}
+
+#[tokio::test]
+async fn pre_confirmed_subscribers_receive_the_next_issue() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\nursula@example.com,Ursula Le Guin\noctavia@example.com,Octavia Butler\n";
+
+    // Act
+    let response = app.post_import_subscribers(csv, true).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+    let html_page = app.get_import_subscribers_html().await;
+    assert!(html_page.contains("<p><i>Imported 2 of 2 rows.</i></p>"));
+    assert!(html_page.contains("<td>subscribers.csv</td>"));
+    assert_eq!(
+        get_statuses(&app).await,
+        vec![
+            ("octavia@example.com".into(), "confirmed".into(), "confirmed".into()),
+            ("ursula@example.com".into(), "confirmed".into(), "confirmed".into()),
+        ]
+    );
+
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn imported_subscribers_are_sent_a_confirmation_email_by_the_worker() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "name,email\r\nUrsula Le Guin,ursula@example.com\r\n";
+
+    // Act - Part 1 - Import
+    let response = app.post_import_subscribers(csv, false).await;
+
+    // Assert - Part 1
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+    assert!(app.email_server.received_requests().await.unwrap().is_empty());
+    assert_eq!(
+        get_statuses(&app).await,
+        vec![(
+            "ursula@example.com".into(),
+            "pending_confirmation".into(),
+            "pending_confirmation".into()
+        )]
+    );
+
+    // Act - Part 2 - Let the worker send the confirmation email
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+    let email_request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    let confirmation_link = app.get_confirmation_links(&email_request).html;
+    reqwest::get(confirmation_link)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Assert - Part 2
+    assert_eq!(
+        get_statuses(&app).await,
+        vec![("ursula@example.com".into(), "confirmed".into(), "confirmed".into())]
+    );
+}
+
+#[tokio::test]
+async fn skipped_rows_are_listed_in_a_downloadable_report() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_import_subscribers("email,name\nexisting@example.com,Existing\n", true)
+        .await;
+    let response = app
+        .post_add_suppression(&serde_json::json!({ "email": "bounced@example.com", "reason": "Hard bounce" }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let csv = "email,name,company\n\
+        ursula@example.com,Ursula Le Guin,Acme\n\
+        \"=cmd,not-an-email\",Mallory,\n\
+        existing@example.com,Existing,\n\
+        octavia@example.com,,\n\
+        bounced@example.com,Bounced,\n\
+        ursula@example.com,Ursula again,\n\
+        \n";
+
+    // Act
+    let response = app.post_import_subscribers(csv, true).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+    let html_page = app.get_import_subscribers_html().await;
+    assert!(html_page.contains(
+        "<p><i>Imported 1 of 6 rows. 5 rows were skipped, download the report for details.</i></p>"
+    ));
+    let import_id = get_last_import_id(&app).await;
+    assert!(html_page.contains(&format!("/admin/subscribers/import/{}/report", import_id)));
+
+    let response = app.get_import_report(&import_id).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "text/csv; charset=utf-8");
+    assert!(response.headers()["Content-Disposition"]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment;"));
+    assert_eq!(
+        response.text().await.unwrap(),
+        "row,email,error\r\n\
+        3,\"'=cmd,not-an-email\",\"'=cmd,not-an-email is not a valid subscriber email.\"\r\n\
+        4,\"existing@example.com\",\"existing@example.com is already on this list.\"\r\n\
+        5,\"octavia@example.com\",\" is not a valid subscriber name.\"\r\n\
+        6,\"bounced@example.com\",\"bounced@example.com is on the suppression list.\"\r\n\
+        7,\"ursula@example.com\",\"ursula@example.com appears earlier in the file.\"\r\n"
+    );
+}
+
+#[tokio::test]
+async fn existing_subscribers_are_added_to_the_list_they_are_imported_into() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_import_subscribers("email,name\nursula@example.com,Ursula Le Guin\n", true)
+        .await;
+    app.post_create_list(&serde_json::json!({ "name": "weekly", "title": "The weekly list" }))
+        .await;
+    let list_id = sqlx::query!("SELECT list_id FROM lists WHERE name = 'weekly'")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .list_id;
+
+    // Act
+    let response = app
+        .post_import_subscribers_to_list(
+            "email,name\nUrsula@Example.com,Ursula Le Guin\n",
+            &list_id.to_string(),
+            true,
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+    let html_page = app.get_import_subscribers_html().await;
+    assert!(html_page.contains("<p><i>Imported 1 of 1 rows.</i></p>"));
+    assert_eq!(
+        get_statuses(&app).await,
+        vec![
+            ("ursula@example.com".into(), "confirmed".into(), "confirmed".into()),
+            ("ursula@example.com".into(), "confirmed".into(), "confirmed".into()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn duplicate_addresses_are_detected_whatever_their_case() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\nursula@example.com,Ursula Le Guin\nURSULA@example.com,Ursula again\n";
+
+    // Act
+    app.post_import_subscribers(csv, true).await;
+
+    // Assert
+    let html_page = app.get_import_subscribers_html().await;
+    assert!(html_page.contains(
+        "<p><i>Imported 1 of 2 rows. 1 rows were skipped, download the report for details.</i></p>"
+    ));
+    let report = app.get_import_report(&get_last_import_id(&app).await).await;
+    assert!(report
+        .text()
+        .await
+        .unwrap()
+        .contains("URSULA@example.com appears earlier in the file."));
+}
+
+#[tokio::test]
+async fn files_without_an_email_and_a_name_column_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_import_subscribers("address,full name\nursula@example.com,Ursula\n", true)
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers/import");
+    let html_page = app.get_import_subscribers_html().await;
+    assert!(html_page.contains(
+        "<p><i>The first row of the file must name an email and a name column.</i></p>"
+    ));
+    assert!(get_statuses(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn reports_of_unknown_imports_are_not_found() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_import_report(&Uuid::new_v4().to_string()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_import_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_import_subscribers("email,name\nursula@example.com,Ursula\n", true)
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_import_subscribers().await, "/login");
+    assert!(get_statuses(&app).await.is_empty());
+}
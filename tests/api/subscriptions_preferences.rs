@@ -0,0 +1,340 @@
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber, spawn_app, when_sending_an_email, TestApp,
+};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+struct Subscriber {
+    email: String,
+    name: String,
+    unsubscribe_token: String,
+}
+
+async fn get_subscriber(app: &TestApp) -> Subscriber {
+    sqlx::query_as!(
+        Subscriber,
+        r#"
+        SELECT subscriptions.email, subscriptions.name, unsubscribe_tokens.unsubscribe_token
+        FROM subscriptions
+        JOIN unsubscribe_tokens ON unsubscribe_tokens.subscriber_id = subscriptions.id
+        "#,
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+}
+
+async fn get_statuses(app: &TestApp) -> (String, Vec<(String, String)>) {
+    let status = sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .status;
+    let list_statuses = sqlx::query!(
+        r#"
+        SELECT lists.name, list_subscriptions.status
+        FROM list_subscriptions
+        JOIN lists ON lists.list_id = list_subscriptions.list_id
+        ORDER BY lists.name
+        "#
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|r| (r.name, r.status))
+    .collect();
+    (status, list_statuses)
+}
+
+fn preferences(subscriber: &Subscriber) -> serde_json::Value {
+    serde_json::json!({
+        "name": subscriber.name,
+        "email": subscriber.email,
+        "list.newsletter": "on",
+    })
+}
+
+fn preferences_path(subscriber: &Subscriber) -> String {
+    format!(
+        "/subscriptions/preferences?unsubscribe_token={}",
+        subscriber.unsubscribe_token
+    )
+}
+
+async fn deliver_issue(app: &TestApp) -> Vec<serde_json::Value> {
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let n_received = app.email_server.received_requests().await.unwrap().len();
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    app.dispatch_all_pending_emails().await;
+    app.email_server.received_requests().await.unwrap()[n_received..]
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn every_issue_links_to_the_preference_center() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let subscriber = get_subscriber(&app).await;
+
+    // Act
+    let emails = deliver_issue(&app).await;
+
+    // Assert
+    let preferences_link = format!("{}{}", app.address, preferences_path(&subscriber));
+    assert!(emails[0]["TextBody"].as_str().unwrap().contains(&preferences_link));
+    assert!(emails[0]["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("<a href=\"{}\">Manage your preferences</a>", preferences_link)));
+}
+
+#[tokio::test]
+async fn the_preference_center_requires_a_valid_token() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+
+    // Act
+    let get_response = app.get_preferences("not-a-token").await;
+    let post_response = app.post_preferences("not-a-token", &preferences(&subscriber)).await;
+
+    // Assert
+    assert_eq!(get_response.status().as_u16(), 401);
+    assert_eq!(post_response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn the_preference_center_shows_the_current_preferences() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+
+    // Act
+    let html_page = app.get_preferences_html(&subscriber.unsubscribe_token).await;
+
+    // Assert
+    assert!(html_page.contains(&format!(
+        r#"name="email" value="{}""#,
+        htmlescape::encode_attribute(&subscriber.email)
+    )));
+    assert!(html_page.contains(r#"name="list.newsletter" checked"#));
+}
+
+#[tokio::test]
+async fn subscribers_can_change_their_name() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+    let mut body = preferences(&subscriber);
+    body["name"] = "Ursula K. Le Guin".into();
+
+    // Act
+    let response = app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, &preferences_path(&subscriber));
+    let html_page = app.get_preferences_html(&subscriber.unsubscribe_token).await;
+    assert!(html_page.contains("<p><i>Your preferences have been saved.</i></p>"));
+    assert_eq!(get_subscriber(&app).await.name, "Ursula K. Le Guin");
+}
+
+#[tokio::test]
+async fn invalid_preferences_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+    let test_cases = vec![
+        ("name", "", " is not a valid subscriber name."),
+        ("email", "not-an-email", "not-an-email is not a valid subscriber email."),
+        ("pause_weeks", "60", "Delivery can be paused for 0 to 52 weeks."),
+    ];
+
+    for (field, value, error_message) in test_cases {
+        let mut body = preferences(&subscriber);
+        body[field] = value.into();
+
+        // Act
+        let response = app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, &preferences_path(&subscriber));
+        let html_page = app.get_preferences_html(&subscriber.unsubscribe_token).await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {} = {}",
+            error_message,
+            field,
+            value
+        );
+    }
+    assert_eq!(get_subscriber(&app).await.name, subscriber.name);
+}
+
+#[tokio::test]
+async fn subscribers_can_pick_their_lists() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let response = app
+        .post_create_list(&serde_json::json!({ "name": "weekly", "title": "Weekly" }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/lists");
+    let subscriber = get_subscriber(&app).await;
+    let mut body = preferences(&subscriber);
+
+    // Act - Part 1 - Swap the default list for another one
+    body.as_object_mut().unwrap().remove("list.newsletter");
+    body["list.weekly"] = "on".into();
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 1
+    assert_eq!(
+        get_statuses(&app).await,
+        (
+            "confirmed".to_string(),
+            vec![
+                ("newsletter".to_string(), "unsubscribed".to_string()),
+                ("weekly".to_string(), "confirmed".to_string()),
+            ]
+        )
+    );
+    assert!(deliver_issue(&app).await.is_empty());
+
+    // Act - Part 2 - Leave every list
+    body.as_object_mut().unwrap().remove("list.weekly");
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 2
+    assert_eq!(get_statuses(&app).await.0, "unsubscribed");
+}
+
+#[tokio::test]
+async fn paused_subscribers_do_not_receive_issues_until_they_resume() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let subscriber = get_subscriber(&app).await;
+    let mut body = preferences(&subscriber);
+
+    // Act - Part 1 - Pause
+    body["pause_weeks"] = "2".into();
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 1
+    assert!(deliver_issue(&app).await.is_empty());
+    let html_page = app.get_preferences_html(&subscriber.unsubscribe_token).await;
+    assert!(html_page.contains("Delivery is paused until"));
+
+    // Act - Part 2 - Saving other preferences keeps the pause
+    body["pause_weeks"] = "".into();
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 2
+    assert!(deliver_issue(&app).await.is_empty());
+
+    // Act - Part 3 - Resume
+    body["pause_weeks"] = "0".into();
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 3
+    assert_eq!(deliver_issue(&app).await.len(), 1);
+}
+
+#[tokio::test]
+async fn email_changes_take_effect_once_the_new_address_is_confirmed() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+    let mut body = preferences(&subscriber);
+    body["email"] = "ursula@example.com".into();
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Request the change
+    let response = app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+
+    // Assert - Part 1
+    assert_is_redirect_to(&response, &preferences_path(&subscriber));
+    let html_page = app.get_preferences_html(&subscriber.unsubscribe_token).await;
+    assert!(html_page.contains(
+        "Open the link we sent to ursula@example.com to confirm your new address."
+    ));
+    assert_eq!(get_subscriber(&app).await.email, subscriber.email);
+    let email_request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    let email: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(email["To"], "ursula@example.com");
+
+    // Act - Part 2 - Confirm it
+    let confirmation_link = app.get_confirmation_links(&email_request).html;
+    let response = reqwest::get(confirmation_link.clone()).await.unwrap();
+
+    // Assert - Part 2
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(get_subscriber(&app).await.email, "ursula@example.com");
+    // The link only works once
+    let response = reqwest::get(confirmation_link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn email_changes_to_an_address_that_is_already_subscribed_are_not_applied() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber = get_subscriber(&app).await;
+    let mut body = preferences(&subscriber);
+    body["email"] = "ursula@example.com".into();
+    let _mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.post_preferences(&subscriber.unsubscribe_token, &body).await;
+    let email_request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    let confirmation_link = app.get_confirmation_links(&email_request).html;
+    app.post_subscriptions("name=le%20guin&email=ursula%40example.com".into())
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Act
+    let response = reqwest::get(confirmation_link).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 409);
+    let email = sqlx::query!(
+        "SELECT email FROM subscriptions WHERE name = $1",
+        subscriber.name
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap()
+    .email;
+    assert_eq!(email, subscriber.email);
+}
@@ -0,0 +1,230 @@
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber, spawn_app, when_sending_an_email, TestApp,
+};
+
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+async fn subscriber_email(app: &TestApp) -> String {
+    sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .email
+}
+
+#[tokio::test]
+async fn admins_can_add_and_remove_suppressed_addresses() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Add
+    let response = app
+        .post_add_suppression(&serde_json::json!({
+            "email": "ursula@example.com",
+            "reason": "Asked to be forgotten",
+        }))
+        .await;
+
+    // Assert - Part 1
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let html_page = app.get_suppressions_html().await;
+    assert!(html_page
+        .contains("<p><i>ursula@example.com has been added to the suppression list.</i></p>"));
+    assert!(html_page.contains("<td>Asked to be forgotten</td>"));
+    assert!(html_page.contains("<td>admin</td>"));
+
+    // Act - Part 2 - Remove
+    let response = app
+        .post_remove_suppression(&serde_json::json!({ "email": "ursula@example.com" }))
+        .await;
+
+    // Assert - Part 2
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let html_page = app.get_suppressions_html().await;
+    assert!(html_page
+        .contains("<p><i>ursula@example.com has been removed from the suppression list.</i></p>"));
+    assert!(!html_page.contains("<td>ursula@example.com</td>"));
+}
+
+#[tokio::test]
+async fn invalid_suppressions_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "ursula@example.com",
+        "reason": "Asked to be forgotten",
+    }))
+    .await;
+
+    let test_cases = vec![
+        (
+            serde_json::json!({"email": "not-an-email", "reason": "Typo"}),
+            "not-an-email is not a valid subscriber email.",
+        ),
+        (
+            serde_json::json!({"email": "le_guin@example.com", "reason": " "}),
+            "Give a reason for suppressing the address.",
+        ),
+        (
+            serde_json::json!({"email": "URSULA@example.com", "reason": "Again"}),
+            "URSULA@example.com is already on the suppression list.",
+        ),
+    ];
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_add_suppression(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/suppressions");
+        let html_page = app.get_suppressions_html().await;
+        assert!(
+            html_page.contains(&format!("<p><i>{}</i></p>", error_message)),
+            "Expected '{}' for {}",
+            error_message,
+            body
+        );
+    }
+}
+
+#[tokio::test]
+async fn suppressed_subscribers_are_not_sent_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let email = subscriber_email(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": email.to_uppercase(),
+        "reason": "Asked to be forgotten",
+    }))
+    .await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_publish_newsletter(&serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    }))
+    .await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let outcome = sqlx::query!("SELECT outcome FROM issue_deliveries")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .outcome;
+    assert_eq!(outcome, "skipped");
+}
+
+#[tokio::test]
+async fn suppressed_addresses_are_not_sent_a_confirmation_email_when_subscribing_again() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "Ursula_Le_Guin@gmail.com",
+        "reason": "Hard bounce reported by support",
+    }))
+    .await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn hard_bounces_are_added_to_the_suppression_list() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act
+    app.post_authenticated_email_event(&serde_json::json!({
+        "RecordType": "Bounce",
+        "Type": "HardBounce",
+        "Email": email,
+    }))
+    .await;
+
+    // Assert
+    let suppressed = sqlx::query!("SELECT email, reason, source FROM suppressed_emails")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(suppressed.email, email.to_lowercase());
+    assert_eq!(suppressed.reason, "bounced");
+    assert_eq!(suppressed.source, "webhook");
+}
+
+#[tokio::test]
+async fn test_copies_are_not_sent_to_suppressed_addresses() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_add_suppression(&serde_json::json!({
+        "email": "ursula@example.com",
+        "reason": "Asked to be forgotten",
+    }))
+    .await;
+
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_send_test_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+            "test_recipients": "editor@example.com, ursula@example.com",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>ursula@example.com is on the suppression list.</i></p>"));
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_the_suppression_list() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_add_suppression(&serde_json::json!({
+            "email": "ursula@example.com",
+            "reason": "Asked to be forgotten",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_is_redirect_to(&app.get_suppressions().await, "/login");
+}
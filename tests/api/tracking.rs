@@ -0,0 +1,305 @@
+use crate::helpers::{
+    spawn_app, spawn_app_with_tracking, create_confirmed_subscriber, when_sending_an_email,
+    TestApp,
+};
+
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+
+async fn publish_and_deliver(app: &TestApp) -> (Uuid, String) {
+    publish_and_deliver_with_tracking(app, true).await
+}
+
+async fn publish_and_deliver_with_tracking(app: &TestApp, is_tracked: bool) -> (Uuid, String) {
+    create_confirmed_subscriber(app).await;
+    app.test_user.login(app).await;
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let mut body = serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Read more at https://www.rust-lang.org",
+        "html": r#"<p>Read more at <a href="https://www.rust-lang.org/learn?a=1&amp;b=2">Rust</a></p>"#,
+        "idempotency_key": Uuid::new_v4().to_string()
+    });
+    // Checkbox, only submitted when ticked
+    if is_tracked {
+        body["is_tracked"] = "on".into();
+    }
+    app.post_publish_newsletter(&body).await;
+    app.dispatch_all_pending_emails().await;
+
+    let issue_id = sqlx::query!("SELECT newsletter_issue_id FROM newsletter_issues")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .newsletter_issue_id;
+    // The confirmation email accounts for the first request
+    let request = &app.email_server.received_requests().await.unwrap()[1];
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    (issue_id, body["HtmlBody"].as_str().unwrap().to_owned())
+}
+
+// Path of the first tracking url of the given kind, e.g. /t/c/<token>
+fn tracking_path(html: &str, kind: &str) -> String {
+    let prefix = format!("/t/{}/", kind);
+    let start = html.find(&prefix).unwrap();
+    html[start..start + prefix.len() + 25].to_owned()
+}
+
+#[tokio::test]
+async fn tracked_issues_get_an_open_pixel_and_rewritten_links() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+
+    // Act
+    let (_, html_body) = publish_and_deliver(&app).await;
+
+    // Assert
+    assert!(!html_body.contains("https://www.rust-lang.org"));
+    let click_path = tracking_path(&html_body, "c");
+    assert!(html_body.contains(&format!(r#"<a href="{}{}">Rust</a>"#, app.address, click_path)));
+    let open_path = tracking_path(&html_body, "o");
+    assert!(html_body.contains(&format!(r#"<img src="{}{}""#, app.address, open_path)));
+    // Unsubscribing never goes through the click endpoint
+    assert!(html_body.contains("/subscriptions/unsubscribe?unsubscribe_token="));
+}
+
+#[tokio::test]
+async fn issues_are_not_tracked_unless_enabled() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let (_, html_body) = publish_and_deliver(&app).await;
+
+    // Assert
+    assert!(html_body.contains(r#"<a href="https://www.rust-lang.org/learn?a=1&amp;b=2">Rust</a>"#));
+    assert!(!html_body.contains("/t/o/"));
+    assert!(!html_body.contains("/t/c/"));
+}
+
+#[tokio::test]
+async fn issues_are_not_tracked_unless_they_opt_in() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+
+    // Act
+    let (_, html_body) = publish_and_deliver_with_tracking(&app, false).await;
+
+    // Assert
+    assert!(html_body.contains(r#"<a href="https://www.rust-lang.org/learn?a=1&amp;b=2">Rust</a>"#));
+    assert!(!html_body.contains("/t/o/"));
+    assert!(!html_body.contains("/t/c/"));
+}
+
+#[tokio::test]
+async fn the_tracking_option_is_only_offered_when_tracking_is_enabled() {
+    // Arrange
+    let enabled_app = spawn_app_with_tracking().await;
+    enabled_app.test_user.login(&enabled_app).await;
+    let disabled_app = spawn_app().await;
+    disabled_app.test_user.login(&disabled_app).await;
+
+    // Act
+    let enabled_page = enabled_app.get_publish_newsletter_html().await;
+    let disabled_page = disabled_app.get_publish_newsletter_html().await;
+
+    // Assert
+    assert!(enabled_page.contains(r#"name="is_tracked""#));
+    assert!(!disabled_page.contains(r#"name="is_tracked""#));
+}
+
+#[tokio::test]
+async fn clicks_redirect_to_the_original_link() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    let (_, html_body) = publish_and_deliver(&app).await;
+
+    // Act
+    let response = app.get_tracking_link(&tracking_path(&html_body, "c")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(
+        response.headers()["Location"],
+        "https://www.rust-lang.org/learn?a=1&b=2"
+    );
+}
+
+#[tokio::test]
+async fn the_open_pixel_is_an_image() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    let (_, html_body) = publish_and_deliver(&app).await;
+
+    // Act
+    let response = app.get_tracking_link(&tracking_path(&html_body, "o")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "image/gif");
+    assert_eq!(response.headers()["Cache-Control"], "no-store");
+}
+
+#[tokio::test]
+async fn opens_and_clicks_are_shown_on_the_issue_dashboard() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    let (issue_id, html_body) = publish_and_deliver(&app).await;
+
+    // Act
+    app.get_tracking_link(&tracking_path(&html_body, "o")).await;
+    app.get_tracking_link(&tracking_path(&html_body, "c")).await;
+    app.get_tracking_link(&tracking_path(&html_body, "c")).await;
+
+    // Assert
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+    assert!(html_page.contains("<tr><th>Opened</th><td>1</td></tr>"));
+    assert!(html_page.contains("<tr><th>Open rate</th><td>100.0%</td></tr>"));
+    assert!(html_page.contains(
+        "<tr><td>https://www.rust-lang.org/learn?a=1&amp;b=2</td><td>2</td><td>1</td></tr>"
+    ));
+}
+
+#[tokio::test]
+async fn a_click_counts_as_an_open() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    let (issue_id, html_body) = publish_and_deliver(&app).await;
+
+    // Act
+    app.get_tracking_link(&tracking_path(&html_body, "c")).await;
+
+    // Assert
+    let html_page = app.get_issue_delivery_status_html(issue_id).await;
+    assert!(html_page.contains("<tr><th>Opened</th><td>1</td></tr>"));
+}
+
+#[tokio::test]
+async fn nothing_is_recorded_when_tracking_is_disabled() {
+    // Arrange
+    let app = spawn_app().await;
+    let (issue_id, _) = publish_and_deliver(&app).await;
+    // A link from an email sent while tracking was still enabled
+    sqlx::query!(
+        r#"
+        INSERT INTO tracking_tokens (tracking_token, newsletter_issue_id, subscriber_email, url)
+        VALUES ('sent-before-disabling', $1, 'ursula@example.com', 'https://www.rust-lang.org')
+        "#,
+        issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_tracking_link("/t/c/sent-before-disabling").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(response.headers()["Location"], "https://www.rust-lang.org");
+    let n_events = sqlx::query!(r#"SELECT COUNT(*) as "count!" FROM tracking_events"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_events, 0);
+}
+
+#[tokio::test]
+async fn unknown_click_tokens_are_not_found() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+
+    // Act
+    let response = app.get_tracking_link("/t/c/does-not-exist").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn click_tokens_of_links_that_cannot_be_redirected_to_are_not_found() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    let (issue_id, _) = publish_and_deliver(&app).await;
+    sqlx::query!(
+        r#"
+        INSERT INTO tracking_tokens (tracking_token, newsletter_issue_id, subscriber_email, url)
+        VALUES ('broken-link', $1, 'ursula@example.com', E'https://a.com/\nx')
+        "#,
+        issue_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_tracking_link("/t/c/broken-link").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn failed_attempts_do_not_store_tracking_tokens() {
+    // Arrange
+    let app = spawn_app_with_tracking().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    app.post_publish_newsletter(&serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Read more at https://www.rust-lang.org",
+        "html": r#"<p>Read more at <a href="https://www.rust-lang.org">Rust</a></p>"#,
+        "is_tracked": "on",
+        "idempotency_key": Uuid::new_v4().to_string()
+    }))
+    .await;
+
+    // Act - Part 1 - The email provider is temporarily unavailable
+    let mock_guard = when_sending_an_email()
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+    drop(mock_guard);
+
+    // Assert - Part 1
+    let n_tokens = sqlx::query!(r#"SELECT COUNT(*) as "count!" FROM tracking_tokens"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_tokens, 0);
+
+    // Act - Part 2 - The retry goes through
+    sqlx::query!("UPDATE issue_delivery_queue SET execute_after = now()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    when_sending_an_email()
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    app.dispatch_all_pending_emails().await;
+
+    // Assert - Part 2 - Only the tokens of the sent email are kept
+    let n_tokens = sqlx::query!(r#"SELECT COUNT(*) as "count!" FROM tracking_tokens"#)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_tokens, 2);
+    let request = app.email_server.received_requests().await.unwrap().pop().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    let response = app
+        .get_tracking_link(&tracking_path(body["HtmlBody"].as_str().unwrap(), "c"))
+        .await;
+    assert_eq!(response.headers()["Location"], "https://www.rust-lang.org");
+}
@@ -0,0 +1,242 @@
+use crate::helpers::{spawn_app, create_confirmed_subscriber, TestApp};
+
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+
+async fn subscriber_email(app: &TestApp) -> String {
+    sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .email
+}
+
+async fn subscriber_status(app: &TestApp) -> String {
+    sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .status
+}
+
+async fn recorded_events(app: &TestApp) -> Vec<(String, String)> {
+    sqlx::query!("SELECT record_type, email FROM email_events ORDER BY received_at")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| (r.record_type, r.email))
+        .collect()
+}
+
+fn bounce(email: &str, bounce_type: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "Bounce",
+        "Type": bounce_type,
+        "Email": email,
+        "Description": "The server was unable to deliver your message",
+        "BouncedAt": "2023-11-26T16:03:05Z"
+    })
+}
+
+fn spam_complaint(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "SpamComplaint",
+        "Type": "SpamComplaint",
+        "Email": email,
+        "BouncedAt": "2023-11-26T16:03:05Z"
+    })
+}
+
+#[tokio::test]
+async fn requests_without_credentials_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/webhooks/email-events", &app.address))
+        .json(&bounce("ursula@example.com", "HardBounce"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(r#"Basic realm="webhooks""#, response.headers()["WWW-Authenticate"]);
+    assert!(recorded_events(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn requests_with_invalid_credentials_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let username = app.configuration.webhooks.username.clone();
+
+    // Act
+    let response = app
+        .post_email_event(&bounce("ursula@example.com", "HardBounce"), &username, "wrong-password")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(recorded_events(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn a_hard_bounce_stops_future_sends() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act - Part 1 - Bounce
+    let response = app.post_authenticated_email_event(&bounce(&email, "HardBounce")).await;
+
+    // Assert - Part 1
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "bounced");
+    assert_eq!(recorded_events(&app).await, vec![("Bounce".to_string(), email)]);
+
+    // Act - Part 2 - Publish
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    app.test_user.login(&app).await;
+    app.post_publish_newsletter(&serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    }))
+    .await;
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn bounces_match_the_address_whatever_its_case() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act
+    let response = app
+        .post_authenticated_email_event(&bounce(&email.to_uppercase(), "HardBounce"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "bounced");
+}
+
+#[tokio::test]
+async fn signing_up_again_keeps_the_bounced_status() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+    app.post_authenticated_email_event(&bounce(&email, "HardBounce")).await;
+    let body = serde_urlencoded::to_string(serde_json::json!({
+        "name": "le guin",
+        "email": email
+    }))
+    .unwrap();
+
+    // Act
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "bounced");
+}
+
+#[tokio::test]
+async fn a_soft_bounce_is_recorded_without_changing_the_subscription() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act
+    let response = app.post_authenticated_email_event(&bounce(&email, "SoftBounce")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+    assert_eq!(recorded_events(&app).await, vec![("Bounce".to_string(), email)]);
+}
+
+#[tokio::test]
+async fn a_spam_complaint_marks_the_subscriber_as_complained() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act
+    let response = app.post_authenticated_email_event(&spam_complaint(&email)).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "complained");
+    assert_eq!(recorded_events(&app).await, vec![("SpamComplaint".to_string(), email)]);
+}
+
+#[tokio::test]
+async fn a_complaint_is_not_downgraded_by_a_later_bounce() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+    app.post_authenticated_email_event(&spam_complaint(&email)).await;
+
+    // Act
+    app.post_authenticated_email_event(&bounce(&email, "HardBounce")).await;
+
+    // Assert
+    assert_eq!(subscriber_status(&app).await, "complained");
+}
+
+#[tokio::test]
+async fn deliveries_are_recorded() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let email = subscriber_email(&app).await;
+
+    // Act
+    let response = app
+        .post_authenticated_email_event(&serde_json::json!({
+            "RecordType": "Delivery",
+            "Recipient": email,
+            "DeliveredAt": "2023-11-26T16:03:05Z",
+            "Details": "Test delivery webhook details"
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+    assert_eq!(recorded_events(&app).await, vec![("Delivery".to_string(), email)]);
+}
+
+#[tokio::test]
+async fn other_events_are_acknowledged_and_ignored() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_authenticated_email_event(&serde_json::json!({
+            "RecordType": "Open",
+            "Recipient": "ursula@example.com"
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(recorded_events(&app).await.is_empty());
+}